@@ -44,3 +44,121 @@ pub fn is_japanese(c: &char) -> bool {
         || is_jp_misc(c)
         || is_full_alphanum(c)
 }
+
+/// Romaji spellings and their hiragana, Hepburn and Kunrei-shiki alike.
+#[rustfmt::skip]
+const ROMAJI: &[(&str, &str)] = &[
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("sa", "さ"), ("shi", "し"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("sha", "しゃ"), ("shu", "しゅ"), ("sho", "しょ"), ("she", "しぇ"),
+    ("sya", "しゃ"), ("syu", "しゅ"), ("syo", "しょ"),
+    ("za", "ざ"), ("ji", "じ"), ("zi", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("jo", "じょ"), ("je", "じぇ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"),
+    ("zya", "じゃ"), ("zyu", "じゅ"), ("zyo", "じょ"),
+    ("ta", "た"), ("chi", "ち"), ("ti", "ち"), ("tsu", "つ"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("cha", "ちゃ"), ("chu", "ちゅ"), ("cho", "ちょ"), ("che", "ちぇ"),
+    ("tya", "ちゃ"), ("tyu", "ちゅ"), ("tyo", "ちょ"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("dya", "ぢゃ"), ("dyu", "ぢゅ"), ("dyo", "ぢょ"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"),
+    ("ha", "は"), ("hi", "ひ"), ("fu", "ふ"), ("hu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("fa", "ふぁ"), ("fi", "ふぃ"), ("fe", "ふぇ"), ("fo", "ふぉ"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    ("wa", "わ"), ("wo", "を"), ("vu", "ゔ"),
+];
+
+/// Convert romaji to hiragana, e.g. to look up a word heard but not seen.
+/// Returns `None` if part of `romaji` is not romaji.
+pub fn romaji_to_kana(romaji: &str) -> Option<String> {
+    // Long vowels written with macrons or circumflexes.
+    let romaji: String = romaji
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ā' | 'â' => "aa".into(),
+            'ī' | 'î' => "ii".into(),
+            'ū' | 'û' => "uu".into(),
+            'ē' | 'ê' => "ei".into(),
+            'ō' | 'ô' => "ou".into(),
+            c => c.to_string(),
+        })
+        .collect();
+    if !romaji.is_ascii() {
+        return None;
+    }
+
+    let bytes = romaji.as_bytes();
+    let mut kana = String::new();
+    let mut i = 0;
+    'outer: while i < bytes.len() {
+        let c = bytes[i];
+        let next = bytes.get(i + 1).copied();
+        match (c, next) {
+            (b' ' | b'\'', _) => {}
+            (b'-', _) => kana.push('ー'),
+            // Syllabic n, also written m before labials.
+            (b'n', next) if !matches!(next, Some(b'a' | b'i' | b'u' | b'e' | b'o' | b'y')) => {
+                kana.push('ん')
+            }
+            (b'm', Some(b'b' | b'm' | b'p')) => kana.push('ん'),
+            // Geminate consonants.
+            (c, Some(next))
+                if (c == next && !b"aiueon".contains(&c)) || (c, next) == (b't', b'c') =>
+            {
+                kana.push('っ')
+            }
+            _ => {
+                for len in (1..=3).rev() {
+                    let Some(head) = romaji.get(i..i + len) else {
+                        continue;
+                    };
+                    if let Some((_, hiragana)) = ROMAJI.iter().find(|(r, _)| *r == head) {
+                        kana.push_str(hiragana);
+                        i += len;
+                        continue 'outer;
+                    }
+                }
+                return None;
+            }
+        }
+        i += 1;
+    }
+    Some(kana).filter(|kana| !kana.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_romaji_to_kana() {
+        let kana = |romaji| romaji_to_kana(romaji);
+        assert_eq!(kana("konnichiwa").as_deref(), Some("こんにちわ"));
+        assert_eq!(kana("Shinbun").as_deref(), Some("しんぶん"));
+        assert_eq!(kana("shimbun").as_deref(), Some("しんぶん"));
+        assert_eq!(kana("kitte").as_deref(), Some("きって"));
+        assert_eq!(kana("matcha").as_deref(), Some("まっちゃ"));
+        assert_eq!(kana("tōkyō").as_deref(), Some("とうきょう"));
+        assert_eq!(kana("kin'en").as_deref(), Some("きんえん"));
+        assert_eq!(kana("ra-men").as_deref(), Some("らーめん"));
+        assert_eq!(kana("hello"), None);
+        assert_eq!(kana("日本"), None);
+        assert_eq!(kana(" "), None);
+    }
+}
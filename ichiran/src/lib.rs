@@ -110,8 +110,61 @@ fn lisp_string(s: &str) -> String {
     out
 }
 
+/// Escape `%`, `_` and `\` so `s` matches literally in a SQL `LIKE` pattern.
+fn like_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Root entries with a kanji spelling or kana reading starting with `$1`.
+/// Exact matches come first, then common words and shorter spellings.
+const FORM_SEARCH: &str = "select t.seq from {table} t join entry e on e.seq = t.seq \
+    where e.root_p and t.text like $1 group by t.seq \
+    order by bool_or(t.text = $2) desc, min(coalesce(t.common, 1000)), min(length(t.text)), t.seq \
+    limit $3";
+
+/// Root entries with a gloss containing `$1`. Exact matches come first, then
+/// common words and shorter glosses.
+const GLOSS_SEARCH: &str = "select s.seq from gloss g join sense s on s.id = g.sense_id \
+    join entry e on e.seq = s.seq where e.root_p and g.text ilike $1 group by s.seq \
+    order by bool_or(lower(g.text) = lower($2)) desc, \
+    (select min(coalesce(r.common, 1000)) from kana_text r where r.seq = s.seq), \
+    min(length(g.text)), s.seq \
+    limit $3";
+
+/// Lisp printing a list holding the entry with sequence number `seq` as
+/// JSON, or nothing if there is no such entry.
+fn entry_expr(seq: u32) -> String {
+    format!(
+        r#"(ichiran/conn::with-db nil
+  (princ (jsown:to-json
+    (loop for seq in (postmodern:query "select seq from entry where seq = $1" {seq} :column)
+          collect (jsown:new-js
+                    ("seq" seq)
+                    ("kanji" (loop for (text common) in (postmodern:query "select text, common from kanji_text where seq = $1 order by ord" seq)
+                                   collect (jsown:new-js ("text" text) ("common" common))))
+                    ("kana" (loop for (text common nokanji) in (postmodern:query "select text, common, nokanji from kana_text where seq = $1 order by ord" seq)
+                                  collect (jsown:new-js
+                                            ("text" text)
+                                            ("common" common)
+                                            ("nokanji" (if nokanji :true :false))
+                                            ("restr" (postmodern:query "select text from restricted_readings where seq = $1 and reading = $2 order by id" seq text :column)))))
+                    ("sense" (loop for sense-id in (postmodern:query "select id from sense where seq = $1 order by ord" seq :column)
+                                   collect (jsown:new-js
+                                             ("gloss" (postmodern:query "select text from gloss where sense_id = $1 order by ord" sense-id :column))
+                                             ("props" (postmodern:query "select tag, text from sense_prop where sense_id = $1 order by ord" sense-id))))))))))"#
+    )
+}
+
 struct State {
     kanji_cache: LruCache<char, Kanji>,
+    entry_cache: LruCache<u32, Entry>,
     segment_cache: LruCache<String, Segment>,
     jmdict: Option<JmDictData>,
 }
@@ -125,6 +178,7 @@ impl Ichiran {
                 pool_size,
                 state: Mutex::new(State {
                     kanji_cache: LruCache::new(nonzero!(512usize)),
+                    entry_cache: LruCache::new(nonzero!(512usize)),
                     segment_cache: LruCache::new(nonzero!(512usize)),
                     jmdict: None,
                 }),
@@ -287,6 +341,104 @@ impl Ichiran {
         self.kanji(&uniq).await
     }
 
+    /// Look up JMdict entries whose `key` forms match `query`, best matches
+    /// first.
+    #[tracing::instrument(level = Level::DEBUG, skip(self), err)]
+    pub async fn search(
+        &self,
+        query: &str,
+        key: SearchKey,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>, IchiranError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+        let (sql, pattern) = match key {
+            SearchKey::Kanji => (
+                FORM_SEARCH.replace("{table}", "kanji_text"),
+                format!("{}%", like_escape(query)),
+            ),
+            SearchKey::Kana => (
+                FORM_SEARCH.replace("{table}", "kana_text"),
+                format!("{}%", like_escape(query)),
+            ),
+            SearchKey::Gloss => (
+                GLOSS_SEARCH.to_string(),
+                format!("%{}%", like_escape(query)),
+            ),
+        };
+        let expr = format!(
+            r#"(ichiran/conn::with-db nil
+  (princ (jsown:to-json
+    (loop for seq in (postmodern:query {} {} {} {} :column)
+          collect (jsown:new-js
+                    ("seq" seq)
+                    ("kanji" (postmodern:query "select text from kanji_text where seq = $1 order by ord" seq :column))
+                    ("kana" (postmodern:query "select text from kana_text where seq = $1 order by ord" seq :column))
+                    ("gloss" (postmodern:query "select g.text from gloss g join sense s on s.id = g.sense_id where s.seq = $1 order by s.ord, g.ord" seq :column)))))))"#,
+            lisp_string(&sql),
+            lisp_string(&pattern),
+            lisp_string(query),
+            limit
+        );
+        let output = self.shared.evaluate(expr).await?;
+        Ok(serde_json::from_str(&output)?)
+    }
+
+    /// Fetch complete JMdict entries by sequence number. Sequence numbers
+    /// without an entry are left out.
+    #[tracing::instrument(level = Level::DEBUG, skip_all, err)]
+    pub async fn entries(&self, seqs: &[u32]) -> Result<HashMap<u32, Entry>, IchiranError> {
+        let (mut entries, query_seqs): (HashMap<u32, Entry>, Vec<u32>) = {
+            let entry_cache = &mut self.shared.state.lock().unwrap().entry_cache;
+            let entries = seqs
+                .iter()
+                .filter_map(|seq| entry_cache.get(seq).map(|entry| (*seq, entry.clone())))
+                .collect();
+            let query_seqs = seqs
+                .iter()
+                .filter(|seq| !entry_cache.contains(seq))
+                .copied()
+                .unique()
+                .collect();
+            (entries, query_seqs)
+        };
+
+        if query_seqs.is_empty() {
+            return Ok(entries);
+        }
+
+        // Fan out per entry like `kanji`; each takes a handful of queries.
+        let shared = self.shared.clone();
+        let span = tracing::Span::current();
+        let results: Vec<Option<Entry>> = stream::iter(query_seqs)
+            .par_then_unordered(
+                None,
+                enclose! { (span, shared) move |seq: u32| {
+                    enclose! { (span, shared) async move {
+                        let output = shared.evaluate(entry_expr(seq)).await?;
+                        let entry: Vec<Entry> = serde_json::from_str(&output)?;
+                        Ok::<_, IchiranError>(entry.into_iter().next())
+                    }.instrument(span)}
+                }},
+            )
+            .try_collect()
+            .await?;
+
+        let entry_cache = &mut self.shared.state.lock().unwrap().entry_cache;
+        for entry in results.into_iter().flatten() {
+            entry_cache.put(entry.seq(), entry.clone());
+            entries.insert(entry.seq(), entry);
+        }
+        Ok(entries)
+    }
+
+    /// Fetch the complete JMdict entry with sequence number `seq`.
+    pub async fn entry(&self, seq: u32) -> Result<Option<Entry>, IchiranError> {
+        Ok(self.entries(&[seq]).await?.remove(&seq))
+    }
+
     pub async fn jmdict_data(&self) -> Result<JmDictData, IchiranError> {
         {
             let state = self.shared.state.lock().unwrap();
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+/// A complete JMdict entry, as opposed to the senses ichiran picks for a
+/// word in context.
+///
+/// http://www.edrdg.org/jmdict/jmdict_dtd_h.html
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// JMdict sequence number
+    seq: u32,
+    /// Kanji spellings, most common first
+    kanji: Vec<KanjiForm>,
+    /// Kana readings, most common first
+    kana: Vec<KanaForm>,
+    /// Senses in order
+    sense: Vec<Sense>,
+}
+
+impl Entry {
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+    pub fn kanji(&self) -> &[KanjiForm] {
+        &self.kanji
+    }
+    pub fn kana(&self) -> &[KanaForm] {
+        &self.kana
+    }
+    pub fn sense(&self) -> &[Sense] {
+        &self.sense
+    }
+    /// Kanji spellings `kana` is a reading of.
+    pub fn spellings<'a>(&'a self, kana: &'a KanaForm) -> Vec<&'a str> {
+        if kana.nokanji {
+            return vec![];
+        }
+        self.kanji
+            .iter()
+            .map(KanjiForm::text)
+            .filter(|text| kana.restr.is_empty() || kana.restr.iter().any(|restr| restr == text))
+            .collect()
+    }
+}
+
+/// `<k_ele>`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct KanjiForm {
+    text: String,
+    /// Commonness, lower is more common
+    common: Option<u32>,
+}
+
+impl KanjiForm {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn common(&self) -> Option<u32> {
+        self.common
+    }
+}
+
+/// `<r_ele>`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct KanaForm {
+    text: String,
+    /// Commonness, lower is more common
+    common: Option<u32>,
+    /// Not a true reading of the kanji spellings
+    nokanji: bool,
+    /// Kanji spellings this reading is restricted to; empty if it applies
+    /// to all of them
+    restr: Vec<String>,
+}
+
+impl KanaForm {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn common(&self) -> Option<u32> {
+        self.common
+    }
+    pub fn nokanji(&self) -> bool {
+        self.nokanji
+    }
+    pub fn restr(&self) -> &[String] {
+        &self.restr
+    }
+}
+
+/// `<sense>`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct Sense {
+    gloss: Vec<String>,
+    /// Sense properties as (element, text) pairs, e.g. `("pos", "n")`
+    props: Vec<(String, String)>,
+}
+
+impl Sense {
+    pub fn gloss(&self) -> &[String] {
+        &self.gloss
+    }
+    /// Text of all properties of the `<tag>` element, in order.
+    pub fn props<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.props
+            .iter()
+            .filter(move |(t, _)| t == tag)
+            .map(|(_, text)| text.as_str())
+    }
+    /// Parts of speech
+    pub fn pos(&self) -> Vec<&str> {
+        self.props("pos").collect()
+    }
+    /// Miscellaneous tags, such as `uk` for words usually written in kana
+    pub fn misc(&self) -> Vec<&str> {
+        self.props("misc").collect()
+    }
+    /// Fields of application
+    pub fn field(&self) -> Vec<&str> {
+        self.props("field").collect()
+    }
+    /// Dialects
+    pub fn dial(&self) -> Vec<&str> {
+        self.props("dial").collect()
+    }
+    /// Other information about the sense
+    pub fn info(&self) -> Vec<&str> {
+        self.props("s_inf").collect()
+    }
+    /// Kanji spellings the sense is restricted to
+    pub fn stagk(&self) -> Vec<&str> {
+        self.props("stagk").collect()
+    }
+    /// Readings the sense is restricted to
+    pub fn stagr(&self) -> Vec<&str> {
+        self.props("stagr").collect()
+    }
+    /// Cross-references to related entries
+    pub fn xref(&self) -> Vec<&str> {
+        self.props("xref").collect()
+    }
+    /// Antonyms
+    pub fn ant(&self) -> Vec<&str> {
+        self.props("ant").collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::SearchKey, tests::fixture};
+
+    #[test]
+    fn test_deserialize() {
+        let entry: Entry = serde_json::from_str(
+            r#"{"seq":1000000,
+                "kanji":[{"text":"上手","common":12},{"text":"上衆","common":null}],
+                "kana":[{"text":"じょうず","common":12,"nokanji":false,"restr":["上手"]},
+                        {"text":"じょうじゅ","common":null,"nokanji":false,"restr":[]}],
+                "sense":[{"gloss":["skillful","skilled"],
+                          "props":[["pos","adj-na"],["pos","n"],["ant","下手"]]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.spellings(&entry.kana()[0]), ["上手"]);
+        assert_eq!(entry.spellings(&entry.kana()[1]), ["上手", "上衆"]);
+        let sense = &entry.sense()[0];
+        assert_eq!(sense.pos(), ["adj-na", "n"]);
+        assert_eq!(sense.ant(), ["下手"]);
+        assert!(sense.xref().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_entry() {
+        let ichiran = fixture::ichiran().await;
+        let taberu = ichiran.search("食べる", SearchKey::Kanji, 1).await.unwrap();
+        let entry = ichiran.entry(taberu[0].seq()).await.unwrap().unwrap();
+        assert_eq!(entry.kanji()[0].text(), "食べる");
+        assert!(entry.kana().iter().any(|kana| kana.text() == "たべる"));
+        assert!(entry.sense()[0].pos().contains(&"v1"));
+
+        // Sense properties such as antonyms come through.
+        let jouzu = ichiran.search("上手", SearchKey::Kanji, 10).await.unwrap();
+        let jouzu = jouzu
+            .iter()
+            .find(|result| result.kana().iter().any(|kana| kana == "じょうず"))
+            .unwrap();
+        let entry = ichiran.entry(jouzu.seq()).await.unwrap().unwrap();
+        assert!(entry
+            .sense()
+            .iter()
+            .any(|sense| sense.ant().iter().any(|ant| ant.starts_with("下手"))));
+
+        assert_eq!(ichiran.entry(0).await.unwrap(), None);
+    }
+}
@@ -1,7 +1,11 @@
+mod entry;
 mod jmdict_data;
 mod kanji;
 mod romanize;
+mod search;
 
+pub use entry::*;
 pub use jmdict_data::*;
 pub use kanji::*;
 pub use romanize::*;
+pub use search::*;
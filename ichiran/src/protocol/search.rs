@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// Which written forms of JMdict entries a search is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchKey {
+    /// Kanji spellings, by prefix.
+    Kanji,
+    /// Kana readings, by prefix.
+    Kana,
+    /// English glosses, by substring.
+    Gloss,
+}
+
+/// A JMdict entry matched by [`Ichiran::search`](crate::Ichiran::search).
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct SearchResult {
+    /// JMdict sequence number
+    seq: u32,
+    /// Kanji spellings, most common first
+    kanji: Vec<String>,
+    /// Kana readings, most common first
+    kana: Vec<String>,
+    /// Glosses of every sense in order
+    gloss: Vec<String>,
+}
+
+impl SearchResult {
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+    pub fn kanji(&self) -> &[String] {
+        &self.kanji
+    }
+    pub fn kana(&self) -> &[String] {
+        &self.kana
+    }
+    pub fn gloss(&self) -> &[String] {
+        &self.gloss
+    }
+    /// The form the entry is usually written in.
+    pub fn headword(&self) -> &str {
+        self.kanji
+            .first()
+            .or_else(|| self.kana.first())
+            .map_or("", String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fixture;
+
+    #[test]
+    fn test_deserialize() {
+        let results: Vec<SearchResult> = serde_json::from_str(
+            r#"[{"seq":1001390,"kanji":[],"kana":["おいしい","おいし"],"gloss":["delicious"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(results[0].headword(), "おいしい");
+        assert_eq!(results[0].kana(), ["おいしい", "おいし"]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_search() {
+        let ichiran = fixture::ichiran().await;
+        let taberu = ichiran.search("食べる", SearchKey::Kanji, 5).await.unwrap();
+        assert_eq!(taberu[0].headword(), "食べる");
+        assert!(taberu[0].kana().iter().any(|kana| kana == "たべる"));
+
+        let eat = ichiran
+            .search("to eat", SearchKey::Gloss, 20)
+            .await
+            .unwrap();
+        assert!(eat.iter().any(|result| result.seq() == taberu[0].seq()));
+
+        let none = ichiran
+            .search("ゐゑゐゑ", SearchKey::Kana, 5)
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+    }
+}
@@ -0,0 +1,315 @@
+//! Pitch accent dictionary.
+//!
+//! ichiran does not know about pitch accent, so accents are looked up in a
+//! separate dictionary keyed by (text, reading). Dictionaries in Kanjium's
+//! `accents.txt` format are imported into a local store, which is kept in the
+//! same format: one `text\treading\taccents` line per entry, where the reading
+//! may be left empty for words written in kana and the accents are the
+//! comma-separated positions of the downstep, 0 for none.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("no accent store is configured")]
+    NoStore,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct AccentSettings {
+    /// Local store imported dictionaries are written to. Empty disables
+    /// pitch accent.
+    pub path: String,
+    /// Mark the pitch pattern over furigana as well as in term views.
+    pub ruby: bool,
+}
+
+impl Default for AccentSettings {
+    fn default() -> Self {
+        Self {
+            path: "pitch_accent.tsv".into(),
+            ruby: false,
+        }
+    }
+}
+
+type Key = (String, String);
+/// Accents by text, then reading, so lookups need not allocate.
+type Entries = HashMap<String, HashMap<String, Arc<[u8]>>>;
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Import {
+    pub entries: usize,
+    /// Lines that could not be read.
+    pub skipped: usize,
+}
+
+#[derive(Default)]
+pub struct AccentStore {
+    path: Option<PathBuf>,
+    entries: RwLock<Entries>,
+}
+
+impl fmt::Debug for AccentStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccentStore")
+            .field("path", &self.path)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl AccentStore {
+    /// Load the store described by `settings`. A missing file is not an
+    /// error; malformed lines are skipped.
+    pub fn open(settings: &AccentSettings) -> Self {
+        if settings.path.is_empty() {
+            return Self::default();
+        }
+        let path = PathBuf::from(&settings.path);
+        let mut entries = Entries::new();
+        match std::fs::File::open(&path) {
+            Ok(file) => {
+                let import = read(BufReader::new(file), &mut entries);
+                if import.skipped > 0 {
+                    tracing::warn!(skipped = import.skipped, ?path, "skipped accent entries");
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => tracing::error!(%err, ?path, "failed to open accent store"),
+        }
+        Self {
+            path: Some(path),
+            entries: RwLock::new(entries),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .read()
+            .unwrap()
+            .values()
+            .map(HashMap::len)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Downstep positions of `text` read as `reading`, most common first.
+    pub fn get(&self, text: &str, reading: &str) -> Option<Arc<[u8]>> {
+        let entries = self.entries.read().unwrap();
+        entries.get(text)?.get(reading).cloned()
+    }
+
+    /// Most common downstep position of `text` read as `reading`.
+    pub fn first(&self, text: &str, reading: &str) -> Option<u8> {
+        let entries = self.entries.read().unwrap();
+        entries.get(text)?.get(reading)?.first().copied()
+    }
+
+    /// Add the entries of a Kanjium-format dictionary, replacing those with
+    /// the same key, and write the store out.
+    pub fn import(&self, source: &Path) -> Result<Import, Error> {
+        let path = self.path.as_ref().ok_or(Error::NoStore)?;
+        let file = std::fs::File::open(source)?;
+        let mut entries = self.entries.read().unwrap().clone();
+        let import = read(BufReader::new(file), &mut entries);
+
+        let mut lines: Vec<String> = entries
+            .iter()
+            .flat_map(|(text, readings)| {
+                readings.iter().map(move |(reading, accents)| {
+                    let accents: Vec<String> = accents.iter().map(u8::to_string).collect();
+                    format!("{}\t{}\t{}\n", text, reading, accents.join(","))
+                })
+            })
+            .collect();
+        lines.sort();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, lines.concat())?;
+
+        *self.entries.write().unwrap() = entries;
+        Ok(import)
+    }
+}
+
+fn read(reader: impl BufRead, entries: &mut Entries) -> Import {
+    let mut import = Import {
+        entries: 0,
+        skipped: 0,
+    };
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            import.skipped += 1;
+            continue;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(&line) {
+            Some(((text, reading), accents)) => {
+                entries
+                    .entry(text)
+                    .or_default()
+                    .insert(reading, accents.into());
+                import.entries += 1;
+            }
+            None => import.skipped += 1,
+        }
+    }
+    import
+}
+
+fn parse_line(line: &str) -> Option<(Key, Vec<u8>)> {
+    let mut fields = line.trim_end_matches('\r').split('\t');
+    let text = fields.next().filter(|text| !text.is_empty())?;
+    let reading = fields.next()?;
+    let reading = if reading.is_empty() { text } else { reading };
+    let accents = parse_accents(fields.next()?)?;
+    Some(((text.to_string(), reading.to_string()), accents))
+}
+
+/// Positions such as `0,2`. Some entries tag positions with the part of
+/// speech they apply to, as in `(名)0,(副)1`; the tags are dropped, as are
+/// positions repeated for another part of speech.
+fn parse_accents(field: &str) -> Option<Vec<u8>> {
+    let mut accents: Vec<u8> = Vec::new();
+    for accent in field.split(',') {
+        let accent = match accent.rfind(')') {
+            Some(idx) => &accent[idx + 1..],
+            None => accent,
+        };
+        if let Ok(accent) = accent.trim().parse() {
+            if !accents.contains(&accent) {
+                accents.push(accent);
+            }
+        }
+    }
+    (!accents.is_empty()).then_some(accents)
+}
+
+/// Split kana into morae. Small kana other than っ belong to the mora before
+/// them.
+pub fn morae(kana: &str) -> Vec<&str> {
+    let mut morae: Vec<&str> = Vec::new();
+    let mut start = 0;
+    for (idx, c) in kana.char_indices() {
+        if idx > start && !is_small(c) {
+            morae.push(&kana[start..idx]);
+            start = idx;
+        }
+    }
+    if start < kana.len() {
+        morae.push(&kana[start..]);
+    }
+    morae
+}
+
+fn is_small(c: char) -> bool {
+    "ぁぃぅぇぉゃゅょゎァィゥェォャュョヮ".contains(c)
+}
+
+/// Whether mora `i` is high in Tokyo pitch accent: the first mora is low
+/// unless the accent is on it, and pitch drops after the accented mora.
+/// Accent 0 never drops.
+pub fn is_high(accent: usize, i: usize) -> bool {
+    if accent == 1 {
+        i == 0
+    } else {
+        i > 0 && (accent == 0 || i < accent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pitch_follows_the_accent() {
+        let pattern = |accent| (0..4).map(|i| is_high(accent, i)).collect::<Vec<_>>();
+        // Heiban, atamadaka, nakadaka and odaka.
+        assert_eq!(pattern(0), [false, true, true, true]);
+        assert_eq!(pattern(1), [true, false, false, false]);
+        assert_eq!(pattern(3), [false, true, true, false]);
+        assert_eq!(pattern(4), [false, true, true, true]);
+    }
+
+    #[test]
+    fn small_kana_join_the_mora_before() {
+        assert_eq!(morae("きょうと"), ["きょ", "う", "と"]);
+        assert_eq!(morae("がっこう"), ["が", "っ", "こ", "う"]);
+        assert_eq!(morae("ティーシャツ"), ["ティ", "ー", "シャ", "ツ"]);
+        assert!(morae("").is_empty());
+    }
+
+    #[test]
+    fn reads_kanjium_lines() {
+        assert_eq!(
+            parse_line("日本\tにほん\t2"),
+            Some((("日本".into(), "にほん".into()), vec![2]))
+        );
+        assert_eq!(
+            parse_line("ああ\t\t1,0\r"),
+            Some((("ああ".into(), "ああ".into()), vec![1, 0]))
+        );
+        assert_eq!(
+            parse_line("一応\tいちおう\t(名)0,(副)0"),
+            Some((("一応".into(), "いちおう".into()), vec![0]))
+        );
+        assert_eq!(
+            parse_line("上手\tじょうず\t(名)3,(形動)3,(名)0"),
+            Some((("上手".into(), "じょうず".into()), vec![3, 0]))
+        );
+        assert_eq!(parse_line("壊れた\tこわれた"), None);
+        assert_eq!(parse_line("壊れた\tこわれた\t?"), None);
+    }
+
+    #[test]
+    fn imports_into_the_store() {
+        let dir = std::env::temp_dir().join(format!("niinii-accent-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("accents.txt");
+        std::fs::write(&source, "箸\tはし\t1\n橋\tはし\t2\n\n端\n").unwrap();
+        let settings = AccentSettings {
+            path: dir.join("store.tsv").to_string_lossy().into(),
+            ..Default::default()
+        };
+
+        let store = AccentStore::open(&settings);
+        assert!(store.is_empty());
+        let import = store.import(&source).unwrap();
+        assert_eq!(
+            import,
+            Import {
+                entries: 2,
+                skipped: 1
+            }
+        );
+        assert_eq!(store.get("橋", "はし").as_deref(), Some(&[2][..]));
+        assert_eq!(store.first("箸", "はし"), Some(1));
+        assert_eq!(store.get("橋", "きょう"), None);
+
+        let reopened = AccentStore::open(&settings);
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.get("箸", "はし").as_deref(), Some(&[1][..]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,12 +4,15 @@ use crate::{
     renderer::context::{Context, ContextFlags},
     settings::Settings,
     support::{docking::UiDocking, regex::CachedRegex},
-    tts::{self, TtsEngine},
+    tts::{self, TtsEngine, Utterance},
     view::{
         gloss::{GlossEvent, GlossInputAction, GlossView},
         inject::InjectView,
         mixins::{ellipses, stroke_text_with_highlight},
+        pronunciation::PronunciationView,
+        script::ScriptView,
         settings::SettingsView,
+        speech::SpeechView,
         style_editor::StyleEditor,
         translator::TranslatorWindow,
     },
@@ -36,16 +39,20 @@ pub struct App {
     translator_window: TranslatorWindow,
     settings_view: SettingsView,
     inject_view: InjectView,
+    script_view: ScriptView,
+    pronunciation_view: PronunciationView,
+    speech_view: SpeechView,
     style_editor: StyleEditor,
 
     auto_tts_regex: CachedRegex,
 }
 
 impl App {
-    pub async fn new(settings: Settings) -> Self {
+    pub async fn new(mut settings: Settings) -> Self {
         let tts = TtsEngine::new(&settings);
         let gloss = GlossView::new(&settings).await;
-        let translator_window = TranslatorWindow::new(&settings);
+        let translator_window = TranslatorWindow::new(&mut settings);
+        let script_view = ScriptView::new(&settings);
         App {
             show_metrics_window: false,
             no_inputs: false,
@@ -56,6 +63,9 @@ impl App {
             translator_window,
             settings_view: SettingsView::new(),
             inject_view: InjectView::new(),
+            script_view,
+            pronunciation_view: PronunciationView::new(),
+            speech_view: SpeechView::new(),
             style_editor: StyleEditor::new(),
             auto_tts_regex: CachedRegex::default(),
         }
@@ -71,8 +81,10 @@ impl App {
         // text and don't need the parsed AST, so kick them off here in
         // parallel with the still-running parse.
         if self.settings.auto_translate {
+            // The parse is still running, so glossary terms are matched
+            // against the raw text.
             self.translator_window
-                .translate(&self.settings, processed.clone());
+                .translate(&self.settings, processed.clone(), None);
         } else {
             self.translator_window.clear_current();
         }
@@ -91,10 +103,30 @@ impl App {
         }
     }
 
+    /// Gloss a line from the script reader and prefetch the translations of
+    /// the lines after it.
+    fn show_script_line(&mut self, ui: &Ui, line: &str) {
+        self.request_gloss(ui, line);
+        let Some(n) = self.settings.chat.prefetch_lines else {
+            return;
+        };
+        // Without auto-translate the current line is only translated on
+        // request, so it is worth having ready too.
+        let include_current = !self.settings.auto_translate;
+        let upcoming = self
+            .script_view
+            .upcoming(include_current, n)
+            .iter()
+            .filter_map(|line| self.gloss.preprocess(line, &self.settings).ok().flatten())
+            .collect();
+        self.translator_window.prefetch(&self.settings, upcoming);
+    }
+
     fn request_tts(&mut self, ui: &Ui, text: &str) {
         let span = tracing::debug_span!("tts");
         let _enter = span.enter();
-        if let Err(err) = self.tts.request_tts(text) {
+        let utterance = Utterance::line(&self.settings.tts, text);
+        if let Err(err) = self.tts.request_tts(utterance, self.settings.tts.playback) {
             self.error(ui, err.into());
         }
     }
@@ -110,8 +142,12 @@ impl App {
             match event {
                 GlossEvent::ClipboardReceived(text) => self.request_gloss(ui, &text),
                 GlossEvent::Failed(err) => self.error(ui, err.into()),
+                GlossEvent::Speak(kana) => self.request_tts(ui, &kana),
             }
         }
+        while let Some(err) = self.tts.poll_error() {
+            self.error(ui, err.into());
+        }
     }
 
     fn show_menu(&mut self, ctx: &mut Context, ui: &Ui) {
@@ -124,6 +160,10 @@ impl App {
                 ui.separator();
                 self.style_editor.show_menu_item(ui);
                 self.translator_window.show_menu_item(ui);
+                self.script_view.show_menu_item(ui);
+                if cfg!(feature = "tts") {
+                    self.speech_view.show_menu_item(ui);
+                }
                 ui.separator();
                 self.settings_view.show_menu_item(ui);
                 ui.separator();
@@ -148,14 +188,43 @@ impl App {
             if ui.menu_item("Translate") {
                 if let Some(gloss) = self.gloss.ast() {
                     let text = gloss.original_text.clone();
-                    self.translator_window.translate(&self.settings, text);
+                    self.translator_window
+                        .translate(&self.settings, text, Some(gloss));
+                }
+            }
+            if ui
+                .menu_item_config("Regenerate")
+                .enabled(self.translator_window.can_regenerate())
+                .build()
+            {
+                self.translator_window
+                    .regenerate(&self.settings, self.gloss.ast());
+            }
+            if ui
+                .menu_item_config("Compare")
+                .enabled(self.translator_window.can_compare(&self.settings))
+                .build()
+            {
+                if let Some(gloss) = self.gloss.ast() {
+                    let text = gloss.original_text.clone();
+                    self.translator_window
+                        .compare(&self.settings, text, Some(gloss));
                 }
             }
-            if cfg!(feature = "voicevox") && ui.menu_item("Speak") {
+            if cfg!(feature = "tts") && ui.menu_item("Speak") {
                 if let Some(gloss) = self.gloss.ast() {
                     self.request_tts(ui, &gloss.original_text.clone());
                 }
             }
+            if cfg!(feature = "tts") && ui.menu_item("Pronunciation") {
+                let text = self
+                    .gloss
+                    .ast()
+                    .map(|gloss| gloss.original_text.clone())
+                    .unwrap_or_default();
+                self.pronunciation_view
+                    .load(&self.settings, &self.tts, &text);
+            }
             drop(disable_state);
         }
     }
@@ -202,6 +271,11 @@ impl App {
             ui.dockspace_over_viewport();
         };
 
+        if ctx.flags().contains(ContextFlags::SUPPORTS_ATLAS_UPDATE) {
+            self.translator_window
+                .add_unknown_glyphs(ctx, &self.settings);
+        }
+
         let no_inputs = self.no_inputs;
         let mut toggle_hovered = false;
         if no_inputs {
@@ -234,12 +308,17 @@ impl App {
 
             if self.settings().show_manual_input {
                 let action = self.gloss.show_input(ui);
-                self.translator_window.draw_current_usage(ui);
+                self.translator_window
+                    .draw_current_usage(ui, &self.settings);
                 if let Some(action) = action {
                     match action {
                         GlossInputAction::Gloss(text) => self.request_gloss(ui, &text),
                         GlossInputAction::Translate(text) => {
-                            self.translator_window.translate(&self.settings, text);
+                            self.translator_window.translate(
+                                &self.settings,
+                                text,
+                                self.gloss.ast(),
+                            );
                         }
                     }
                 }
@@ -261,10 +340,16 @@ impl App {
             }
         });
 
-        self.settings_view.ui(ctx, ui, &mut self.settings);
+        self.settings_view
+            .ui(ctx, ui, &mut self.settings, &self.tts);
         self.inject_view.ui(ui, &mut self.settings);
         self.style_editor.ui(ui, &mut self.settings);
+        self.pronunciation_view.ui(ui, &self.settings, &self.tts);
+        self.speech_view.ui(ui, &mut self.settings, &self.tts);
         self.translator_window.ui(ui, &mut self.settings);
+        if let Some(line) = self.script_view.ui(ui, &mut self.settings) {
+            self.show_script_line(ui, &line);
+        }
         if self.show_metrics_window {
             ui.show_metrics_window(&mut self.show_metrics_window);
         }
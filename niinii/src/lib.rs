@@ -1,3 +1,4 @@
+pub mod accent;
 pub mod app;
 #[cfg(feature = "hook")]
 pub mod hook;
@@ -7,4 +8,5 @@ pub mod settings;
 pub mod support;
 pub mod translator;
 pub mod tts;
+pub mod vocab;
 pub mod view;
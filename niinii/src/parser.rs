@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use ichiran::prelude::*;
 use thiserror::Error;
@@ -92,4 +95,36 @@ impl Parser {
     pub async fn parse_kanji(&self, text: &str) -> Result<HashMap<char, Kanji>, Error> {
         Ok(self.shared.ichiran.kanji_from_str(text).await?)
     }
+
+    /// Fetch the complete dictionary entry with sequence number `seq`.
+    pub async fn entry(&self, seq: u32) -> Result<Option<Entry>, Error> {
+        Ok(self.shared.ichiran.entry(seq).await?)
+    }
+
+    /// Part-of-speech descriptions and the like, for drawing entries.
+    pub async fn jmdict_data(&self) -> Result<JmDictData, Error> {
+        Ok(self.shared.ichiran.jmdict_data().await?)
+    }
+
+    /// Look up dictionary entries by each of `queries` at once. Results are
+    /// in query order, each entry listed only for the first query it matched.
+    pub async fn search(
+        &self,
+        queries: &[(SearchKey, String)],
+        limit: u32,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let ichiran = &self.shared.ichiran;
+        let found = futures::future::try_join_all(
+            queries
+                .iter()
+                .map(|(key, query)| ichiran.search(query, *key, limit)),
+        )
+        .await?;
+        let mut seen = HashSet::new();
+        Ok(found
+            .into_iter()
+            .flatten()
+            .filter(|result| seen.insert(result.seq()))
+            .collect())
+    }
 }
@@ -24,6 +24,53 @@ pub enum TextStyle {
     Body,
 }
 
+/// Regional variant of the CJK font. Han characters are drawn differently in
+/// Japanese, Chinese and Korean, so the face follows the translation target.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum FontFace {
+    #[default]
+    Japanese,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Korean,
+}
+
+impl FontFace {
+    /// Face for a target language given as free text, e.g. "Korean" or
+    /// "Traditional Chinese". Anything unrecognized keeps the Japanese face.
+    pub fn for_language(language: &str) -> Self {
+        let language = language.to_lowercase();
+        if language.contains("korean") {
+            FontFace::Korean
+        } else if ["traditional", "cantonese", "taiwan", "hong kong"]
+            .iter()
+            .any(|x| language.contains(x))
+        {
+            FontFace::TraditionalChinese
+        } else if language.contains("chinese") || language.contains("mandarin") {
+            FontFace::SimplifiedChinese
+        } else {
+            FontFace::Japanese
+        }
+    }
+    fn font_data(self) -> Vec<u8> {
+        const SARASA_MONO_J_REGULAR: &[u8] =
+            include_bytes!("../../res/sarasa-mono-j-regular.ttf.gz");
+        const SARASA_MONO_SC_REGULAR: &[u8] =
+            include_bytes!("../../res/sarasa-mono-sc-regular.ttf.gz");
+        const SARASA_MONO_TC_REGULAR: &[u8] =
+            include_bytes!("../../res/sarasa-mono-tc-regular.ttf.gz");
+        const SARASA_MONO_K_REGULAR: &[u8] =
+            include_bytes!("../../res/sarasa-mono-k-regular.ttf.gz");
+        decompress_gzip_font(match self {
+            FontFace::Japanese => SARASA_MONO_J_REGULAR,
+            FontFace::SimplifiedChinese => SARASA_MONO_SC_REGULAR,
+            FontFace::TraditionalChinese => SARASA_MONO_TC_REGULAR,
+            FontFace::Korean => SARASA_MONO_K_REGULAR,
+        })
+    }
+}
+
 bitflags! {
 pub struct ContextFlags: u32 {
     /// Whether the renderer supports updating font atlases on the fly.
@@ -35,6 +82,7 @@ pub struct ContextFlags: u32 {
 
 pub struct Context {
     font_data: Vec<u8>,
+    font_face: FontFace,
     fonts: HashMap<TextStyle, FontId>,
 
     added_font_glyphs: HashSet<u32>,
@@ -52,12 +100,12 @@ impl Context {
         let mut font_glyph_ranges = vec![0; FONT_GLYPH_RANGE_BUFFER_SZ];
         font_glyph_ranges[0..FONT_BASIC_RANGES_UTF8.len()].copy_from_slice(FONT_BASIC_RANGES_UTF8);
 
-        const SARASA_MONO_J_REGULAR: &[u8] =
-            include_bytes!("../../res/sarasa-mono-j-regular.ttf.gz");
-        let font_data = decompress_gzip_font(SARASA_MONO_J_REGULAR);
+        let font_face = FontFace::default();
+        let font_data = font_face.font_data();
 
         let mut ctx = Context {
             font_data,
+            font_face,
             fonts: HashMap::new(),
 
             added_font_glyphs: HashSet::new(),
@@ -76,6 +124,14 @@ impl Context {
     pub fn font_atlas_dirty(&self) -> bool {
         self.font_atlas_dirty
     }
+    /// Switch the CJK font to `face`, rebuilding the atlas if it changed.
+    pub fn set_font_face(&mut self, face: FontFace) {
+        if self.font_face != face {
+            self.font_data = face.font_data();
+            self.font_face = face;
+            self.font_atlas_dirty = true;
+        }
+    }
     fn add_default_glyphs(&mut self) {
         let mut code: u32 = 0x4e00;
         for off in FONT_JA_ACC_OFF_4E00_UTF8 {
@@ -85,6 +141,11 @@ impl Context {
     }
     fn add_font_glyph(&mut self, code: u32) {
         debug_assert!(!self.has_font_glyph(code));
+        // Keep room for the pair and the terminating zero.
+        if self.font_glyph_range_size + 2 >= self.font_glyph_ranges.len() {
+            let len = self.font_glyph_ranges.len() * 2;
+            self.font_glyph_ranges.resize(len, 0);
+        }
         self.added_font_glyphs.insert(code);
         self.font_glyph_ranges[self.font_glyph_range_size] = code;
         self.font_glyph_ranges[self.font_glyph_range_size + 1] = code;
@@ -166,6 +227,16 @@ impl Context {
             }
         }
     }
+    /// Add glyphs for any characters of `text` outside the basic ranges, so
+    /// translations into Chinese or Korean render.
+    pub fn add_unknown_glyphs_from_text(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            let code = c as u32;
+            if !is_basic_glyph(code) && !self.has_font_glyph(code) {
+                self.add_font_glyph(code);
+            }
+        }
+    }
     pub fn add_unknown_glyphs_from_root(&mut self, root: &Root) {
         struct RootVisitor<'a>(&'a mut Context);
         impl RootVisitor<'_> {
@@ -223,3 +294,9 @@ impl Context {
         root_visitor.visit_root(root);
     }
 }
+
+fn is_basic_glyph(code: u32) -> bool {
+    FONT_BASIC_RANGES_UTF8
+        .chunks_exact(2)
+        .any(|range| (range[0]..=range[1]).contains(&code))
+}
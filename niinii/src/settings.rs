@@ -2,6 +2,18 @@ use num_derive::FromPrimitive;
 use serde::{Deserialize, Serialize};
 use strum_macros::{EnumIter, IntoStaticStr};
 
+use crate::accent::AccentSettings;
+use crate::translator::{
+    budget::Budget,
+    compare::CompareLane,
+    memory::MemorySettings,
+    pricing::Pricing,
+    summary::SummarySettings,
+    template::{split_speaker, PromptPreset},
+};
+use crate::tts::{export::ExportFormat, protocol::Prosody, PlaybackMode};
+use crate::vocab::VocabSettings;
+
 #[derive(
     Debug,
     Clone,
@@ -29,15 +41,115 @@ pub enum RubyTextType {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter)]
 pub enum TranslatorType {
+    /// OpenAI Chat Completions, or any compatible server.
     Chat,
+    /// Anthropic Messages API.
+    #[strum(serialize = "Messages (Anthropic)")]
+    Messages,
+    /// OpenAI Responses API. Context is kept server-side and chained.
+    #[strum(serialize = "Responses (OpenAI)")]
+    Responses,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter)]
+pub enum TtsBackendType {
+    /// VOICEVOX core, linked in-process. Windows only.
+    #[strum(serialize = "VOICEVOX core")]
+    VoicevoxCore,
+    /// VOICEVOX engine HTTP API, also served by AivisSpeech.
+    #[strum(serialize = "VOICEVOX engine")]
+    VoicevoxEngine,
+    /// OpenAI-compatible `/v1/audio/speech`.
+    #[strum(serialize = "OpenAI speech")]
+    OpenAiSpeech,
+    /// A local program that reads the line on stdin and writes audio to
+    /// stdout.
+    Command,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TtsSettings {
+    pub backend: TtsBackendType,
+    /// Base URL of a VOICEVOX-compatible engine.
+    pub engine_endpoint: String,
+    /// Speech endpoint. The chat endpoint is used if empty.
+    pub speech_endpoint: String,
+    pub speech_model: openai::ModelId,
+    pub speech_voice: String,
+    /// Delivery instructions, for models that accept them.
+    pub speech_instructions: String,
+    /// Run by the platform shell.
+    pub command: String,
+    /// VOICEVOX style ID lines are spoken with.
+    pub style: u32,
+    /// Styles for particular speakers, matched against the name split off
+    /// the line.
+    pub voices: Vec<Voice>,
+    pub prosody: Prosody,
+    pub playback: PlaybackMode,
+    /// Size of the synthesized audio cache, in MiB.
+    pub cache_size: usize,
+    pub export_dir: String,
+    pub export_format: ExportFormat,
+    /// Used to encode exports that are not WAV already.
+    pub ffmpeg_path: String,
+}
+impl TtsSettings {
+    /// Style to speak `text` with.
+    pub fn style_for(&self, text: &str) -> u32 {
+        let (speaker, _) = split_speaker(text);
+        speaker
+            .and_then(|speaker| self.voices.iter().find(|voice| voice.speaker == speaker))
+            .map_or(self.style, |voice| voice.style)
+    }
+}
+impl Default for TtsSettings {
+    fn default() -> Self {
+        Self {
+            backend: if cfg!(all(windows, feature = "voicevox")) {
+                TtsBackendType::VoicevoxCore
+            } else {
+                TtsBackendType::VoicevoxEngine
+            },
+            engine_endpoint: "http://127.0.0.1:50021".into(),
+            speech_endpoint: String::new(),
+            speech_model: openai::ModelId("gpt-4o-mini-tts".into()),
+            speech_voice: "alloy".into(),
+            speech_instructions: String::new(),
+            command: String::new(),
+            style: 11,
+            voices: Vec::new(),
+            prosody: Prosody::default(),
+            playback: PlaybackMode::default(),
+            cache_size: 64,
+            export_dir: "audio".into(),
+            export_format: ExportFormat::default(),
+            ffmpeg_path: "ffmpeg".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Voice {
+    pub speaker: String,
+    pub style: u32,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct ChatSettings {
     pub api_endpoint: String,
+    pub anthropic_api_endpoint: String,
     pub model: openai::ModelId,
+    /// Prompt template used when no preset is selected.
     pub system_prompt: String,
+    pub prompt_presets: Vec<PromptPreset>,
+    /// Name of the selected preset.
+    pub prompt_preset: Option<String>,
+    pub game_title: String,
+    pub target_language: String,
     pub max_context_tokens: [u32; 2],
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
@@ -48,15 +160,55 @@ pub struct ChatSettings {
     pub stream: bool,
     pub service_tier: Option<openai::ServiceTier>,
     pub reasoning_effort: Option<openai::ReasoningEffort>,
+    /// Ask the Responses API for a summary of the model's reasoning.
+    pub reasoning_summary: Option<openai::responses::ReasoningSummary>,
     pub verbosity: Option<openai::Verbosity>,
+    pub pricing: Pricing,
+    pub budget: Budget,
+    pub memory: MemorySettings,
+    pub summary: SummarySettings,
+    /// TOML glossary of the current project.
+    pub glossary_path: String,
+    pub sessions_dir: String,
+    /// Session restored on startup and autosaved to.
+    pub session: Option<String>,
+    /// Models a line is sent to by "Compare".
+    pub compare_lanes: Vec<CompareLane>,
+    /// Number of upcoming script lines to translate ahead of time.
+    pub prefetch_lines: Option<usize>,
+}
+impl ChatSettings {
+    /// Template of the selected preset, or the system prompt.
+    pub fn prompt_template(&self) -> &str {
+        self.prompt_preset
+            .as_ref()
+            .and_then(|name| self.prompt_presets.iter().find(|x| &x.name == name))
+            .map_or(&self.system_prompt, |preset| &preset.template)
+    }
+    pub fn prompt_template_mut(&mut self) -> &mut String {
+        let preset = self
+            .prompt_preset
+            .as_ref()
+            .and_then(|name| self.prompt_presets.iter().position(|x| &x.name == name));
+        match preset {
+            Some(idx) => &mut self.prompt_presets[idx].template,
+            None => &mut self.system_prompt,
+        }
+    }
 }
 impl Default for ChatSettings {
     fn default() -> Self {
         Self {
             api_endpoint: "https://api.openai.com".into(),
+            anthropic_api_endpoint: "https://api.anthropic.com".into(),
             model: Default::default(),
-            system_prompt: "You will translate the following visual novel script into English."
-                .into(),
+            system_prompt:
+                "You will translate the following visual novel script into {{target_language}}."
+                    .into(),
+            prompt_presets: PromptPreset::defaults(),
+            prompt_preset: None,
+            game_title: String::new(),
+            target_language: "English".into(),
             max_context_tokens: [64, 64],
             temperature: None,
             top_p: None,
@@ -67,7 +219,17 @@ impl Default for ChatSettings {
             stream: true,
             service_tier: Some(openai::ServiceTier::Priority),
             reasoning_effort: None,
+            reasoning_summary: None,
             verbosity: None,
+            pricing: Pricing::default(),
+            budget: Budget::default(),
+            memory: MemorySettings::default(),
+            summary: SummarySettings::default(),
+            glossary_path: "glossary.toml".into(),
+            sessions_dir: "sessions".into(),
+            session: None,
+            compare_lanes: Vec::new(),
+            prefetch_lines: None,
         }
     }
 }
@@ -90,12 +252,16 @@ pub struct Settings {
     pub ruby_text_type: RubyTextType,
     pub more_variants: bool,
     pub stroke_text: bool,
+    pub accent: AccentSettings,
+    pub vocab: VocabSettings,
 
     pub translator_type: TranslatorType,
     pub auto_translate: bool,
     pub openai_api_key: String,
+    pub anthropic_api_key: String,
     pub chat: ChatSettings,
 
+    pub tts: TtsSettings,
     pub vv_model_path: String,
     pub auto_tts_regex: Option<String>,
 
@@ -107,6 +273,11 @@ pub struct Settings {
     pub regex_replace: String,
 
     pub inject_proc_name: String,
+
+    /// Text file read line by line by the script reader.
+    pub script_path: String,
+    /// Line the script reader was last on.
+    pub script_line: Option<usize>,
 }
 impl Default for Settings {
     fn default() -> Self {
@@ -126,12 +297,16 @@ impl Default for Settings {
             ruby_text_type: RubyTextType::None,
             more_variants: true,
             stroke_text: true,
+            accent: AccentSettings::default(),
+            vocab: VocabSettings::default(),
 
             translator_type: TranslatorType::Chat,
             auto_translate: false,
             openai_api_key: Default::default(),
+            anthropic_api_key: Default::default(),
             chat: Default::default(),
 
+            tts: Default::default(),
             vv_model_path: Default::default(),
             auto_tts_regex: None,
 
@@ -143,6 +318,9 @@ impl Default for Settings {
             regex_replace: Default::default(),
 
             inject_proc_name: Default::default(),
+
+            script_path: Default::default(),
+            script_line: None,
         }
     }
 }
@@ -182,3 +360,23 @@ impl Settings {
         std::fs::write(Self::CONFIG_FILE, toml::to_string(self).unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speakers_with_a_voice_use_its_style() {
+        let tts = TtsSettings {
+            style: 2,
+            voices: vec![Voice {
+                speaker: "雪菜".into(),
+                style: 8,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(tts.style_for("【雪菜】「おはよう」"), 8);
+        assert_eq!(tts.style_for("春希「おはよう」"), 2);
+        assert_eq!(tts.style_for("おはよう"), 2);
+    }
+}
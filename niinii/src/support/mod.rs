@@ -1,5 +1,6 @@
 //! Random compatibility shims
 
 pub mod docking;
+pub mod hash;
 pub mod platform;
 pub mod regex;
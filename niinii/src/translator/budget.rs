@@ -0,0 +1,463 @@
+//! Spend budgets and request rate limiting.
+//!
+//! Spend in this run is derived from the exchanges the backend already
+//! records. Usage is also appended to a JSON-lines log, which is read back
+//! at startup so the hourly and daily windows carry over restarts. Windows
+//! are rolling. Spend is only known once a request completes, so the request
+//! that crosses a budget is allowed and the next one is blocked. Requests
+//! beyond the rate limit are queued until it allows them.
+
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use openai::{chat::Usage, ModelId};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::translator::{
+    chat::{ExchangeView, Response},
+    pricing::{Pricing, UsageTotals},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Hour,
+    Day,
+    Session,
+}
+
+/// The longest window that carries over restarts.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Window {
+    fn duration(&self) -> Option<Duration> {
+        match self {
+            Window::Hour => Some(Duration::from_secs(60 * 60)),
+            Window::Day => Some(DAY),
+            Window::Session => None,
+        }
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Window::Hour => "hourly",
+            Window::Day => "daily",
+            Window::Session => "session",
+        })
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BudgetExceeded {
+    #[error("{window} token budget exhausted ({spent} / {limit})")]
+    Tokens {
+        window: Window,
+        spent: u64,
+        limit: u64,
+    },
+    #[error("{window} spend budget exhausted ({spent:.4} / {limit:.4})")]
+    Cost {
+        window: Window,
+        spent: f64,
+        limit: f64,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Budget {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_hour: Option<u64>,
+    pub tokens_per_day: Option<u64>,
+    pub tokens_per_session: Option<u64>,
+    /// In the pricing table's currency.
+    pub cost_per_hour: Option<f64>,
+    pub cost_per_day: Option<f64>,
+    pub cost_per_session: Option<f64>,
+    /// JSON-lines file usage is logged to. Empty only counts usage since
+    /// startup.
+    pub usage_path: String,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: None,
+            tokens_per_hour: None,
+            tokens_per_day: None,
+            tokens_per_session: None,
+            cost_per_hour: None,
+            cost_per_day: None,
+            cost_per_session: None,
+            usage_path: "usage.jsonl".into(),
+        }
+    }
+}
+
+impl Budget {
+    /// How long a new request must be queued for to stay within the rate
+    /// limit. Queued requests count at the time they will be sent.
+    pub fn delay(&self, exchanges: &[ExchangeView], now: Instant) -> Duration {
+        let Some(limit) = self.requests_per_minute.filter(|limit| *limit > 0) else {
+            return Duration::ZERO;
+        };
+        let minute = Duration::from_secs(60);
+        let mut sent: Vec<Instant> = exchanges
+            .iter()
+            .filter(|ex| !ex.recalled && !matches!(ex.response, Response::Blocked(_)))
+            .map(|ex| ex.started)
+            .filter(|started| now.saturating_duration_since(*started) < minute)
+            .collect();
+        if sent.len() < limit as usize {
+            return Duration::ZERO;
+        }
+        // The next slot opens a minute after the request `limit` places
+        // from the end.
+        sent.sort();
+        let slot = sent[sent.len() - limit as usize] + minute;
+        slot.saturating_duration_since(now)
+    }
+
+    /// Whether a new request may be sent without exceeding a budget.
+    pub fn check(
+        &self,
+        exchanges: &[ExchangeView],
+        earlier: &UsageLog,
+        pricing: &Pricing,
+        now: Instant,
+    ) -> Result<(), BudgetExceeded> {
+        for (window, tokens, cost) in [
+            (Window::Hour, self.tokens_per_hour, self.cost_per_hour),
+            (Window::Day, self.tokens_per_day, self.cost_per_day),
+            (
+                Window::Session,
+                self.tokens_per_session,
+                self.cost_per_session,
+            ),
+        ] {
+            if tokens.is_none() && cost.is_none() {
+                continue;
+            }
+            let totals = spent(exchanges, earlier, pricing, now, window);
+            let spent_tokens = totals.prompt_tokens + totals.completion_tokens;
+            if let Some(limit) = tokens.filter(|limit| spent_tokens >= *limit) {
+                return Err(BudgetExceeded::Tokens {
+                    window,
+                    spent: spent_tokens,
+                    limit,
+                });
+            }
+            let spent_cost = totals.cost.total();
+            if let Some(limit) = cost.filter(|limit| spent_cost >= *limit) {
+                return Err(BudgetExceeded::Cost {
+                    window,
+                    spent: spent_cost,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Usage of exchanges started within `window` of `now`, including requests
+/// from earlier runs unless the window is the session.
+pub fn spent(
+    exchanges: &[ExchangeView],
+    earlier: &UsageLog,
+    pricing: &Pricing,
+    now: Instant,
+    window: Window,
+) -> UsageTotals {
+    let mut totals = UsageTotals::default();
+    let exchanges = exchanges.iter().filter_map(|ex| {
+        let age = now.saturating_duration_since(ex.started);
+        Some((age, &ex.model, ex.usage.as_ref()?))
+    });
+    let since_load = now.saturating_duration_since(earlier.loaded);
+    let earlier = match window {
+        Window::Session => &[][..],
+        Window::Hour | Window::Day => &earlier.records[..],
+    }
+    .iter()
+    .map(|(age, model, usage)| (*age + since_load, model, usage));
+    for (age, model, usage) in exchanges.chain(earlier) {
+        if window.duration().is_none_or(|duration| age < duration) {
+            totals.add(pricing, model, usage);
+        }
+    }
+    totals
+}
+
+#[derive(Deserialize, Serialize)]
+struct UsageRecord {
+    /// Seconds since the Unix epoch.
+    at: u64,
+    model: ModelId,
+    usage: Usage,
+}
+
+/// Log of the usage of every completed request. Only requests from earlier
+/// runs are kept in memory, since this run's are already in the exchanges.
+#[derive(Debug)]
+pub struct UsageLog {
+    path: Option<PathBuf>,
+    loaded: Instant,
+    /// Age when loaded, model and usage of each request.
+    records: Vec<(Duration, ModelId, Usage)>,
+}
+
+impl Default for UsageLog {
+    fn default() -> Self {
+        Self {
+            path: None,
+            loaded: Instant::now(),
+            records: Vec::new(),
+        }
+    }
+}
+
+impl UsageLog {
+    /// Load the log at `path`, dropping records too old to count towards
+    /// any window. A missing file is not an error; malformed lines are
+    /// skipped.
+    pub fn open(path: &str) -> Self {
+        if path.is_empty() {
+            return Self::default();
+        }
+        let path = PathBuf::from(path);
+        let (loaded, now) = (Instant::now(), SystemTime::now());
+        let mut records = Vec::new();
+        match std::fs::File::open(&path) {
+            Ok(file) => {
+                let mut kept = String::new();
+                let mut dropped = false;
+                for line in BufReader::new(file).lines() {
+                    let Ok(line) = line else { break };
+                    let record = match serde_json::from_str::<UsageRecord>(&line) {
+                        Ok(record) => record,
+                        Err(err) => {
+                            tracing::warn!(%err, "skipping usage record");
+                            dropped = true;
+                            continue;
+                        }
+                    };
+                    let at = UNIX_EPOCH + Duration::from_secs(record.at);
+                    let age = now.duration_since(at).unwrap_or_default();
+                    if age >= DAY {
+                        dropped = true;
+                        continue;
+                    }
+                    records.push((age, record.model, record.usage));
+                    kept.push_str(&line);
+                    kept.push('\n');
+                }
+                // Compact the log to the records that still count.
+                if dropped {
+                    if let Err(err) = std::fs::write(&path, kept) {
+                        tracing::error!(%err, ?path, "failed to compact usage log");
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => tracing::error!(%err, ?path, "failed to open usage log"),
+        }
+        Self {
+            path: Some(path),
+            loaded,
+            records,
+        }
+    }
+
+    /// Append the usage of a request completed now. The file is written on
+    /// a blocking thread.
+    pub fn record(&self, model: &ModelId, usage: &Usage) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let record = UsageRecord {
+            at,
+            model: model.clone(),
+            usage: usage.clone(),
+        };
+        let line = match serde_json::to_string(&record) {
+            Ok(line) => line + "\n",
+            Err(err) => {
+                tracing::error!(%err, "failed to serialize usage record");
+                return;
+            }
+        };
+        tokio::task::spawn_blocking(move || {
+            let result = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(line.as_bytes()));
+            if let Err(err) = result {
+                tracing::error!(%err, ?path, "failed to write usage log");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translator::pricing::ModelPrice;
+
+    fn usage(total_tokens: u32) -> Usage {
+        Usage {
+            prompt_tokens: total_tokens,
+            completion_tokens: 0,
+            total_tokens,
+            completion_tokens_details: None,
+            prompt_tokens_details: None,
+        }
+    }
+
+    fn exchange(started: Instant, total_tokens: u32) -> ExchangeView {
+        ExchangeView {
+            model: ModelId("m".into()),
+            response: Response::Cancelled,
+            usage: Some(usage(total_tokens)),
+            started,
+            ..Default::default()
+        }
+    }
+
+    /// A reference point far enough from boot that windows can be
+    /// subtracted from it.
+    fn now() -> Instant {
+        Instant::now() + Duration::from_secs(48 * 60 * 60)
+    }
+
+    fn pricing() -> Pricing {
+        Pricing {
+            currency: "$".into(),
+            models: vec![ModelPrice {
+                model: "m".into(),
+                input: 1_000_000.0,
+                cached_input: 0.0,
+                output: 0.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn rate_limit_queues_beyond_recent_requests() {
+        let now = now();
+        let budget = Budget {
+            requests_per_minute: Some(2),
+            ..Default::default()
+        };
+        let mut exchanges = vec![exchange(now - Duration::from_secs(90), 0)];
+        exchanges.push(exchange(now - Duration::from_secs(30), 0));
+        assert_eq!(budget.delay(&exchanges, now), Duration::ZERO);
+        exchanges.push(exchange(now - Duration::from_secs(10), 0));
+        assert_eq!(budget.delay(&exchanges, now), Duration::from_secs(30));
+        // A queued request takes the slot it is waiting for.
+        exchanges.push(exchange(now + Duration::from_secs(30), 0));
+        assert_eq!(budget.delay(&exchanges, now), Duration::from_secs(50));
+        exchanges.pop();
+        // Blocked and recalled requests never reached the server.
+        exchanges[2].response = Response::Blocked("".into());
+        assert_eq!(budget.delay(&exchanges, now), Duration::ZERO);
+        exchanges.push(exchange(now, 0));
+        exchanges[3].recalled = true;
+        assert_eq!(budget.delay(&exchanges, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn token_budget_uses_rolling_window() {
+        let now = now();
+        let budget = Budget {
+            tokens_per_hour: Some(100),
+            ..Default::default()
+        };
+        let exchanges = vec![
+            exchange(now - Duration::from_secs(2 * 60 * 60), 500),
+            exchange(now - Duration::from_secs(60), 60),
+        ];
+        let log = UsageLog::default();
+        assert_eq!(budget.check(&exchanges, &log, &pricing(), now), Ok(()));
+        let budget = Budget {
+            tokens_per_session: Some(100),
+            ..Default::default()
+        };
+        assert!(matches!(
+            budget.check(&exchanges, &log, &pricing(), now),
+            Err(BudgetExceeded::Tokens {
+                window: Window::Session,
+                spent: 560,
+                limit: 100,
+            })
+        ));
+    }
+
+    #[test]
+    fn cost_budget_uses_pricing() {
+        let now = now();
+        let budget = Budget {
+            cost_per_day: Some(50.0),
+            ..Default::default()
+        };
+        let log = UsageLog::default();
+        let exchanges = vec![exchange(now, 40)];
+        assert_eq!(budget.check(&exchanges, &log, &pricing(), now), Ok(()));
+        let exchanges = vec![exchange(now, 40), exchange(now, 20)];
+        let err = budget.check(&exchanges, &log, &pricing(), now).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "daily spend budget exhausted (60.0000 / 50.0000)"
+        );
+    }
+
+    #[test]
+    fn earlier_usage_counts_towards_hour_and_day() {
+        let path =
+            std::env::temp_dir().join(format!("niinii-usage-test-{}.jsonl", std::process::id()));
+        let unix_now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let lines: String = [(2 * 60 * 60, 30), (60, 20), (2 * 24 * 60 * 60, 1000)]
+            .into_iter()
+            .map(|(age, tokens)| {
+                let record = UsageRecord {
+                    at: unix_now - age,
+                    model: ModelId("m".into()),
+                    usage: usage(tokens),
+                };
+                serde_json::to_string(&record).unwrap() + "\n"
+            })
+            .chain(["garbage\n".to_string()])
+            .collect();
+        std::fs::write(&path, lines).unwrap();
+
+        let log = UsageLog::open(&path.to_string_lossy());
+        let now = Instant::now();
+        let exchanges = vec![exchange(now, 5)];
+        let tokens = |window| {
+            let totals = spent(&exchanges, &log, &pricing(), now, window);
+            totals.prompt_tokens + totals.completion_tokens
+        };
+        assert_eq!(tokens(Window::Hour), 25);
+        assert_eq!(tokens(Window::Day), 55);
+        assert_eq!(tokens(Window::Session), 5);
+        // Records too old to count and malformed lines are compacted away.
+        let compacted = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(compacted.lines().count(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}
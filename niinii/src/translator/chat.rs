@@ -1,13 +1,30 @@
-//! Chat Completions backend for the translator runtime.
+//! Chat backend for the translator runtime.
 //!
-//! Implements [`Backend`] for [`ChatBackend`]. Shape:
+//! Speaks Chat Completions, the Anthropic Messages API or the OpenAI
+//! Responses API through [`ChatClient`]. Shape:
 //!
-//! - Commands (external, from the UI): translate, cancel, edit the context
+//! - Commands (external, from the UI): translate, regenerate a line or
+//!   choose between its attempts, compare a line across models and pick the
+//!   winner, prefetch upcoming lines, cancel, edit or replace the context
 //!   buffer, refresh the models list.
 //! - Events (internal, from adapter tasks): stream start, token deltas,
 //!   completion, failure, models refreshed.
+//! - Summaries: with summaries enabled, turns evicted from the context buffer
+//!   are queued and folded into a pinned summary message by an exchange of
+//!   their own, one at a time.
+//! - Alternates: regenerating a line sends it again with the context it was
+//!   first sent with. Every attempt is kept; the one that completes last, or
+//!   the one chosen, replaces the line's turns in the context buffer in
+//!   place.
+//! - Prefetching: upcoming lines are translated one at a time before they
+//!   are shown, each sent as if the lines before it were already committed.
+//!   When a prefetched line is translated it is served instantly and
+//!   committed in order; anything else that changes the context buffer
+//!   cancels the queue.
 //! - State (published as immutable snapshots): editable context buffer,
-//!   in-flight + completed exchanges, models list, last error.
+//!   in-flight + completed exchanges, models list, last error, the
+//!   server-side response chain for stateful backends, and the translation
+//!   memory.
 //!
 //! Per-request parameters (`TranslateConfig`) are snapshotted from `Settings`
 //! at submission time; nothing in this module reads `Settings` live.
@@ -18,26 +35,38 @@ use std::{
         atomic::{AtomicU64, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use arc_swap::ArcSwap;
 use enclose::enclose;
 use openai::{
     chat::{
-        self, Message, PartialToolCall, Role, Tool, ToolCall, ToolCallAccumulator, ToolChoice,
+        Message, PartialToolCall, Role, Tokenizer, Tool, ToolCall, ToolCallAccumulator, ToolChoice,
         Usage,
     },
-    ConnectionPolicy, ModelId, ReasoningEffort, ServiceTier, Verbosity,
+    responses::ReasoningSummary,
+    ModelId, ReasoningEffort, ServiceTier, Verbosity,
 };
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 
-use crate::settings::Settings;
+use crate::{
+    settings::Settings,
+    translator::{
+        budget::{Budget, UsageLog},
+        client::{ChatClient, ChatDelta},
+        glossary::{self, GlossaryTerm},
+        memory::{MemoryEntry, MemoryKey, MemorySettings, TranslationMemory},
+        pricing::Pricing,
+        summary::{self, SummarySettings},
+        template::{Template, Variable, Variables},
+    },
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct ExchangeId(pub u64);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -54,7 +83,11 @@ pub struct ContextMessage {
 #[derive(Clone, Debug)]
 pub struct TranslateConfig {
     pub model: ModelId,
+    /// Rendered from the prompt template.
     pub system_prompt: String,
+    /// Language translations are written in. Part of the memory key and
+    /// selects the glossary terms that apply.
+    pub target_language: String,
     pub max_context_tokens: [u32; 2],
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
@@ -62,18 +95,29 @@ pub struct TranslateConfig {
     pub presence_penalty: Option<f32>,
     pub service_tier: Option<ServiceTier>,
     pub reasoning_effort: Option<ReasoningEffort>,
+    pub reasoning_summary: Option<ReasoningSummary>,
     pub verbosity: Option<Verbosity>,
     pub stream: bool,
     pub tools: Vec<Tool>,
     pub tool_choice: Option<ToolChoice>,
+    pub budget: Budget,
+    pub pricing: Pricing,
+    pub memory: MemorySettings,
+    /// Glossary terms detected in the text being translated.
+    pub glossary: Vec<GlossaryTerm>,
+    /// The system prompt already lists the glossary terms, so they are not
+    /// sent next to the line.
+    pub glossary_in_prompt: bool,
+    pub summary: SummarySettings,
 }
 
 impl TranslateConfig {
     pub fn from_settings(settings: &Settings) -> Self {
         let c = &settings.chat;
-        Self {
+        let mut config = Self {
             model: c.model.clone(),
-            system_prompt: c.system_prompt.clone(),
+            system_prompt: String::new(),
+            target_language: c.target_language.clone(),
             max_context_tokens: c.max_context_tokens,
             temperature: c.temperature,
             top_p: c.top_p,
@@ -81,12 +125,91 @@ impl TranslateConfig {
             presence_penalty: c.presence_penalty,
             service_tier: c.service_tier,
             reasoning_effort: c.reasoning_effort,
+            reasoning_summary: c.reasoning_summary,
             verbosity: c.verbosity,
             stream: c.stream,
             tools: Vec::new(),
             tool_choice: None,
+            budget: c.budget.clone(),
+            pricing: c.pricing.clone(),
+            memory: c.memory.clone(),
+            glossary: Vec::new(),
+            glossary_in_prompt: false,
+            summary: c.summary.clone(),
+        };
+        let variables = Variables {
+            game_title: c.game_title.clone(),
+            target_language: c.target_language.clone(),
+            ..Default::default()
+        };
+        config.set_prompt(c.prompt_template(), &variables);
+        config
+    }
+
+    /// Render the system prompt from `template`. A malformed template is
+    /// sent verbatim.
+    pub fn set_prompt(&mut self, template: &str, variables: &Variables) {
+        match Template::parse(template) {
+            Ok(template) => {
+                self.system_prompt = template.render(variables);
+                self.glossary_in_prompt = template.uses(Variable::Glossary);
+            }
+            Err(err) => {
+                tracing::warn!(%err, "invalid prompt template");
+                self.system_prompt = template.to_string();
+                self.glossary_in_prompt = false;
+            }
+        }
+    }
+
+    /// The context limit, less what the system prompt and tools take up in
+    /// every request.
+    fn context_limit(&self) -> ContextLimit {
+        let system = Message {
+            role: Role::System,
+            content: Some(self.system_prompt.clone()),
+            ..Default::default()
+        };
+        ContextLimit {
+            tokens: self.max_context_tokens,
+            overhead: Tokenizer::for_model(self.model.as_ref())
+                .count_prompt(&[system], &self.tools),
         }
     }
+
+    /// Parameters for summary requests, if summaries are enabled.
+    fn summarizer(&self) -> Option<Arc<TranslateConfig>> {
+        let summary = &self.summary;
+        summary.enabled.then(|| {
+            let mut config = TranslateConfig {
+                model: summary.model.clone().unwrap_or_else(|| self.model.clone()),
+                max_tokens: summary.max_tokens,
+                tools: Vec::new(),
+                tool_choice: None,
+                glossary: Vec::new(),
+                summary: SummarySettings {
+                    enabled: false,
+                    ..summary.clone()
+                },
+                ..self.clone()
+            };
+            let variables = Variables {
+                target_language: self.target_language.clone(),
+                ..Default::default()
+            };
+            config.set_prompt(&summary.prompt, &variables);
+            config.glossary_in_prompt = false;
+            Arc::new(config)
+        })
+    }
+}
+
+/// `[trim to, trim above]` token counts for the context buffer, and the
+/// tokens every request spends outside of it.
+#[derive(Clone, Copy, Debug)]
+pub struct ContextLimit {
+    tokens: [u32; 2],
+    overhead: u32,
 }
 
 #[derive(Clone, Debug)]
@@ -101,6 +224,8 @@ pub enum Response {
     },
     Errored(Arc<str>),
     Cancelled,
+    /// Not sent because a budget was exhausted.
+    Blocked(Arc<str>),
 }
 
 impl Response {
@@ -109,7 +234,7 @@ impl Response {
     pub fn content(&self) -> &str {
         match self {
             Response::Streaming { content, .. } | Response::Completed { content, .. } => content,
-            Response::Errored(_) | Response::Cancelled => "",
+            Response::Errored(_) | Response::Cancelled | Response::Blocked(_) => "",
         }
     }
     pub fn is_terminal(&self) -> bool {
@@ -117,6 +242,10 @@ impl Response {
     }
 }
 
+/// Number of most recent exchanges that keep the context they were sent
+/// after, and so can be regenerated.
+pub const REGENERATE_HISTORY: usize = 32;
+
 #[derive(Clone, Debug)]
 pub struct ExchangeView {
     pub id: ExchangeId,
@@ -124,29 +253,186 @@ pub struct ExchangeView {
     pub user_message: Message,
     pub response: Response,
     pub usage: Option<Usage>,
+    /// When the request was sent, or will be once the rate limit allows it.
+    /// Budgets and the rate limit are windowed on this.
+    pub started: Instant,
+    /// Served from the translation memory without a request.
+    pub recalled: bool,
+    /// Memory key the response is remembered under once it completes.
+    pub memory_key: Option<MemoryKey>,
+    /// Context messages this exchange committed, so another attempt at the
+    /// line can replace them.
+    pub committed: Vec<MsgId>,
+    /// The context buffer the line was sent after, system prompt aside.
+    /// Regenerating the line sends it after the same turns. Dropped once
+    /// [`REGENERATE_HISTORY`] newer exchanges have started.
+    pub context: Option<Arc<[Message]>>,
+    /// First attempt at the line, if this exchange regenerated it.
+    pub attempt_of: Option<ExchangeId>,
+    /// Glossary terms sent with the request.
+    pub glossary: Vec<GlossaryTerm>,
+    /// Glossary terms whose preferred translation the response did not use.
+    pub ignored_terms: Vec<GlossaryTerm>,
+    /// Updates the summary instead of translating. Never committed to the
+    /// context buffer.
+    pub summary: bool,
+    /// Comparison candidate, committed to the context buffer only if picked.
+    pub candidate: bool,
+    /// When the first content arrived.
+    pub first_token: Option<Instant>,
+    /// When the exchange completed, failed or was cancelled.
+    pub finished: Option<Instant>,
+    /// Translated ahead of time. Committed only once the line is shown.
+    pub prefetched: bool,
+    /// Reasoning streamed ahead of the answer, or a summary of it. Never
+    /// committed to the context buffer.
+    pub reasoning: String,
+}
+
+impl Default for ExchangeView {
+    /// An exchange submitted now that has yet to receive anything.
+    fn default() -> Self {
+        Self {
+            id: ExchangeId::default(),
+            model: ModelId::default(),
+            user_message: Message::default(),
+            response: Response::Streaming {
+                content: String::new(),
+                tool_calls: ToolCallAccumulator::new(),
+            },
+            usage: None,
+            started: Instant::now(),
+            recalled: false,
+            memory_key: None,
+            committed: Vec::new(),
+            context: None,
+            attempt_of: None,
+            glossary: Vec::new(),
+            ignored_terms: Vec::new(),
+            summary: false,
+            candidate: false,
+            first_token: None,
+            finished: None,
+            prefetched: false,
+            reasoning: String::new(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct ChatState {
     pub context: VecDeque<ContextMessage>,
+    /// Bumped whenever `context` changes, so observers can tell a changed
+    /// buffer without comparing it.
+    pub context_revision: u64,
     next_msg_id: u64,
     pub exchanges: Vec<ExchangeView>,
     pub models: Vec<ModelId>,
     pub last_error: Option<Arc<str>>,
+    /// Head of the server-side conversation for stateful backends. Only valid
+    /// while the context buffer mirrors the server's history, so any edit,
+    /// clear or trim of the buffer resets it and the next request resends
+    /// the whole buffer.
+    pub response_id: Option<String>,
+    pub memory: Arc<TranslationMemory>,
+    /// Usage of requests from earlier runs, for the hourly and daily budgets.
+    pub usage_log: Arc<UsageLog>,
+    /// Session the context buffer belongs to, if any.
+    pub session: Option<String>,
+    /// Evicted turns not yet folded into the summary. Kept until a summary
+    /// of them completes.
+    pub evicted: Vec<Message>,
+    /// Parameters for the next summary request, from the translation that
+    /// last evicted turns.
+    summarizer: Option<Arc<TranslateConfig>>,
+    pub summarizing: bool,
+    /// Number of turns at the front of `evicted` the summary in flight
+    /// covers.
+    summarized: usize,
+    /// The last summary did not complete. Retried once more turns are
+    /// evicted, rather than on every event.
+    summary_stalled: bool,
+    /// Prefetched lines not shown yet, in order.
+    pub speculation: VecDeque<ExchangeId>,
+    /// Lines to prefetch, with their parameters.
+    upcoming: Vec<(String, Arc<TranslateConfig>)>,
+    /// Translations that were served by a prefetched exchange, to that
+    /// exchange.
+    aliases: HashMap<ExchangeId, ExchangeId>,
 }
 
 impl ChatState {
     pub fn exchange(&self, id: ExchangeId) -> Option<&ExchangeView> {
+        let id = self.aliases.get(&id).copied().unwrap_or(id);
         self.exchanges.iter().find(|e| e.id == id)
     }
+    /// The context the line of `id` was first sent after, if an attempt at
+    /// it still holds it.
+    pub fn regenerate_context(&self, id: ExchangeId) -> Option<Arc<[Message]>> {
+        let ex = self.exchange(id).filter(|ex| !ex.summary)?;
+        let root = ex.attempt_of.unwrap_or(ex.id);
+        self.exchanges
+            .iter()
+            .rev()
+            .filter(|ex| ex.id == root || ex.attempt_of == Some(root))
+            .find_map(|ex| ex.context.clone())
+    }
+    /// Every attempt at the line of `id`, first to last.
+    pub fn alternates(&self, id: ExchangeId) -> Vec<&ExchangeView> {
+        let Some(ex) = self.exchange(id) else {
+            return Vec::new();
+        };
+        let root = ex.attempt_of.unwrap_or(ex.id);
+        self.exchanges
+            .iter()
+            .filter(|e| e.id == root || e.attempt_of == Some(root))
+            .collect()
+    }
+    /// Size of the context buffer as counted by `tokenizer`.
+    pub fn context_tokens(&self, tokenizer: Tokenizer) -> u32 {
+        count_tokens(&self.context, tokenizer)
+    }
     fn mint_id(&mut self) -> MsgId {
         let id = MsgId(self.next_msg_id);
         self.next_msg_id += 1;
         id
     }
-    fn push_back(&mut self, message: Message) {
+    /// Replace the summary message, pinning a new one to the front if there
+    /// is none yet.
+    fn set_summary(&mut self, content: &str) {
+        let message = summary::message(content);
+        match self
+            .context
+            .iter_mut()
+            .find(|e| e.message.name.as_deref() == Some(summary::NAME))
+        {
+            Some(entry) => entry.message = message,
+            None => {
+                let id = self.mint_id();
+                self.context.push_front(ContextMessage { id, message });
+            }
+        }
+        self.response_id = None;
+        self.context_revision += 1;
+    }
+    /// The summary in flight completed with `content`, or ended without one.
+    fn end_summary(&mut self, content: Option<&str>) {
+        self.summarizing = false;
+        match content.filter(|content| !content.trim().is_empty()) {
+            Some(content) => {
+                self.set_summary(content);
+                let summarized = self.summarized.min(self.evicted.len());
+                self.evicted.drain(..summarized);
+            }
+            None => self.summary_stalled = true,
+        }
+        self.summarized = 0;
+    }
+    fn push_back(&mut self, message: Message) -> MsgId {
         let id = self.mint_id();
         self.context.push_back(ContextMessage { id, message });
+        self.context_revision += 1;
+        id
     }
 }
 
@@ -166,9 +452,44 @@ pub enum ChatCommand {
         text: String,
         config: Arc<TranslateConfig>,
     },
+    /// Translate the line of `of` again after the same context, bypassing
+    /// the translation memory. The new attempt is kept next to the earlier
+    /// ones and replaces whichever of them is committed once it completes.
+    /// `config` may name a different model or temperature.
+    Regenerate {
+        id: ExchangeId,
+        of: ExchangeId,
+        config: Arc<TranslateConfig>,
+    },
+    /// Commit a completed attempt at a line in place of the one that is.
+    Choose {
+        id: ExchangeId,
+        config: Arc<TranslateConfig>,
+    },
+    /// Translate `text` with every lane at once. Each lane is sent the whole
+    /// context buffer and nothing is committed until one is picked.
+    Compare {
+        text: String,
+        lanes: Vec<(ExchangeId, ChatClient, Arc<TranslateConfig>)>,
+    },
+    /// Commit a completed comparison candidate to the context buffer.
+    Pick {
+        id: ExchangeId,
+        config: Arc<TranslateConfig>,
+    },
+    /// Replace the lines to translate ahead of time, in the order they will
+    /// be shown. Prefetched lines that are still upcoming are kept.
+    Prefetch {
+        lines: Vec<(String, Arc<TranslateConfig>)>,
+    },
     Cancel(ExchangeId),
     EditContext(ContextEdit),
     ClearContext,
+    /// Replace the context buffer, e.g. when switching sessions.
+    LoadSession {
+        name: Option<String>,
+        context: Vec<Message>,
+    },
     RefreshModels,
 }
 
@@ -177,11 +498,23 @@ pub enum ChatEvent {
         id: ExchangeId,
         model: ModelId,
         user_message: Message,
+        recalled: bool,
+        memory_key: Option<MemoryKey>,
+        glossary: Vec<GlossaryTerm>,
+        summary: bool,
+        candidate: bool,
+        prefetched: bool,
+        context: Arc<[Message]>,
+        attempt_of: Option<ExchangeId>,
     },
     Delta {
         id: ExchangeId,
         content: String,
     },
+    ReasoningDelta {
+        id: ExchangeId,
+        reasoning: String,
+    },
     ToolCallDelta {
         id: ExchangeId,
         partials: Vec<PartialToolCall>,
@@ -189,7 +522,12 @@ pub enum ChatEvent {
     Completed {
         id: ExchangeId,
         usage: Option<Usage>,
-        max_context_tokens: [u32; 2],
+        limit: ContextLimit,
+        response_id: Option<String>,
+        /// The chain head the request continued from.
+        previous_response_id: Option<String>,
+        /// Set if turns evicted by this exchange should be summarized.
+        summarizer: Option<Arc<TranslateConfig>>,
     },
     Failed {
         id: ExchangeId,
@@ -198,6 +536,10 @@ pub enum ChatEvent {
     Cancelled {
         id: ExchangeId,
     },
+    Blocked {
+        id: ExchangeId,
+        reason: Arc<str>,
+    },
     ModelsRefreshed(Vec<ModelId>),
     Error(Arc<str>),
 }
@@ -205,40 +547,132 @@ pub enum ChatEvent {
 fn handle_command(
     cmd: ChatCommand,
     state: &mut ChatState,
-    client: &openai::Client,
+    client: &ChatClient,
     inflight: &mut HashMap<ExchangeId, CancellationToken>,
     evt_tx: &mpsc::Sender<ChatEvent>,
 ) {
     match cmd {
         ChatCommand::Translate { id, text, config } => {
-            let user_message = Message {
-                role: Role::User,
-                content: Some(text),
-                ..Default::default()
+            if promote(state, id, &text, &config) {
+                return;
+            }
+            // The reader moved off the prefetched lines.
+            cancel_speculation(state, inflight, 0);
+            state.upcoming.clear();
+            translate(
+                state,
+                client,
+                inflight,
+                evt_tx,
+                id,
+                text,
+                config,
+                Submit::Recall,
+            );
+        }
+        ChatCommand::Regenerate { id, of, config } => {
+            if state.regenerate_context(of).is_none() {
+                return;
+            }
+            let Some(ex) = state.exchange(of) else {
+                return;
             };
-            let prompt = build_prompt(state, &config, &user_message);
-            // Synchronously seed the exchange -- no channel trip needed since
-            // we're already holding the state.
-            reduce(
+            let root = ex.attempt_of.unwrap_or(ex.id);
+            let text = ex.user_message.content.clone().unwrap_or_default();
+            cancel_speculation(state, inflight, 0);
+            translate(
                 state,
-                ChatEvent::Started {
-                    id,
-                    model: config.model.clone(),
-                    user_message,
-                },
+                client,
+                inflight,
+                evt_tx,
+                id,
+                text,
+                config,
+                Submit::Regenerate(root),
             );
-            let cancel = CancellationToken::new();
-            inflight.insert(id, cancel.clone());
-            spawn_adapter(client.clone(), config, prompt, id, cancel, evt_tx.clone());
+        }
+        ChatCommand::Choose { id, config } => {
+            let choosable = state.exchange(id).is_some_and(|ex| {
+                !ex.summary
+                    && ex.committed.is_empty()
+                    && matches!(ex.response, Response::Completed { .. })
+            });
+            if choosable {
+                cancel_speculation(state, inflight, 0);
+                adopt(state, id, config.context_limit(), config.summarizer());
+            }
+        }
+        ChatCommand::Compare { text, lanes } => {
+            for (id, client, config) in lanes {
+                translate(
+                    state,
+                    &client,
+                    inflight,
+                    evt_tx,
+                    id,
+                    text.clone(),
+                    config,
+                    Submit::Candidate,
+                );
+            }
+        }
+        ChatCommand::Pick { id, config } => {
+            let pickable = state.exchange(id).is_some_and(|ex| {
+                ex.candidate
+                    && ex.committed.is_empty()
+                    && matches!(ex.response, Response::Completed { .. })
+            });
+            if pickable {
+                cancel_speculation(state, inflight, 0);
+                commit(state, id, config.context_limit(), config.summarizer());
+                // Candidates are never chained, so the server's history does
+                // not include the pick.
+                state.response_id = None;
+            }
+        }
+        ChatCommand::Prefetch { lines } => {
+            let kept = state
+                .speculation
+                .iter()
+                .zip(&lines)
+                .take_while(|(id, (text, _))| {
+                    state
+                        .exchange(**id)
+                        .is_some_and(|ex| ex.user_message.content.as_ref() == Some(text))
+                })
+                .count();
+            cancel_speculation(state, inflight, kept);
+            state.upcoming = lines;
         }
         ChatCommand::Cancel(id) => {
+            let id = state.aliases.get(&id).copied().unwrap_or(id);
             if let Some(tok) = inflight.remove(&id) {
                 tok.cancel();
                 reduce(state, ChatEvent::Cancelled { id });
             }
         }
-        ChatCommand::EditContext(edit) => apply_edit(state, edit),
-        ChatCommand::ClearContext => state.context.clear(),
+        ChatCommand::EditContext(edit) => {
+            cancel_speculation(state, inflight, 0);
+            apply_edit(state, edit);
+        }
+        ChatCommand::ClearContext => {
+            cancel_speculation(state, inflight, 0);
+            cancel_summary(state, inflight);
+            state.context.clear();
+            state.context_revision += 1;
+            state.response_id = None;
+        }
+        ChatCommand::LoadSession { name, context } => {
+            cancel_speculation(state, inflight, 0);
+            cancel_summary(state, inflight);
+            state.context.clear();
+            state.context_revision += 1;
+            state.response_id = None;
+            for message in context {
+                state.push_back(message);
+            }
+            state.session = name;
+        }
         ChatCommand::RefreshModels => {
             let client = client.clone();
             let tx = evt_tx.clone();
@@ -258,28 +692,457 @@ fn handle_command(
     }
 }
 
+/// How [`translate`] submits a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Submit {
+    /// Serve it from the translation memory if there is a hit.
+    Recall,
+    /// Send the line of the given exchange again with the context it was
+    /// sent with. Never chained, since the server's history has moved on.
+    Regenerate(ExchangeId),
+    /// Send a comparison candidate. Always sent with the whole context
+    /// buffer, since candidates for the same line must not extend the chain.
+    Candidate,
+    /// Prefetch an upcoming line. Served from the translation memory if
+    /// possible, otherwise sent with the whole context buffer followed by
+    /// the lines prefetched before it.
+    Speculate,
+}
+
+/// Serve `text` from the first prefetched line if it matches, making `id` an
+/// alias of the prefetched exchange.
+fn promote(state: &mut ChatState, id: ExchangeId, text: &str, config: &TranslateConfig) -> bool {
+    let Some(&front) = state.speculation.front() else {
+        return false;
+    };
+    let usable = state.exchange(front).is_some_and(|ex| {
+        ex.user_message.content.as_deref() == Some(text)
+            && matches!(
+                ex.response,
+                Response::Streaming { .. } | Response::Completed { .. }
+            )
+    });
+    if !usable {
+        return false;
+    }
+    state.speculation.pop_front();
+    if state.upcoming.first().is_some_and(|(line, _)| line == text) {
+        state.upcoming.remove(0);
+    }
+    state.aliases.insert(id, front);
+    // A line that is still streaming is committed when it completes.
+    let completed = state
+        .exchange(front)
+        .is_some_and(|ex| matches!(ex.response, Response::Completed { .. }));
+    if completed {
+        commit(state, front, config.context_limit(), config.summarizer());
+        state.response_id = None;
+    }
+    true
+}
+
+/// Cancel the prefetched lines after the first `keep`.
+fn cancel_speculation(
+    state: &mut ChatState,
+    inflight: &mut HashMap<ExchangeId, CancellationToken>,
+    keep: usize,
+) {
+    let keep = keep.min(state.speculation.len());
+    for id in state.speculation.split_off(keep) {
+        if let Some(tok) = inflight.remove(&id) {
+            tok.cancel();
+            reduce(state, ChatEvent::Cancelled { id });
+        }
+    }
+}
+
+/// Cancel the summary in flight and forget the turns waiting for one, so
+/// nothing of the old story is folded into a cleared or replaced context.
+fn cancel_summary(state: &mut ChatState, inflight: &mut HashMap<ExchangeId, CancellationToken>) {
+    let running: Vec<ExchangeId> = state
+        .exchanges
+        .iter()
+        .filter(|ex| ex.summary && !ex.response.is_terminal())
+        .map(|ex| ex.id)
+        .collect();
+    for id in running {
+        if let Some(tok) = inflight.remove(&id) {
+            tok.cancel();
+        }
+        reduce(state, ChatEvent::Cancelled { id });
+    }
+    state.evicted.clear();
+    state.summarizer = None;
+    state.summarizing = false;
+    state.summarized = 0;
+    state.summary_stalled = false;
+}
+
+/// Prefetch the next upcoming line once the lines before it have completed
+/// and nothing else is about to change the context buffer.
+fn speculate(
+    state: &mut ChatState,
+    client: &ChatClient,
+    inflight: &mut HashMap<ExchangeId, CancellationToken>,
+    evt_tx: &mpsc::Sender<ChatEvent>,
+    next_id: &AtomicU64,
+) {
+    let Some((text, config)) = state.upcoming.get(state.speculation.len()).cloned() else {
+        return;
+    };
+    let busy = state.exchanges.iter().any(|ex| {
+        !ex.response.is_terminal()
+            && !ex.summary
+            && !ex.candidate
+            && !state.speculation.contains(&ex.id)
+    });
+    let settled = state.speculation.iter().all(|id| {
+        state
+            .exchange(*id)
+            .is_some_and(|ex| matches!(ex.response, Response::Completed { .. }))
+    });
+    if busy || !settled {
+        return;
+    }
+    // Prefetching is opportunistic, so an exhausted budget or the rate limit
+    // skips it instead of recording blocked or queued exchanges.
+    let now = Instant::now();
+    let budget = config
+        .budget
+        .check(&state.exchanges, &state.usage_log, &config.pricing, now);
+    if budget.is_err() || !config.budget.delay(&state.exchanges, now).is_zero() {
+        return;
+    }
+    let id = ExchangeId(next_id.fetch_add(1, Ordering::Relaxed));
+    state.speculation.push_back(id);
+    translate(
+        state,
+        client,
+        inflight,
+        evt_tx,
+        id,
+        text,
+        config,
+        Submit::Speculate,
+    );
+}
+
+/// Turns of the lines prefetched before `id`, as they will be committed.
+fn speculative_turns(state: &ChatState, id: ExchangeId) -> Vec<Message> {
+    state
+        .speculation
+        .iter()
+        .take_while(|x| **x != id)
+        .filter_map(|x| state.exchange(*x))
+        .flat_map(|ex| {
+            [
+                ex.user_message.clone(),
+                Message {
+                    role: Role::Assistant,
+                    content: Some(ex.response.content().to_string()),
+                    ..Default::default()
+                },
+            ]
+        })
+        .collect()
+}
+
+/// Submit `text` as described by `submit`.
+#[allow(clippy::too_many_arguments)]
+fn translate(
+    state: &mut ChatState,
+    client: &ChatClient,
+    inflight: &mut HashMap<ExchangeId, CancellationToken>,
+    evt_tx: &mpsc::Sender<ChatEvent>,
+    id: ExchangeId,
+    text: String,
+    config: Arc<TranslateConfig>,
+    submit: Submit,
+) {
+    let buffer = state.context.iter().map(|e| e.message.clone());
+    let context: Arc<[Message]> = match submit {
+        Submit::Regenerate(of) => state
+            .regenerate_context(of)
+            .unwrap_or_else(|| Arc::from([])),
+        Submit::Speculate => buffer.chain(speculative_turns(state, id)).collect(),
+        Submit::Recall | Submit::Candidate => buffer.collect(),
+    };
+    let attempt_of = match submit {
+        Submit::Regenerate(of) => Some(of),
+        _ => None,
+    };
+    let memory_key = config
+        .memory
+        .enabled
+        .then(|| key_for(context.iter(), &config, &text));
+    let recalled = memory_key
+        .as_ref()
+        .filter(|_| matches!(submit, Submit::Recall | Submit::Speculate))
+        .and_then(|key| state.memory.get(key));
+    let user_message = Message {
+        role: Role::User,
+        content: Some(text),
+        ..Default::default()
+    };
+    if let Some(entry) = recalled {
+        for event in [
+            ChatEvent::Started {
+                id,
+                model: config.model.clone(),
+                user_message,
+                recalled: true,
+                memory_key: None,
+                glossary: config.glossary.clone(),
+                summary: false,
+                candidate: false,
+                prefetched: submit == Submit::Speculate,
+                context,
+                attempt_of,
+            },
+            ChatEvent::Delta {
+                id,
+                content: entry.translation,
+            },
+            // The server never saw this exchange, so the chain breaks.
+            ChatEvent::Completed {
+                id,
+                usage: None,
+                limit: config.context_limit(),
+                response_id: None,
+                previous_response_id: None,
+                summarizer: config.summarizer(),
+            },
+        ] {
+            reduce(state, event);
+        }
+        return;
+    }
+    let now = Instant::now();
+    let budget = config
+        .budget
+        .check(&state.exchanges, &state.usage_log, &config.pricing, now);
+    let delay = config.budget.delay(&state.exchanges, now);
+    let chained = client.is_stateful() && submit == Submit::Recall;
+    let previous_response_id = chained.then(|| state.response_id.clone()).flatten();
+    let prompt = match previous_response_id {
+        Some(_) => build_prompt(&config, std::iter::empty(), &user_message),
+        None => build_prompt(&config, context.iter(), &user_message),
+    };
+    // Synchronously seed the exchange -- no channel trip needed since
+    // we're already holding the state.
+    reduce(
+        state,
+        ChatEvent::Started {
+            id,
+            model: config.model.clone(),
+            user_message,
+            recalled: false,
+            memory_key,
+            glossary: config.glossary.clone(),
+            summary: false,
+            candidate: submit == Submit::Candidate,
+            prefetched: submit == Submit::Speculate,
+            context,
+            attempt_of,
+        },
+    );
+    if let Err(reason) = budget {
+        tracing::warn!(%reason, "translation blocked");
+        reduce(
+            state,
+            ChatEvent::Blocked {
+                id,
+                reason: Arc::from(reason.to_string()),
+            },
+        );
+        return;
+    }
+    queue(state, id, delay);
+    let cancel = CancellationToken::new();
+    inflight.insert(id, cancel.clone());
+    spawn_adapter(
+        client.clone(),
+        config,
+        prompt,
+        previous_response_id,
+        id,
+        delay,
+        cancel,
+        evt_tx.clone(),
+    );
+}
+
+/// Start a summary exchange for the evicted turns unless one is already in
+/// flight.
+fn summarize(
+    state: &mut ChatState,
+    client: &ChatClient,
+    inflight: &mut HashMap<ExchangeId, CancellationToken>,
+    evt_tx: &mpsc::Sender<ChatEvent>,
+    next_id: &AtomicU64,
+) {
+    if state.summarizing || state.summary_stalled || state.evicted.is_empty() {
+        return;
+    }
+    let Some(config) = state.summarizer.clone() else {
+        return;
+    };
+    let id = ExchangeId(next_id.fetch_add(1, Ordering::Relaxed));
+    let previous = summary::current(state.context.iter().map(|e| &e.message));
+    let user_message = Message {
+        role: Role::User,
+        content: Some(summary::request(previous, &state.evicted)),
+        ..Default::default()
+    };
+    let prompt = vec![
+        Message {
+            role: Role::System,
+            content: Some(config.system_prompt.clone()),
+            ..Default::default()
+        },
+        user_message.clone(),
+    ];
+    let now = Instant::now();
+    let budget = config
+        .budget
+        .check(&state.exchanges, &state.usage_log, &config.pricing, now);
+    let delay = config.budget.delay(&state.exchanges, now);
+    reduce(
+        state,
+        ChatEvent::Started {
+            id,
+            model: config.model.clone(),
+            user_message,
+            recalled: false,
+            memory_key: None,
+            glossary: Vec::new(),
+            summary: true,
+            candidate: false,
+            prefetched: false,
+            context: Arc::from([]),
+            attempt_of: None,
+        },
+    );
+    if let Err(reason) = budget {
+        tracing::warn!(%reason, "summary blocked");
+        reduce(
+            state,
+            ChatEvent::Blocked {
+                id,
+                reason: Arc::from(reason.to_string()),
+            },
+        );
+        state.summary_stalled = true;
+        return;
+    }
+    state.summarizing = true;
+    state.summarized = state.evicted.len();
+    queue(state, id, delay);
+    let cancel = CancellationToken::new();
+    inflight.insert(id, cancel.clone());
+    spawn_adapter(
+        client.clone(),
+        config,
+        prompt,
+        None,
+        id,
+        delay,
+        cancel,
+        evt_tx.clone(),
+    );
+}
+
+/// Hold back the request of exchange `id` for `delay` to stay within the
+/// rate limit.
+fn queue(state: &mut ChatState, id: ExchangeId, delay: Duration) {
+    if let Some(ex) = find_mut(&mut state.exchanges, id) {
+        ex.started += delay;
+    }
+}
+
+/// Key `text` by model, system prompt, glossary and the preceding user lines
+/// in `context`.
+fn key_for<'a>(
+    context: impl DoubleEndedIterator<Item = &'a Message>,
+    config: &TranslateConfig,
+    text: &str,
+) -> MemoryKey {
+    let mut context: Vec<&str> = context
+        .rev()
+        .filter(|message| message.role == Role::User)
+        .filter_map(|message| message.content.as_deref())
+        .take(config.memory.context_lines)
+        .collect();
+    context.reverse();
+    let mut prompt = config.system_prompt.clone();
+    if !config.glossary.is_empty() {
+        prompt.push('\n');
+        prompt.push_str(&glossary::prompt(&config.glossary));
+    }
+    MemoryKey::new(
+        text,
+        config.model.as_ref(),
+        &config.target_language,
+        &prompt,
+        context,
+    )
+}
+
 fn reduce(state: &mut ChatState, event: ChatEvent) {
     match event {
         ChatEvent::Started {
             id,
             model,
             user_message,
+            recalled,
+            memory_key,
+            glossary,
+            summary,
+            candidate,
+            prefetched,
+            context,
+            attempt_of,
         } => {
             state.exchanges.push(ExchangeView {
                 id,
                 model,
                 user_message,
-                response: Response::Streaming {
-                    content: String::new(),
-                    tool_calls: ToolCallAccumulator::new(),
-                },
-                usage: None,
+                recalled,
+                memory_key,
+                context: Some(context),
+                attempt_of,
+                glossary,
+                summary,
+                candidate,
+                prefetched,
+                ..Default::default()
             });
+            // Snapshots are only kept for recent lines; each would otherwise
+            // hold a copy of the whole context buffer for the session.
+            if let Some(old) = state.exchanges.iter_mut().rev().nth(REGENERATE_HISTORY) {
+                old.context = None;
+            }
         }
         ChatEvent::Delta { id, content } => {
             if let Some(ex) = find_mut(&mut state.exchanges, id) {
                 if let Response::Streaming { content: acc, .. } = &mut ex.response {
-                    acc.push_str(&content);
+                    // Reasoning models tend to open the answer with blank lines.
+                    let content = if acc.is_empty() {
+                        content.trim_start()
+                    } else {
+                        &content
+                    };
+                    acc.push_str(content);
+                    if !acc.is_empty() {
+                        ex.first_token.get_or_insert_with(Instant::now);
+                    }
+                }
+            }
+        }
+        ChatEvent::ReasoningDelta { id, reasoning } => {
+            if let Some(ex) = find_mut(&mut state.exchanges, id) {
+                if !ex.response.is_terminal() {
+                    ex.reasoning.push_str(&reasoning);
                 }
             }
         }
@@ -293,8 +1156,12 @@ fn reduce(state: &mut ChatState, event: ChatEvent) {
         ChatEvent::Completed {
             id,
             usage,
-            max_context_tokens,
+            limit,
+            response_id,
+            previous_response_id,
+            summarizer,
         } => {
+            let speculative = state.speculation.contains(&id);
             let Some(ex) = find_mut(&mut state.exchanges, id) else {
                 return;
             };
@@ -303,7 +1170,7 @@ fn reduce(state: &mut ChatState, event: ChatEvent) {
                 Response::Streaming {
                     content,
                     tool_calls,
-                } => (content, tool_calls.finish()),
+                } => (content.trim_end().to_string(), tool_calls.finish()),
                 other => {
                     ex.response = other;
                     return;
@@ -313,21 +1180,49 @@ fn reduce(state: &mut ChatState, event: ChatEvent) {
                 content: content.clone(),
                 tool_calls: tool_calls.clone(),
             };
+            if let Some(usage) = &usage {
+                state.usage_log.record(&ex.model, usage);
+            }
             ex.usage = usage;
-            let assistant = Message {
-                role: Role::Assistant,
-                content: Some(content),
-                tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
-                ..Default::default()
+            ex.finished = Some(Instant::now());
+            ex.reasoning.truncate(ex.reasoning.trim_end().len());
+            if ex.summary {
+                state.end_summary(Some(&content));
+                return;
+            }
+            ex.ignored_terms = ex
+                .glossary
+                .iter()
+                .filter(|term| !term.is_followed_in(&content))
+                .cloned()
+                .collect();
+            if ex.candidate || speculative {
+                return;
+            }
+            if ex.attempt_of.is_some() {
+                adopt(state, id, limit, summarizer);
+                return;
+            }
+            let prefetched = ex.prefetched;
+            let trimmed = commit(state, id, limit, summarizer);
+            // Only extend the chain if this exchange continued from the
+            // current head; otherwise the server's history has diverged.
+            state.response_id = match response_id {
+                Some(response_id)
+                    if !trimmed && !prefetched && state.response_id == previous_response_id =>
+                {
+                    Some(response_id)
+                }
+                _ => None,
             };
-            let user_clone = ex.user_message.clone();
-            state.push_back(user_clone);
-            state.push_back(assistant);
-            enforce_context_limit(&mut state.context, &max_context_tokens);
         }
         ChatEvent::Failed { id, error } => {
             if let Some(ex) = find_mut(&mut state.exchanges, id) {
                 ex.response = Response::Errored(error.clone());
+                ex.finished = Some(Instant::now());
+                if ex.summary {
+                    state.end_summary(None);
+                }
             }
             state.last_error = Some(error);
         }
@@ -335,31 +1230,187 @@ fn reduce(state: &mut ChatState, event: ChatEvent) {
             if let Some(ex) = find_mut(&mut state.exchanges, id) {
                 if let Response::Streaming { .. } = ex.response {
                     ex.response = Response::Cancelled;
+                    ex.finished = Some(Instant::now());
+                    if ex.summary {
+                        state.end_summary(None);
+                    }
                 }
             }
         }
+        ChatEvent::Blocked { id, reason } => {
+            if let Some(ex) = find_mut(&mut state.exchanges, id) {
+                ex.response = Response::Blocked(reason);
+            }
+        }
         ChatEvent::ModelsRefreshed(models) => state.models = models,
         ChatEvent::Error(err) => state.last_error = Some(err),
     }
 }
 
+/// Commit a completed exchange's turns to the context buffer and remember
+/// its translation. Returns whether enforcing the context limit evicted
+/// anything.
+fn commit(
+    state: &mut ChatState,
+    id: ExchangeId,
+    limit: ContextLimit,
+    summarizer: Option<Arc<TranslateConfig>>,
+) -> bool {
+    let Some(ex) = state.exchange(id) else {
+        return false;
+    };
+    let Some(assistant) = assistant_turn(ex) else {
+        return false;
+    };
+    remember(&state.memory, ex);
+    let tokenizer = Tokenizer::for_model(ex.model.as_ref());
+    let user = ex.user_message.clone();
+    let committed = vec![state.push_back(user), state.push_back(assistant)];
+    if let Some(ex) = find_mut(&mut state.exchanges, id) {
+        ex.committed = committed;
+    }
+    enforce_limit(state, limit, tokenizer, summarizer)
+}
+
+/// Commit a completed attempt at a line in place of the attempt that is
+/// committed, or append it if none is.
+fn adopt(
+    state: &mut ChatState,
+    id: ExchangeId,
+    limit: ContextLimit,
+    summarizer: Option<Arc<TranslateConfig>>,
+) {
+    // The line's turns are no longer what the server saw.
+    state.response_id = None;
+    let previous = state
+        .alternates(id)
+        .into_iter()
+        .find(|ex| !ex.committed.is_empty())
+        .map(|ex| ex.id);
+    let Some(previous) = previous.filter(|previous| *previous != id) else {
+        commit(state, id, limit, summarizer);
+        return;
+    };
+    let Some(ex) = state.exchange(id) else { return };
+    let Some(assistant) = assistant_turn(ex) else {
+        return;
+    };
+    remember(&state.memory, ex);
+    let tokenizer = Tokenizer::for_model(ex.model.as_ref());
+    let committed = find_mut(&mut state.exchanges, previous)
+        .map(|ex| std::mem::take(&mut ex.committed))
+        .unwrap_or_default();
+    // The user turn is the same line; only the answer changes. Turns that
+    // were since evicted or deleted stay gone.
+    if let Some(entry) = state
+        .context
+        .iter_mut()
+        .find(|e| committed.last() == Some(&e.id))
+    {
+        entry.message = assistant;
+        state.context_revision += 1;
+    }
+    if let Some(ex) = find_mut(&mut state.exchanges, id) {
+        ex.committed = committed;
+    }
+    enforce_limit(state, limit, tokenizer, summarizer);
+}
+
+/// The assistant turn of a completed exchange.
+fn assistant_turn(ex: &ExchangeView) -> Option<Message> {
+    let Response::Completed {
+        content,
+        tool_calls,
+    } = &ex.response
+    else {
+        return None;
+    };
+    Some(Message {
+        role: Role::Assistant,
+        content: Some(content.clone()),
+        tool_calls: (!tool_calls.is_empty()).then(|| tool_calls.clone()),
+        ..Default::default()
+    })
+}
+
+/// Remember a completed exchange's translation.
+fn remember(memory: &TranslationMemory, ex: &ExchangeView) {
+    let (
+        Some(key),
+        Response::Completed {
+            content,
+            tool_calls,
+        },
+    ) = (&ex.memory_key, &ex.response)
+    else {
+        return;
+    };
+    // Tool call round-trips are not replayable from memory.
+    if tool_calls.is_empty() && !content.is_empty() {
+        memory.insert(MemoryEntry::new(
+            key.clone(),
+            ex.user_message.content.clone().unwrap_or_default(),
+            content.clone(),
+        ));
+    }
+}
+
+/// Enforce the context limit, queueing evicted turns for the summarizer.
+/// Returns whether anything was evicted.
+fn enforce_limit(
+    state: &mut ChatState,
+    limit: ContextLimit,
+    tokenizer: Tokenizer,
+    summarizer: Option<Arc<TranslateConfig>>,
+) -> bool {
+    let evicted = enforce_context_limit(&mut state.context, limit, tokenizer);
+    let trimmed = !evicted.is_empty();
+    if trimmed {
+        state.context_revision += 1;
+    }
+    if let (Some(summarizer), true) = (summarizer, trimmed) {
+        state.evicted.extend(evicted);
+        state.summarizer = Some(summarizer);
+        state.summary_stalled = false;
+    }
+    trimmed
+}
+
 fn find_mut(exchanges: &mut [ExchangeView], id: ExchangeId) -> Option<&mut ExchangeView> {
     exchanges.iter_mut().find(|e| e.id == id)
 }
 
-fn build_prompt(state: &ChatState, config: &TranslateConfig, user: &Message) -> Vec<Message> {
-    let mut prompt = Vec::with_capacity(state.context.len() + 2);
-    prompt.push(Message {
+/// `context` is empty when the server already holds the context buffer.
+fn build_prompt<'a>(
+    config: &TranslateConfig,
+    context: impl Iterator<Item = &'a Message>,
+    user: &Message,
+) -> Vec<Message> {
+    let mut prompt = vec![Message {
         role: Role::System,
         content: Some(config.system_prompt.clone()),
         ..Default::default()
-    });
-    prompt.extend(state.context.iter().map(|e| e.message.clone()));
-    prompt.push(user.clone());
+    }];
+    prompt.extend(context.cloned());
+    // Sent in the user turn, ahead of the line, so that everything before it
+    // stays cacheable. APIs with a separate system prompt hoist system
+    // messages into it, which would change the prefix with every line.
+    let mut user = user.clone();
+    if !config.glossary.is_empty() && !config.glossary_in_prompt {
+        let line = user.content.unwrap_or_default();
+        user.content = Some(format!(
+            "{}\n\n{}",
+            glossary::prompt(&config.glossary),
+            line
+        ));
+    }
+    prompt.push(user);
     prompt
 }
 
 fn apply_edit(state: &mut ChatState, edit: ContextEdit) {
+    state.response_id = None;
+    state.context_revision += 1;
     let context = &mut state.context;
     match edit {
         ContextEdit::Insert { idx, message } => {
@@ -396,58 +1447,71 @@ fn apply_edit(state: &mut ChatState, edit: ContextEdit) {
     }
 }
 
-/// Trim the oldest non-pinned messages until token count is under
-/// `limits[0]`. Messages with a `name` set are treated as pinned.
-/// A trimmed message pulls along any following non-user messages so the
-/// remaining buffer always starts at a user turn.
-fn enforce_context_limit(context: &mut VecDeque<ContextMessage>, limits: &[u32; 2]) {
-    if count_tokens(context) <= limits[1] {
-        return;
+/// Once the prompt exceeds `limit.tokens[1]`, trim the oldest non-pinned
+/// messages until it is under `limit.tokens[0]`. Messages with a `name` set
+/// are treated as pinned. A trimmed message pulls along any following
+/// non-user messages so the remaining buffer always starts at a user turn.
+/// Returns the trimmed messages, oldest first.
+fn enforce_context_limit(
+    context: &mut VecDeque<ContextMessage>,
+    limit: ContextLimit,
+    tokenizer: Tokenizer,
+) -> Vec<Message> {
+    let mut evicted = Vec::new();
+    let mut tokens = limit.overhead + count_tokens(context, tokenizer);
+    if tokens <= limit.tokens[1] {
+        return evicted;
     }
     let mut idx = 0;
-    while count_tokens(context) > limits[0] && idx < context.len() {
+    while tokens > limit.tokens[0] && idx < context.len() {
         if context[idx].message.name.is_some() {
             idx += 1;
             continue;
         }
-        context.remove(idx);
-        while let Some(entry) = context.get(idx) {
-            if entry.message.role == Role::User {
+        while let Some(entry) = context.remove(idx) {
+            tokens = tokens.saturating_sub(tokenizer.count_message(&entry.message));
+            evicted.push(entry.message);
+            if context
+                .get(idx)
+                .is_none_or(|entry| entry.message.role == Role::User)
+            {
                 break;
             }
-            context.remove(idx);
         }
     }
+    evicted
 }
 
-fn count_tokens(context: &VecDeque<ContextMessage>) -> u32 {
-    context.iter().map(|e| e.message.estimate_tokens()).sum()
+fn count_tokens(context: &VecDeque<ContextMessage>, tokenizer: Tokenizer) -> u32 {
+    context
+        .iter()
+        .map(|e| tokenizer.count_message(&e.message))
+        .sum()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_adapter(
-    client: openai::Client,
+    client: ChatClient,
     config: Arc<TranslateConfig>,
     prompt: Vec<Message>,
+    previous_response_id: Option<String>,
     id: ExchangeId,
+    delay: Duration,
     cancel: CancellationToken,
     evt_tx: mpsc::Sender<ChatEvent>,
 ) {
     tokio::spawn(enclose! { (config) async move {
-        let req = chat::Request::builder()
-            .model(config.model.clone())
-            .messages(prompt)
-            .maybe_temperature(config.temperature)
-            .maybe_top_p(config.top_p)
-            .maybe_max_completion_tokens(config.max_tokens)
-            .maybe_presence_penalty(config.presence_penalty)
-            .maybe_service_tier(config.service_tier)
-            .maybe_reasoning_effort(config.reasoning_effort)
-            .maybe_verbosity(config.verbosity)
-            .build();
-
-        let max_ctx = config.max_context_tokens;
-        if config.stream {
-            let mut stream = match client.stream(req).await {
+        let limit = config.context_limit();
+        let mut stream = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                let _ = evt_tx.send(ChatEvent::Cancelled { id }).await;
+                return;
+            }
+            res = async {
+                tokio::time::sleep(delay).await;
+                client.stream(&config, prompt, previous_response_id.clone()).await
+            } => match res {
                 Ok(s) => s,
                 Err(err) => {
                     let _ = evt_tx.send(ChatEvent::Failed {
@@ -456,85 +1520,46 @@ fn spawn_adapter(
                     }).await;
                     return;
                 }
-            };
-            let mut usage = None;
-            loop {
-                tokio::select! {
-                    biased;
-                    _ = cancel.cancelled() => {
-                        let _ = evt_tx.send(ChatEvent::Cancelled { id }).await;
-                        return;
-                    }
-                    chunk = stream.next() => match chunk {
-                        Some(Ok(cmpl)) => {
-                            if let Some(u) = cmpl.usage { usage = Some(u); }
-                            for choice in cmpl.choices {
-                                if let Some(content) = choice.delta.content {
-                                    let _ = evt_tx.send(ChatEvent::Delta {
-                                        id,
-                                        content: content.replace('\n', ""),
-                                    }).await;
-                                }
-                                if let Some(calls) = choice.delta.tool_calls {
-                                    let _ = evt_tx.send(ChatEvent::ToolCallDelta {
-                                        id,
-                                        partials: calls,
-                                    }).await;
-                                }
-                            }
-                        }
-                        Some(Err(err)) => {
-                            let _ = evt_tx.send(ChatEvent::Failed {
-                                id,
-                                error: Arc::from(err.to_string()),
-                            }).await;
-                            return;
-                        }
-                        None => {
-                            let _ = evt_tx.send(ChatEvent::Completed {
-                                id, usage, max_context_tokens: max_ctx,
-                            }).await;
-                            return;
-                        }
-                    }
-                }
             }
-        } else {
+        };
+        let mut usage = None;
+        let mut response_id = None;
+        loop {
             tokio::select! {
                 biased;
                 _ = cancel.cancelled() => {
                     let _ = evt_tx.send(ChatEvent::Cancelled { id }).await;
+                    return;
                 }
-                res = client.chat(req) => match res {
-                    Ok(cmpl) => {
-                        let usage = Some(cmpl.usage.clone());
-                        if let Some(choice) = cmpl.choices.into_iter().next() {
-                            if let Some(content) = choice.message.content {
-                                let _ = evt_tx.send(ChatEvent::Delta { id, content }).await;
-                            }
-                            if let Some(calls) = choice.message.tool_calls {
-                                let partials = calls.into_iter().enumerate()
-                                    .map(|(i, call)| PartialToolCall {
-                                        index: i as u32,
-                                        id: Some(call.id),
-                                        kind: Some(call.kind),
-                                        function: Some(openai::chat::PartialFunctionCall {
-                                            name: Some(call.function.name),
-                                            arguments: Some(call.function.arguments),
-                                        }),
-                                    })
-                                    .collect();
-                                let _ = evt_tx.send(ChatEvent::ToolCallDelta { id, partials }).await;
-                            }
-                        }
-                        let _ = evt_tx.send(ChatEvent::Completed {
-                            id, usage, max_context_tokens: max_ctx,
-                        }).await;
+                delta = stream.next() => match delta {
+                    Some(Ok(ChatDelta::Content(content))) => {
+                        let _ = evt_tx.send(ChatEvent::Delta { id, content }).await;
                     }
-                    Err(err) => {
+                    Some(Ok(ChatDelta::Reasoning(reasoning))) => {
+                        let _ = evt_tx.send(ChatEvent::ReasoningDelta { id, reasoning }).await;
+                    }
+                    Some(Ok(ChatDelta::ToolCalls(partials))) => {
+                        let _ = evt_tx.send(ChatEvent::ToolCallDelta { id, partials }).await;
+                    }
+                    Some(Ok(ChatDelta::Usage(u))) => usage = Some(u),
+                    Some(Ok(ChatDelta::ResponseId(r))) => response_id = Some(r),
+                    Some(Err(err)) => {
                         let _ = evt_tx.send(ChatEvent::Failed {
-                            id, error: Arc::from(err.to_string()),
+                            id,
+                            error: Arc::from(err.to_string()),
                         }).await;
+                        return;
+                    }
+                    None => {
+                        let _ = evt_tx.send(ChatEvent::Completed {
+                            id,
+                            usage,
+                            limit,
+                            response_id,
+                            previous_response_id,
+                            summarizer: config.summarizer(),
+                        }).await;
+                        return;
                     }
                 }
             }
@@ -547,23 +1572,59 @@ fn spawn_adapter(
 /// here so `translate()` can return an `ExchangeId` synchronously.
 #[derive(Clone)]
 pub struct ChatHandle {
+    client: ChatClient,
     cmd_tx: mpsc::Sender<ChatCommand>,
     state: Arc<ArcSwap<ChatState>>,
     next_id: Arc<AtomicU64>,
 }
 
 impl ChatHandle {
+    /// Client of the main translator, for lanes to share its connections.
+    pub fn client(&self) -> &ChatClient {
+        &self.client
+    }
     pub fn state(&self) -> Arc<ChatState> {
         self.state.load_full()
     }
     fn send(&self, cmd: ChatCommand) {
         let _ = self.cmd_tx.try_send(cmd);
     }
+    fn mint_id(&self) -> ExchangeId {
+        ExchangeId(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
     pub fn translate(&self, text: String, config: Arc<TranslateConfig>) -> ExchangeId {
-        let id = ExchangeId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let id = self.mint_id();
         self.send(ChatCommand::Translate { id, text, config });
         id
     }
+    pub fn regenerate(&self, of: ExchangeId, config: Arc<TranslateConfig>) -> ExchangeId {
+        let id = self.mint_id();
+        self.send(ChatCommand::Regenerate { id, of, config });
+        id
+    }
+    pub fn choose(&self, id: ExchangeId, config: Arc<TranslateConfig>) {
+        self.send(ChatCommand::Choose { id, config });
+    }
+    /// Send `text` to every lane, returning the candidate ids in lane order.
+    pub fn compare(
+        &self,
+        text: String,
+        lanes: Vec<(ChatClient, Arc<TranslateConfig>)>,
+    ) -> Vec<ExchangeId> {
+        let lanes: Vec<_> = lanes
+            .into_iter()
+            .map(|(client, config)| (self.mint_id(), client, config))
+            .collect();
+        let ids = lanes.iter().map(|(id, _, _)| *id).collect();
+        self.send(ChatCommand::Compare { text, lanes });
+        ids
+    }
+    pub fn pick(&self, id: ExchangeId, config: Arc<TranslateConfig>) {
+        self.send(ChatCommand::Pick { id, config });
+    }
+    pub fn prefetch(&self, lines: Vec<(String, Arc<TranslateConfig>)>) {
+        self.send(ChatCommand::Prefetch { lines });
+    }
     pub fn cancel(&self, id: ExchangeId) {
         self.send(ChatCommand::Cancel(id));
     }
@@ -573,34 +1634,39 @@ impl ChatHandle {
     pub fn clear_context(&self) {
         self.send(ChatCommand::ClearContext);
     }
+    pub fn load_session(&self, name: Option<String>, context: Vec<Message>) {
+        self.send(ChatCommand::LoadSession { name, context });
+    }
     pub fn refresh_models(&self) {
         self.send(ChatCommand::RefreshModels);
     }
 }
 
 pub fn spawn(settings: &Settings) -> ChatHandle {
-    let client = openai::Client::new(
-        &settings.openai_api_key,
-        &settings.chat.api_endpoint,
-        ConnectionPolicy {
-            timeout: Duration::from_millis(settings.chat.timeout),
-            connect_timeout: Duration::from_millis(settings.chat.connection_timeout),
-        },
-    );
+    let client = ChatClient::new(settings);
     let (cmd_tx, mut cmd_rx) = mpsc::channel::<ChatCommand>(32);
     let (evt_tx, mut evt_rx) = mpsc::channel::<ChatEvent>(256);
-    let state = Arc::new(ArcSwap::from_pointee(ChatState::default()));
+    let initial = ChatState {
+        memory: Arc::new(TranslationMemory::open(&settings.chat.memory)),
+        usage_log: Arc::new(UsageLog::open(&settings.chat.budget.usage_path)),
+        ..Default::default()
+    };
+    let state = Arc::new(ArcSwap::from_pointee(initial.clone()));
+
+    let next_id = Arc::new(AtomicU64::new(0));
 
     let state_writer = state.clone();
+    let client_task = client.clone();
     let evt_tx_task = evt_tx.clone();
+    let next_id_task = next_id.clone();
     tokio::spawn(async move {
-        let mut local = ChatState::default();
+        let mut local = initial;
         let mut inflight: HashMap<ExchangeId, CancellationToken> = HashMap::new();
         loop {
             tokio::select! {
                 cmd = cmd_rx.recv() => match cmd {
                     Some(cmd) => {
-                        handle_command(cmd, &mut local, &client, &mut inflight, &evt_tx_task);
+                        handle_command(cmd, &mut local, &client_task, &mut inflight, &evt_tx_task);
                     }
                     None => break,
                 },
@@ -612,15 +1678,929 @@ pub fn spawn(settings: &Settings) -> ChatHandle {
             while let Ok(evt) = evt_rx.try_recv() {
                 reduce(&mut local, evt);
             }
+            summarize(
+                &mut local,
+                &client_task,
+                &mut inflight,
+                &evt_tx_task,
+                &next_id_task,
+            );
+            speculate(
+                &mut local,
+                &client_task,
+                &mut inflight,
+                &evt_tx_task,
+                &next_id_task,
+            );
             state_writer.store(Arc::new(local.clone()));
         }
     });
 
     let handle = ChatHandle {
+        client,
         cmd_tx,
         state,
-        next_id: Arc::new(AtomicU64::new(0)),
+        next_id,
     };
     handle.refresh_models();
     handle
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use openai::{
+        chat::FunctionDef,
+        mock::{chunk, MockResponse, MockServer},
+    };
+
+    use super::*;
+    use crate::{
+        settings::TranslatorType,
+        translator::{compare::CompareLane, template::PromptPreset},
+    };
+
+    const CHAT: &str = "v1/chat/completions";
+    const RESPONSES: &str = "v1/responses";
+
+    fn settings(server: &MockServer) -> Settings {
+        let mut settings = Settings::default();
+        settings.chat.api_endpoint = server.url();
+        settings.chat.model = ModelId(chunk::MODEL.into());
+        settings.chat.memory.path = String::new();
+        settings.chat.budget.usage_path = String::new();
+        settings
+    }
+
+    /// Poll the published state until the exchange reaches a terminal
+    /// response.
+    async fn settle(handle: &ChatHandle, id: ExchangeId) -> ExchangeView {
+        for _ in 0..500 {
+            if let Some(ex) = handle.state().exchange(id) {
+                if ex.response.is_terminal() {
+                    return ex.clone();
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("exchange {id:?} did not settle");
+    }
+
+    #[tokio::test]
+    async fn stream_translation_commits_to_context() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            CHAT,
+            MockResponse::stream([
+                chunk::role(),
+                chunk::content("Good\nmorning"),
+                chunk::content("."),
+                chunk::finish("stop"),
+                chunk::usage(20, 4),
+            ]),
+        );
+        let settings = settings(&server);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("おはよう".into(), config);
+
+        let ex = settle(&handle, id).await;
+        assert!(matches!(ex.response, Response::Completed { .. }));
+        // Line breaks in the answer are kept.
+        assert_eq!(ex.response.content(), "Good\nmorning.");
+        assert_eq!(ex.usage.as_ref().map(|u| u.total_tokens), Some(24));
+
+        let state = handle.state();
+        let context: Vec<_> = state.context.iter().map(|e| &e.message).collect();
+        assert_eq!(context.len(), 2);
+        assert_eq!(context[0].role, Role::User);
+        assert_eq!(context[0].content.as_deref(), Some("おはよう"));
+        assert_eq!(context[1].role, Role::Assistant);
+
+        let body = server.requests_to(CHAT)[0].json();
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(
+            body["messages"][0]["content"],
+            "You will translate the following visual novel script into English."
+        );
+        assert_eq!(body["messages"][1]["content"], "おはよう");
+    }
+
+    #[tokio::test]
+    async fn reasoning_is_kept_apart_from_the_answer() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            CHAT,
+            MockResponse::stream([
+                chunk::role(),
+                chunk::reasoning("A greeting.\n"),
+                chunk::reasoning("Keep it short.\n"),
+                chunk::content("\n\nHello."),
+                chunk::finish("stop"),
+                chunk::usage(20, 12),
+            ]),
+        );
+        let settings = settings(&server);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config);
+
+        let ex = settle(&handle, id).await;
+        assert_eq!(ex.reasoning, "A greeting.\nKeep it short.");
+        assert_eq!(ex.response.content(), "Hello.");
+        let state = handle.state();
+        assert_eq!(state.context[1].message.content.as_deref(), Some("Hello."));
+        assert!(state.context[1].message.reasoning_text().is_none());
+    }
+
+    #[tokio::test]
+    async fn blocking_translation_completes() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::completion("Hello."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config);
+
+        let ex = settle(&handle, id).await;
+        assert_eq!(ex.response.content(), "Hello.");
+        assert_eq!(ex.usage.as_ref().map(|u| u.total_tokens), Some(15));
+    }
+
+    #[tokio::test]
+    async fn memory_serves_repeated_line() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::completion("Hello."));
+        server.enqueue(CHAT, MockResponse::completion("Hi."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config.clone());
+        settle(&handle, id).await;
+
+        // Same line after the same (empty) preceding context.
+        handle.clear_context();
+        let id = handle.translate("こんにちは ".into(), config.clone());
+        let ex = settle(&handle, id).await;
+        assert!(ex.recalled);
+        assert_eq!(ex.response.content(), "Hello.");
+        assert!(ex.usage.is_none());
+        assert_eq!(server.requests_to(CHAT).len(), 1);
+        assert_eq!(handle.state().context.len(), 2);
+
+        // Regenerating skips the memory and replaces the recalled turns.
+        let id = handle.regenerate(id, config);
+        let ex = settle(&handle, id).await;
+        assert!(!ex.recalled);
+        assert_eq!(ex.response.content(), "Hi.");
+        assert_eq!(server.requests_to(CHAT).len(), 2);
+        let state = handle.state();
+        assert_eq!(state.context.len(), 2);
+        assert_eq!(state.context[1].message.content.as_deref(), Some("Hi."));
+        assert_eq!(state.memory.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn regenerated_attempts_are_alternates() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::error(400, "server_error", "Try again"));
+        server.enqueue(CHAT, MockResponse::completion("Hello."));
+        server.enqueue(CHAT, MockResponse::completion("Goodbye."));
+        server.enqueue(CHAT, MockResponse::completion("Hi."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+
+        // Retrying a failed line commits the new attempt.
+        let failed = handle.translate("こんにちは".into(), config.clone());
+        settle(&handle, failed).await;
+        assert!(handle.state().context.is_empty());
+        let first = handle.regenerate(failed, config.clone());
+        settle(&handle, first).await;
+        let id = handle.translate("さようなら".into(), config.clone());
+        settle(&handle, id).await;
+
+        // Regenerating sends the same context and replaces the answer in
+        // place, with a different temperature.
+        let hotter = Arc::new(TranslateConfig {
+            temperature: Some(1.5),
+            ..(*config).clone()
+        });
+        let second = handle.regenerate(first, hotter);
+        let ex = settle(&handle, second).await;
+        assert_eq!(ex.attempt_of, Some(failed));
+        let body = server.requests_to(CHAT)[3].json();
+        assert_eq!(body["temperature"], 1.5);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(body["messages"][1]["content"], "こんにちは");
+
+        let state = handle.state();
+        let alternates: Vec<ExchangeId> = state.alternates(first).iter().map(|ex| ex.id).collect();
+        assert_eq!(alternates, [failed, first, second]);
+        let contents: Vec<_> = state
+            .context
+            .iter()
+            .map(|e| e.message.content.as_deref().unwrap())
+            .collect();
+        assert_eq!(contents, ["こんにちは", "Hi.", "さようなら", "Goodbye."]);
+        assert!(state.exchange(first).unwrap().committed.is_empty());
+        assert_eq!(state.exchange(second).unwrap().committed.len(), 2);
+
+        // Choosing an earlier attempt puts it back.
+        handle.choose(first, config);
+        for _ in 0..500 {
+            if handle.state().context[1].message.content.as_deref() == Some("Hello.") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let state = handle.state();
+        assert_eq!(state.context.len(), 4);
+        assert_eq!(state.context[1].message.content.as_deref(), Some("Hello."));
+        assert!(state.exchange(second).unwrap().committed.is_empty());
+        assert_eq!(state.exchange(first).unwrap().committed.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn glossary_is_sent_and_checked() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::completion("Hello, Takana."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        let handle = spawn(&settings);
+        let mut config = TranslateConfig::from_settings(&settings);
+        config.glossary = vec![GlossaryTerm {
+            term: "田中".into(),
+            translation: "Tanaka".into(),
+            ..Default::default()
+        }];
+        let id = handle.translate("田中さん、こんにちは".into(), Arc::new(config));
+
+        let ex = settle(&handle, id).await;
+        assert_eq!(ex.ignored_terms.len(), 1);
+        let body = server.requests_to(CHAT)[0].json();
+        assert_eq!(body["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(body["messages"][1]["role"], "user");
+        assert_eq!(
+            body["messages"][1]["content"],
+            "Use these glossary translations in the next line:\n- 田中 → Tanaka\n\n\
+             田中さん、こんにちは"
+        );
+        // The glossary is not committed to the context.
+        let state = handle.state();
+        assert_eq!(state.context.len(), 2);
+        assert_eq!(
+            state.context[0].message.content.as_deref(),
+            Some("田中さん、こんにちは")
+        );
+    }
+
+    #[tokio::test]
+    async fn prompt_template_is_rendered() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::completion("Hello, Tanaka."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        settings.chat.game_title = "Ever17".into();
+        settings.chat.prompt_presets.push(PromptPreset {
+            name: "test".into(),
+            template: "{{game_title}} into {{target_language}}.\n{{glossary}}".into(),
+        });
+        settings.chat.prompt_preset = Some("test".into());
+        let handle = spawn(&settings);
+        let mut config = TranslateConfig::from_settings(&settings);
+        assert_eq!(config.system_prompt, "Ever17 into English.\n");
+        config.glossary = vec![GlossaryTerm {
+            term: "田中".into(),
+            translation: "Tanaka".into(),
+            ..Default::default()
+        }];
+        let variables = Variables {
+            game_title: settings.chat.game_title.clone(),
+            target_language: settings.chat.target_language.clone(),
+            glossary: glossary::prompt(&config.glossary),
+            ..Default::default()
+        };
+        config.set_prompt(settings.chat.prompt_template(), &variables);
+        let id = handle.translate("田中さん".into(), Arc::new(config));
+
+        settle(&handle, id).await;
+        let body = server.requests_to(CHAT)[0].json();
+        assert_eq!(
+            body["messages"][0]["content"],
+            "Ever17 into English.\n\
+             Use these glossary translations in the next line:\n- 田中 → Tanaka"
+        );
+        // Already in the system prompt, so not sent next to the line.
+        assert_eq!(body["messages"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn target_language_is_remembered_separately() {
+        let mut settings = Settings::default();
+        let english = TranslateConfig::from_settings(&settings);
+        settings.chat.target_language = "Korean".into();
+        settings.chat.summary.enabled = true;
+        let korean = TranslateConfig::from_settings(&settings);
+        assert!(korean.system_prompt.ends_with("into Korean."));
+        let summarizer = korean.summarizer().unwrap();
+        assert!(summarizer.system_prompt.contains("only, in Korean,"));
+        let key = |config| key_for(std::iter::empty(), config, "猫です");
+        assert_eq!(key(&english).target_language, "English");
+        assert_ne!(key(&english), key(&korean));
+    }
+
+    /// Poll the published state until the summary message reads `expected`.
+    async fn await_summary(handle: &ChatHandle, expected: &str) {
+        for _ in 0..500 {
+            let state = handle.state();
+            if summary::current(state.context.iter().map(|e| &e.message)) == Some(expected) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("summary did not become {expected:?}");
+    }
+
+    #[tokio::test]
+    async fn evicted_turns_are_summarized() {
+        let server = MockServer::start().await;
+        for reply in [
+            "Good morning.",
+            "They met.",
+            "Good night.",
+            "They met and parted.",
+        ] {
+            server.enqueue(CHAT, MockResponse::completion(reply));
+        }
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        settings.chat.max_context_tokens = [1, 1];
+        settings.chat.summary.enabled = true;
+        settings.chat.summary.model = Some(ModelId("summarizer".into()));
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+
+        let id = handle.translate("おはよう".into(), config.clone());
+        settle(&handle, id).await;
+        await_summary(&handle, "They met.").await;
+        let state = handle.state();
+        assert_eq!(state.context.len(), 1);
+        assert_eq!(
+            state.context[0].message.name.as_deref(),
+            Some(summary::NAME)
+        );
+
+        let id = handle.translate("おやすみ".into(), config);
+        settle(&handle, id).await;
+        await_summary(&handle, "They met and parted.").await;
+        let state = handle.state();
+        assert_eq!(state.context.len(), 1);
+        assert!(!state.summarizing);
+        // Summaries are exchanges of their own, so budgets see them.
+        assert_eq!(state.exchanges.iter().filter(|ex| ex.summary).count(), 2);
+
+        let requests = server.requests_to(CHAT);
+        let first_summary = requests[1].json();
+        assert_eq!(first_summary["model"], "summarizer");
+        let prompt = first_summary["messages"][0]["content"].as_str().unwrap();
+        assert!(prompt
+            .ends_with("Reply with the updated summary only, in English, in under 150 words."));
+        let request = first_summary["messages"][1]["content"].as_str().unwrap();
+        assert!(request.contains("Original: おはよう\nTranslation: Good morning."));
+        // The pinned summary is sent with the next translation...
+        let translation = requests[2].json();
+        assert_eq!(
+            translation["messages"][1]["content"],
+            "Story so far: They met."
+        );
+        assert_eq!(translation["messages"][2]["content"], "おやすみ");
+        // ...and merged with the next evicted turns.
+        let request = requests[3].json()["messages"][1]["content"].clone();
+        assert!(request
+            .as_str()
+            .unwrap()
+            .starts_with("Summary so far:\nThey met."));
+    }
+
+    #[tokio::test]
+    async fn failed_summary_keeps_evicted_turns() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::completion("Good morning."));
+        server.enqueue(
+            CHAT,
+            MockResponse::error(400, "invalid_request_error", "Bad request"),
+        );
+        server.enqueue(CHAT, MockResponse::completion("Good night."));
+        server.enqueue(CHAT, MockResponse::completion("They met and parted."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        settings.chat.max_context_tokens = [1, 1];
+        settings.chat.summary.enabled = true;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+
+        let id = handle.translate("おはよう".into(), config.clone());
+        settle(&handle, id).await;
+        for _ in 0..500 {
+            if !handle.state().summarizing {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let state = handle.state();
+        assert!(state
+            .exchanges
+            .iter()
+            .any(|ex| ex.summary && matches!(ex.response, Response::Errored(_))));
+        assert_eq!(state.evicted.len(), 2);
+
+        // The failed turns are summarized along with the next evicted ones.
+        let id = handle.translate("おやすみ".into(), config);
+        settle(&handle, id).await;
+        await_summary(&handle, "They met and parted.").await;
+        assert!(handle.state().evicted.is_empty());
+        let requests = server.requests_to(CHAT);
+        assert_eq!(requests.len(), 4);
+        let request = requests[3].json()["messages"][1]["content"].clone();
+        let request = request.as_str().unwrap();
+        assert!(request.contains("Original: おはよう\nTranslation: Good morning."));
+        assert!(request.contains("Original: おやすみ\nTranslation: Good night."));
+    }
+
+    #[tokio::test]
+    async fn exhausted_budget_blocks_translation() {
+        let server = MockServer::start().await;
+        server.enqueue(CHAT, MockResponse::completion("Hello."));
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        settings.chat.budget.tokens_per_session = Some(10);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config.clone());
+        settle(&handle, id).await;
+
+        let id = handle.translate("こんばんは".into(), config);
+        let ex = settle(&handle, id).await;
+        let Response::Blocked(reason) = &ex.response else {
+            panic!("unexpected response: {:?}", ex.response);
+        };
+        assert!(reason.contains("token budget"));
+        assert_eq!(server.requests_to(CHAT).len(), 1);
+        assert!(handle.state().last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn http_error_fails_exchange() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            CHAT,
+            MockResponse::error(401, "invalid_api_key", "Incorrect API key"),
+        );
+        let settings = settings(&server);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config);
+
+        let ex = settle(&handle, id).await;
+        let Response::Errored(err) = &ex.response else {
+            panic!("unexpected response: {:?}", ex.response);
+        };
+        assert!(err.contains("Incorrect API key"));
+        assert!(handle.state().last_error.is_some());
+        assert!(handle.state().context.is_empty());
+    }
+
+    #[tokio::test]
+    async fn malformed_event_fails_exchange() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            CHAT,
+            MockResponse::events([chunk::content("a").to_string(), "{oops".to_string()]),
+        );
+        let settings = settings(&server);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config);
+
+        let ex = settle(&handle, id).await;
+        assert!(matches!(ex.response, Response::Errored(_)));
+        assert!(handle.state().context.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_slow_stream() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            CHAT,
+            MockResponse::stream((0..50).map(|_| chunk::content("x")))
+                .with_interval(Duration::from_millis(50)),
+        );
+        let settings = settings(&server);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("こんにちは".into(), config);
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        handle.cancel(id);
+
+        let ex = settle(&handle, id).await;
+        assert!(matches!(ex.response, Response::Cancelled));
+        assert!(handle.state().context.is_empty());
+    }
+
+    #[test]
+    fn context_limit_counts_with_model_tokenizer() {
+        let mut state = ChatState::default();
+        for (role, content) in [
+            (Role::User, "猫"),
+            (Role::Assistant, "cat"),
+            (Role::User, "犬"),
+            (Role::Assistant, "dog"),
+        ] {
+            state.push_back(Message {
+                role,
+                content: Some(content.into()),
+                ..Default::default()
+            });
+        }
+        // Four tokens of framing plus one of content per message.
+        assert_eq!(state.context_tokens(Tokenizer::Heuristic), 20);
+        let limit = ContextLimit {
+            tokens: [10, 15],
+            overhead: 0,
+        };
+        let evicted = enforce_context_limit(&mut state.context, limit, Tokenizer::Heuristic);
+        assert_eq!(evicted.len(), 2);
+        assert_eq!(evicted[1].content.as_deref(), Some("cat"));
+        assert_eq!(state.context.len(), 2);
+        assert_eq!(state.context[0].message.content.as_deref(), Some("犬"));
+        assert!(enforce_context_limit(&mut state.context, limit, Tokenizer::Heuristic).is_empty());
+
+        // The system prompt and tools count against the limit too.
+        let limit = ContextLimit {
+            tokens: [10, 15],
+            overhead: 8,
+        };
+        let evicted = enforce_context_limit(&mut state.context, limit, Tokenizer::Heuristic);
+        assert_eq!(evicted.len(), 2);
+        assert!(state.context.is_empty());
+    }
+
+    #[test]
+    fn old_exchanges_drop_their_context() {
+        let mut state = ChatState::default();
+        let context: Arc<[Message]> = Arc::from([Message::default()]);
+        for id in 0..=REGENERATE_HISTORY {
+            reduce(
+                &mut state,
+                ChatEvent::Started {
+                    id: ExchangeId(id as u64),
+                    model: ModelId::default(),
+                    user_message: Message::default(),
+                    recalled: false,
+                    memory_key: None,
+                    glossary: Vec::new(),
+                    summary: false,
+                    candidate: false,
+                    prefetched: false,
+                    context: context.clone(),
+                    attempt_of: None,
+                },
+            );
+        }
+        assert!(state.regenerate_context(ExchangeId(0)).is_none());
+        assert!(state.regenerate_context(ExchangeId(1)).is_some());
+    }
+
+    #[test]
+    fn cleared_context_drops_summary_in_flight() {
+        let mut state = ChatState::default();
+        let mut inflight = HashMap::new();
+        let id = ExchangeId(0);
+        reduce(
+            &mut state,
+            ChatEvent::Started {
+                id,
+                model: ModelId::default(),
+                user_message: Message::default(),
+                recalled: false,
+                memory_key: None,
+                glossary: Vec::new(),
+                summary: true,
+                candidate: false,
+                prefetched: false,
+                context: Arc::from([]),
+                attempt_of: None,
+            },
+        );
+        let cancel = CancellationToken::new();
+        inflight.insert(id, cancel.clone());
+        state.summarizing = true;
+        state.summarized = 2;
+        state.evicted = vec![Message::default(), Message::default()];
+
+        cancel_summary(&mut state, &mut inflight);
+        state.context.clear();
+        assert!(cancel.is_cancelled());
+        assert!(!state.summarizing && !state.summary_stalled);
+        assert!(state.evicted.is_empty());
+
+        // A completion that raced the cancellation is dropped.
+        reduce(
+            &mut state,
+            ChatEvent::Delta {
+                id,
+                content: "They met.".into(),
+            },
+        );
+        reduce(
+            &mut state,
+            ChatEvent::Completed {
+                id,
+                usage: None,
+                limit: ContextLimit {
+                    tokens: [10, 15],
+                    overhead: 0,
+                },
+                response_id: None,
+                previous_response_id: None,
+                summarizer: None,
+            },
+        );
+        assert!(state.context.is_empty());
+        assert!(!state.summarizing && !state.summary_stalled);
+    }
+
+    #[test]
+    fn context_revision_tracks_buffer_changes() {
+        let mut state = ChatState::default();
+        state.push_back(Message {
+            role: Role::User,
+            content: Some("猫".into()),
+            ..Default::default()
+        });
+        let revision = state.context_revision;
+        assert!(revision > 0);
+        reduce(
+            &mut state,
+            ChatEvent::Started {
+                id: ExchangeId(0),
+                model: ModelId::default(),
+                user_message: Message::default(),
+                recalled: false,
+                memory_key: None,
+                glossary: Vec::new(),
+                summary: false,
+                candidate: false,
+                prefetched: false,
+                context: Arc::from([]),
+                attempt_of: None,
+            },
+        );
+        reduce(
+            &mut state,
+            ChatEvent::Delta {
+                id: ExchangeId(0),
+                content: "cat".into(),
+            },
+        );
+        assert_eq!(state.context_revision, revision);
+        apply_edit(&mut state, ContextEdit::Delete(0));
+        assert!(state.context_revision > revision);
+    }
+
+    #[test]
+    fn context_limit_includes_prompt_and_tools() {
+        let mut config = TranslateConfig::from_settings(&Settings::default());
+        config.model = ModelId("local-model".into());
+        config.system_prompt = "Translate.".into();
+        let bare = config.context_limit().overhead;
+        assert!(bare > 0);
+        config.tools = vec![Tool::function(FunctionDef {
+            name: "lookup".into(),
+            description: Some("Look up a term".into()),
+            parameters: None,
+            strict: None,
+        })];
+        assert!(config.context_limit().overhead > bare);
+    }
+
+    /// A streamed Responses API reply with the given id and text.
+    fn response_stream(id: &str, text: &str) -> MockResponse {
+        let response = |status: &str| {
+            serde_json::json!({
+                "id": id, "object": "response", "status": status, "model": chunk::MODEL,
+                "output": [], "usage": {"input_tokens": 10, "output_tokens": 2, "total_tokens": 12},
+            })
+        };
+        MockResponse::events(
+            [
+                serde_json::json!({"type": "response.created", "response": response("in_progress")}),
+                serde_json::json!({"type": "response.output_text.delta", "item_id": "msg_1",
+                    "output_index": 0, "content_index": 0, "delta": text}),
+                serde_json::json!({"type": "response.completed", "response": response("completed")}),
+            ]
+            .map(|event| format!("event: {}\ndata: {}", event["type"].as_str().unwrap(), event)),
+        )
+    }
+
+    #[tokio::test]
+    async fn responses_translation_chains_server_side() {
+        let server = MockServer::start().await;
+        server.enqueue(RESPONSES, response_stream("resp_1", "Good morning."));
+        server.enqueue(RESPONSES, response_stream("resp_2", "Good night."));
+        server.enqueue(RESPONSES, response_stream("resp_3", "Hello."));
+        let mut settings = settings(&server);
+        settings.translator_type = TranslatorType::Responses;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+
+        let id = handle.translate("おはよう".into(), config.clone());
+        let ex = settle(&handle, id).await;
+        assert_eq!(ex.response.content(), "Good morning.");
+        assert_eq!(handle.state().response_id.as_deref(), Some("resp_1"));
+
+        let id = handle.translate("おやすみ".into(), config.clone());
+        settle(&handle, id).await;
+        assert_eq!(handle.state().response_id.as_deref(), Some("resp_2"));
+        assert_eq!(handle.state().context.len(), 4);
+
+        // Editing the buffer breaks the chain; the next request resends it.
+        handle.edit_context(ContextEdit::Delete(0));
+        let id = handle.translate("こんにちは".into(), config);
+        settle(&handle, id).await;
+
+        let prompt = "You will translate the following visual novel script into English.";
+        let requests = server.requests_to(RESPONSES);
+        let first = requests[0].json();
+        assert_eq!(first["instructions"], prompt);
+        assert!(first.get("previous_response_id").is_none());
+        let second = requests[1].json();
+        assert_eq!(second["previous_response_id"], "resp_1");
+        assert_eq!(second["instructions"], prompt);
+        assert_eq!(second["input"].as_array().unwrap().len(), 1);
+        assert_eq!(second["input"][0]["content"], "おやすみ");
+        let third = requests[2].json();
+        assert!(third.get("previous_response_id").is_none());
+        assert_eq!(third["input"].as_array().unwrap().len(), 4);
+        assert_eq!(handle.state().response_id.as_deref(), Some("resp_3"));
+    }
+
+    #[tokio::test]
+    async fn loading_session_replaces_context() {
+        let server = MockServer::start().await;
+        server.enqueue(RESPONSES, response_stream("resp_1", "Good morning."));
+        let mut settings = settings(&server);
+        settings.translator_type = TranslatorType::Responses;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let id = handle.translate("おはよう".into(), config);
+        settle(&handle, id).await;
+        assert!(handle.state().response_id.is_some());
+
+        let info = Message {
+            role: Role::User,
+            content: Some("雪菜は幼馴染".into()),
+            name: Some("info".into()),
+            ..Default::default()
+        };
+        handle.load_session(Some("route-b".into()), vec![info]);
+        for _ in 0..500 {
+            if handle.state().session.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let state = handle.state();
+        assert_eq!(state.session.as_deref(), Some("route-b"));
+        assert_eq!(state.context.len(), 1);
+        assert_eq!(state.context[0].message.name.as_deref(), Some("info"));
+        assert!(state.response_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn comparison_commits_only_the_pick() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            CHAT,
+            MockResponse::stream([
+                chunk::role(),
+                chunk::content("Hello."),
+                chunk::finish("stop"),
+                chunk::usage(20, 2),
+            ]),
+        );
+        server.enqueue(RESPONSES, response_stream("resp_1", "Hi."));
+        let settings = settings(&server);
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        let lanes = [TranslatorType::Chat, TranslatorType::Responses].map(|translator_type| {
+            let lane = CompareLane {
+                model: settings.chat.model.clone(),
+                translator_type,
+                ..Default::default()
+            };
+            (handle.client().for_lane(&settings, &lane), config.clone())
+        });
+        let ids = handle.compare("こんにちは".into(), Vec::from(lanes));
+
+        let chat = settle(&handle, ids[0]).await;
+        let responses = settle(&handle, ids[1]).await;
+        assert_eq!(chat.response.content(), "Hello.");
+        assert_eq!(responses.response.content(), "Hi.");
+        assert!(chat.candidate && chat.first_token.is_some() && chat.finished.is_some());
+        assert!(handle.state().context.is_empty());
+
+        handle.pick(ids[1], config);
+        for _ in 0..500 {
+            if !handle.state().context.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let state = handle.state();
+        assert_eq!(state.context.len(), 2);
+        assert_eq!(state.context[1].message.content.as_deref(), Some("Hi."));
+        assert_eq!(state.exchange(ids[1]).unwrap().committed.len(), 2);
+        assert!(state.exchange(ids[0]).unwrap().committed.is_empty());
+        // Candidates never extend the server-side chain.
+        assert!(state.response_id.is_none());
+        assert_eq!(state.memory.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn prefetched_lines_are_served_in_order() {
+        let server = MockServer::start().await;
+        for reply in ["Hello.", "Bye."] {
+            server.enqueue(CHAT, MockResponse::completion(reply));
+        }
+        let mut settings = settings(&server);
+        settings.chat.stream = false;
+        let handle = spawn(&settings);
+        let config = Arc::new(TranslateConfig::from_settings(&settings));
+        handle.prefetch(vec![
+            ("おはよう".into(), config.clone()),
+            ("さようなら".into(), config.clone()),
+        ]);
+        for _ in 0..500 {
+            let state = handle.state();
+            if state.speculation.len() == 2
+                && state
+                    .speculation
+                    .iter()
+                    .all(|&id| state.exchange(id).unwrap().response.is_terminal())
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // Nothing is committed until the lines are shown.
+        assert!(handle.state().context.is_empty());
+        let requests = server.requests_to(CHAT);
+        assert_eq!(requests.len(), 2);
+        // The second line sees the first as if it had been committed.
+        let messages = requests[1].json()["messages"].clone();
+        let contents: Vec<_> = messages
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["content"].as_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            contents[1..],
+            ["おはよう", "Hello.", "さようなら"].map(String::from)
+        );
+
+        let id = handle.translate("おはよう".into(), config.clone());
+        let ex = settle(&handle, id).await;
+        assert_eq!(ex.response.content(), "Hello.");
+        assert!(ex.prefetched);
+        assert_eq!(handle.state().context.len(), 2);
+
+        let id = handle.translate("さようなら".into(), config);
+        let ex = settle(&handle, id).await;
+        assert_eq!(ex.response.content(), "Bye.");
+        let state = handle.state();
+        assert_eq!(state.context.len(), 4);
+        assert_eq!(state.context[3].message.content.as_deref(), Some("Bye."));
+        assert!(state.speculation.is_empty());
+        assert_eq!(server.requests_to(CHAT).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn refresh_models_populates_state() {
+        let server = MockServer::start().await;
+        let handle = spawn(&settings(&server));
+        for _ in 0..500 {
+            if !handle.state().models.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(handle.state().models, vec![ModelId(chunk::MODEL.into())]);
+    }
+}
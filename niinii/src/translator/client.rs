@@ -0,0 +1,495 @@
+//! Protocol-agnostic chat client for the translator adapter.
+//!
+//! [`ChatClient`] hides which wire protocol a backend speaks. Every request,
+//! streaming or not, comes back as a stream of [`ChatDelta`]s so the adapter
+//! has a single code path.
+//!
+//! The Responses backend is stateful: instead of resending the context buffer
+//! it continues from the previous response stored server-side. See
+//! [`ChatClient::is_stateful`].
+
+use std::time::Duration;
+
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use openai::{
+    chat::{self, Message, PartialFunctionCall, PartialToolCall, ToolCallKind, Usage},
+    messages, responses, ConnectionPolicy, ModelId,
+};
+
+use crate::{
+    settings::{Settings, TranslatorType},
+    translator::{chat::TranslateConfig, compare::CompareLane},
+};
+
+/// Default `max_tokens` for the Messages API, which requires one.
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+/// One increment of an assistant turn.
+#[derive(Debug, Clone)]
+pub enum ChatDelta {
+    Content(String),
+    /// Reasoning or a summary of it, shown apart from the answer.
+    Reasoning(String),
+    ToolCalls(Vec<PartialToolCall>),
+    /// Cumulative usage so far. Later reports supersede earlier ones.
+    Usage(Usage),
+    /// Server-side id of the response, for chaining the next request.
+    ResponseId(String),
+}
+
+pub type DeltaStream<'a> = BoxStream<'a, Result<ChatDelta, openai::Error>>;
+
+#[derive(Clone)]
+pub enum ChatClient {
+    /// OpenAI Chat Completions (`/v1/chat/completions`).
+    Chat(openai::Client),
+    /// Anthropic Messages (`/v1/messages`).
+    Messages(openai::Client),
+    /// OpenAI Responses (`/v1/responses`).
+    Responses(openai::Client),
+}
+
+impl ChatClient {
+    pub fn new(settings: &Settings) -> Self {
+        let c = &settings.chat;
+        let policy = ConnectionPolicy {
+            timeout: Duration::from_millis(c.timeout),
+            connect_timeout: Duration::from_millis(c.connection_timeout),
+        };
+        match settings.translator_type {
+            TranslatorType::Chat => Self::Chat(openai::Client::new(
+                &settings.openai_api_key,
+                &c.api_endpoint,
+                policy,
+            )),
+            TranslatorType::Responses => Self::Responses(openai::Client::new(
+                &settings.openai_api_key,
+                &c.api_endpoint,
+                policy,
+            )),
+            TranslatorType::Messages => Self::Messages(openai::Client::anthropic(
+                &settings.anthropic_api_key,
+                &c.anthropic_api_endpoint,
+                policy,
+            )),
+        }
+    }
+
+    /// Client for a comparison lane. Credentials are those of the main
+    /// translator, and the connection pool and timeouts are shared with it.
+    pub fn for_lane(&self, settings: &Settings, lane: &CompareLane) -> Self {
+        let c = &settings.chat;
+        let base = match self {
+            Self::Chat(client) | Self::Messages(client) | Self::Responses(client) => client,
+        };
+        let endpoint = lane.endpoint(c);
+        match lane.translator_type {
+            TranslatorType::Chat => Self::Chat(base.sibling(&settings.openai_api_key, endpoint)),
+            TranslatorType::Responses => {
+                Self::Responses(base.sibling(&settings.openai_api_key, endpoint))
+            }
+            TranslatorType::Messages => {
+                Self::Messages(base.anthropic_sibling(&settings.anthropic_api_key, endpoint))
+            }
+        }
+    }
+
+    pub async fn models(&self) -> Result<Vec<ModelId>, openai::Error> {
+        match self {
+            Self::Chat(client) | Self::Messages(client) | Self::Responses(client) => {
+                client.models().await
+            }
+        }
+    }
+
+    /// Whether the server keeps conversation state. If so, a request that
+    /// continues from `previous_response_id` only needs the new turn.
+    pub fn is_stateful(&self) -> bool {
+        matches!(self, Self::Responses(_))
+    }
+
+    /// Issue one request for `prompt`. When `config.stream` is unset the
+    /// complete response is replayed as a short stream.
+    /// `previous_response_id` is ignored by stateless backends.
+    pub async fn stream(
+        &self,
+        config: &TranslateConfig,
+        prompt: Vec<Message>,
+        previous_response_id: Option<String>,
+    ) -> Result<DeltaStream<'_>, openai::Error> {
+        match self {
+            Self::Chat(client) => chat_stream(client, config, prompt).await,
+            Self::Messages(client) => messages_stream(client, config, prompt).await,
+            Self::Responses(client) => {
+                responses_stream(client, config, prompt, previous_response_id).await
+            }
+        }
+    }
+}
+
+async fn chat_stream<'a>(
+    client: &'a openai::Client,
+    config: &TranslateConfig,
+    prompt: Vec<Message>,
+) -> Result<DeltaStream<'a>, openai::Error> {
+    let req = chat::Request::builder()
+        .model(config.model.clone())
+        .messages(prompt)
+        .maybe_temperature(config.temperature)
+        .maybe_top_p(config.top_p)
+        .maybe_max_completion_tokens(config.max_tokens)
+        .maybe_presence_penalty(config.presence_penalty)
+        .maybe_service_tier(config.service_tier)
+        .maybe_reasoning_effort(config.reasoning_effort)
+        .maybe_verbosity(config.verbosity)
+        .maybe_tools((!config.tools.is_empty()).then(|| config.tools.clone()))
+        .maybe_tool_choice(config.tool_choice.clone())
+        .build();
+
+    if config.stream {
+        let stream = client.stream(req).await?;
+        Ok(stream
+            .flat_map(|chunk| {
+                let deltas = match chunk {
+                    Ok(cmpl) => {
+                        let mut deltas = Vec::new();
+                        for mut choice in cmpl.choices {
+                            if let Some(reasoning) = choice.delta.take_reasoning() {
+                                deltas.push(Ok(ChatDelta::Reasoning(reasoning)));
+                            }
+                            if let Some(content) = choice.delta.content {
+                                deltas.push(Ok(ChatDelta::Content(content)));
+                            }
+                            if let Some(calls) = choice.delta.tool_calls {
+                                deltas.push(Ok(ChatDelta::ToolCalls(calls)));
+                            }
+                        }
+                        if let Some(usage) = cmpl.usage {
+                            deltas.push(Ok(ChatDelta::Usage(usage)));
+                        }
+                        deltas
+                    }
+                    Err(err) => vec![Err(err)],
+                };
+                stream::iter(deltas)
+            })
+            .boxed())
+    } else {
+        let cmpl = client.chat(req).await?;
+        let mut deltas = Vec::new();
+        if let Some(mut choice) = cmpl.choices.into_iter().next() {
+            if let Some(reasoning) = choice.message.take_reasoning() {
+                deltas.push(Ok(ChatDelta::Reasoning(reasoning)));
+            }
+            if let Some(content) = choice.message.content {
+                deltas.push(Ok(ChatDelta::Content(content)));
+            }
+            if let Some(calls) = choice.message.tool_calls {
+                let partials = calls
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, call)| PartialToolCall {
+                        index: i as u32,
+                        id: Some(call.id),
+                        kind: Some(call.kind),
+                        function: Some(PartialFunctionCall {
+                            name: Some(call.function.name),
+                            arguments: Some(call.function.arguments),
+                        }),
+                    })
+                    .collect();
+                deltas.push(Ok(ChatDelta::ToolCalls(partials)));
+            }
+        }
+        deltas.push(Ok(ChatDelta::Usage(cmpl.usage)));
+        Ok(stream::iter(deltas).boxed())
+    }
+}
+
+/// Build a Messages request from the Chat Completions-shaped prompt. Cache
+/// breakpoints go on the system prompt and on the last context turn so the
+/// growing context buffer is served from the prompt cache.
+fn messages_request(config: &TranslateConfig, prompt: Vec<Message>) -> messages::Request {
+    let (mut system, mut turns) = messages::from_chat_messages(prompt);
+    if let Some(block) = system.last_mut() {
+        block.set_cache_control(messages::CacheControl::Ephemeral);
+    }
+    if turns.len() >= 2 {
+        let idx = turns.len() - 2;
+        if let Some(block) = turns[idx].content.last_mut() {
+            block.set_cache_control(messages::CacheControl::Ephemeral);
+        }
+    }
+    messages::Request::builder()
+        .model(config.model.clone())
+        .messages(turns)
+        .system(system)
+        .max_tokens(config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS))
+        .maybe_temperature(config.temperature)
+        .maybe_top_p(config.top_p)
+        .maybe_tools(
+            (!config.tools.is_empty())
+                .then(|| config.tools.iter().cloned().map(Into::into).collect()),
+        )
+        .maybe_tool_choice(config.tool_choice.clone().map(Into::into))
+        .build()
+}
+
+async fn messages_stream<'a>(
+    client: &'a openai::Client,
+    config: &TranslateConfig,
+    prompt: Vec<Message>,
+) -> Result<DeltaStream<'a>, openai::Error> {
+    let req = messages_request(config, prompt);
+
+    if config.stream {
+        let stream = client.messages_stream(req).await?;
+        let mut usage = messages::Usage::default();
+        Ok(stream
+            .map(move |event| {
+                let delta = match event {
+                    Ok(messages::StreamEvent::MessageStart { message }) => {
+                        usage.merge(&message.usage);
+                        None
+                    }
+                    Ok(messages::StreamEvent::ContentBlockStart {
+                        index,
+                        content_block,
+                    }) => match content_block {
+                        messages::ContentBlock::Text { text, .. } if !text.is_empty() => {
+                            Some(ChatDelta::Content(text))
+                        }
+                        messages::ContentBlock::Thinking { thinking, .. }
+                            if !thinking.is_empty() =>
+                        {
+                            Some(ChatDelta::Reasoning(thinking))
+                        }
+                        messages::ContentBlock::ToolUse { id, name, .. } => {
+                            Some(ChatDelta::ToolCalls(vec![PartialToolCall {
+                                index,
+                                id: Some(id),
+                                kind: Some(ToolCallKind::Function),
+                                function: Some(PartialFunctionCall {
+                                    name: Some(name),
+                                    arguments: Some(String::new()),
+                                }),
+                            }]))
+                        }
+                        _ => None,
+                    },
+                    Ok(messages::StreamEvent::ContentBlockDelta { index, delta }) => match delta {
+                        messages::Delta::TextDelta { text } => Some(ChatDelta::Content(text)),
+                        messages::Delta::ThinkingDelta { thinking } => {
+                            Some(ChatDelta::Reasoning(thinking))
+                        }
+                        messages::Delta::InputJsonDelta { partial_json } => {
+                            Some(ChatDelta::ToolCalls(vec![PartialToolCall {
+                                index,
+                                function: Some(PartialFunctionCall {
+                                    arguments: Some(partial_json),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            }]))
+                        }
+                        _ => None,
+                    },
+                    Ok(messages::StreamEvent::MessageDelta { usage: u, .. }) => {
+                        usage.merge(&u);
+                        Some(ChatDelta::Usage(usage.clone().into()))
+                    }
+                    Ok(_) => None,
+                    Err(err) => return Some(Err(err)),
+                };
+                delta.map(Ok)
+            })
+            .filter_map(future::ready)
+            .boxed())
+    } else {
+        let response = client.messages(req).await?;
+        let mut deltas = Vec::new();
+        let thinking = response.thinking();
+        if !thinking.is_empty() {
+            deltas.push(Ok(ChatDelta::Reasoning(thinking)));
+        }
+        let text = response.text();
+        if !text.is_empty() {
+            deltas.push(Ok(ChatDelta::Content(text)));
+        }
+        let partials: Vec<_> = response
+            .content
+            .into_iter()
+            .filter_map(|block| match block {
+                messages::ContentBlock::ToolUse { id, name, input } => Some((id, name, input)),
+                _ => None,
+            })
+            .enumerate()
+            .map(|(i, (id, name, input))| PartialToolCall {
+                index: i as u32,
+                id: Some(id),
+                kind: Some(ToolCallKind::Function),
+                function: Some(PartialFunctionCall {
+                    name: Some(name),
+                    arguments: Some(input.to_string()),
+                }),
+            })
+            .collect();
+        if !partials.is_empty() {
+            deltas.push(Ok(ChatDelta::ToolCalls(partials)));
+        }
+        deltas.push(Ok(ChatDelta::Usage(response.usage.into())));
+        Ok(stream::iter(deltas).boxed())
+    }
+}
+
+fn responses_request(
+    config: &TranslateConfig,
+    prompt: Vec<Message>,
+    previous_response_id: Option<String>,
+) -> responses::Request {
+    let (instructions, input) = responses::from_chat_messages(prompt);
+    responses::Request::builder()
+        .model(config.model.clone())
+        .input(input)
+        .maybe_instructions(instructions)
+        .maybe_previous_response_id(previous_response_id)
+        .store(true)
+        .maybe_temperature(config.temperature)
+        .maybe_top_p(config.top_p)
+        .maybe_max_output_tokens(config.max_tokens)
+        .maybe_service_tier(config.service_tier)
+        .maybe_reasoning(
+            (config.reasoning_effort.is_some() || config.reasoning_summary.is_some()).then(|| {
+                responses::Reasoning {
+                    effort: config.reasoning_effort,
+                    summary: config.reasoning_summary,
+                }
+            }),
+        )
+        .maybe_text(config.verbosity.map(|verbosity| responses::TextConfig {
+            verbosity: Some(verbosity),
+        }))
+        .tools(config.tools.iter().cloned().map(Into::into).collect())
+        .maybe_tool_choice(config.tool_choice.clone().map(Into::into))
+        // The server-side history only grows; let it drop the oldest items
+        // rather than fail once it outgrows the context window.
+        .truncation(responses::Truncation::Auto)
+        .build()
+}
+
+fn function_call_delta(index: u32, item: responses::OutputItem) -> Option<ChatDelta> {
+    match item {
+        responses::OutputItem::FunctionCall {
+            call_id,
+            name,
+            arguments,
+            ..
+        } => Some(ChatDelta::ToolCalls(vec![PartialToolCall {
+            index,
+            id: Some(call_id),
+            kind: Some(ToolCallKind::Function),
+            function: Some(PartialFunctionCall {
+                name: Some(name),
+                arguments: Some(arguments),
+            }),
+        }])),
+        _ => None,
+    }
+}
+
+/// Usage and id reported with a finished response.
+fn finished_deltas(response: responses::Response) -> Vec<ChatDelta> {
+    let mut deltas = Vec::new();
+    if let Some(usage) = response.usage {
+        deltas.push(ChatDelta::Usage(usage.into()));
+    }
+    deltas.push(ChatDelta::ResponseId(response.id));
+    deltas
+}
+
+async fn responses_stream<'a>(
+    client: &'a openai::Client,
+    config: &TranslateConfig,
+    prompt: Vec<Message>,
+    previous_response_id: Option<String>,
+) -> Result<DeltaStream<'a>, openai::Error> {
+    let req = responses_request(config, prompt, previous_response_id);
+
+    if config.stream {
+        let stream = client.responses_stream(req).await?;
+        Ok(stream
+            .flat_map(|event| {
+                let deltas = match event {
+                    Ok(responses::StreamEvent::OutputTextDelta { delta, .. }) => {
+                        vec![Ok(ChatDelta::Content(delta))]
+                    }
+                    Ok(responses::StreamEvent::ReasoningSummaryTextDelta { delta, .. }) => {
+                        vec![Ok(ChatDelta::Reasoning(delta))]
+                    }
+                    // Separate consecutive summary parts like the non-streaming
+                    // summary does.
+                    Ok(responses::StreamEvent::ReasoningSummaryTextDone { .. }) => {
+                        vec![Ok(ChatDelta::Reasoning("\n\n".into()))]
+                    }
+                    // Arguments arrive separately; only take the skeleton.
+                    Ok(responses::StreamEvent::OutputItemAdded { output_index, item }) => {
+                        function_call_delta(output_index, item)
+                            .map(Ok)
+                            .into_iter()
+                            .collect()
+                    }
+                    Ok(responses::StreamEvent::FunctionCallArgumentsDelta {
+                        output_index,
+                        delta,
+                        ..
+                    }) => vec![Ok(ChatDelta::ToolCalls(vec![PartialToolCall {
+                        index: output_index,
+                        function: Some(PartialFunctionCall {
+                            arguments: Some(delta),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }]))],
+                    Ok(
+                        responses::StreamEvent::Completed { response }
+                        | responses::StreamEvent::Incomplete { response },
+                    ) => finished_deltas(*response).into_iter().map(Ok).collect(),
+                    Ok(_) => Vec::new(),
+                    Err(err) => vec![Err(err)],
+                };
+                stream::iter(deltas)
+            })
+            .boxed())
+    } else {
+        let response = client.responses(req).await?;
+        let mut deltas = Vec::new();
+        let summary = response.reasoning_summary();
+        if !summary.is_empty() {
+            deltas.push(ChatDelta::Reasoning(summary));
+        }
+        let text = response.output_text();
+        if !text.is_empty() {
+            deltas.push(ChatDelta::Content(text));
+        }
+        let partials: Vec<_> = response
+            .output
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter_map(|(i, item)| match function_call_delta(i as u32, item) {
+                Some(ChatDelta::ToolCalls(partials)) => Some(partials),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        if !partials.is_empty() {
+            deltas.push(ChatDelta::ToolCalls(partials));
+        }
+        deltas.extend(finished_deltas(response));
+        Ok(stream::iter(deltas.into_iter().map(Ok)).boxed())
+    }
+}
@@ -0,0 +1,102 @@
+//! Side-by-side model comparison.
+//!
+//! A line is fanned out to several lanes at once, each a model behind its
+//! own backend and endpoint. Every lane sees the same context buffer, and
+//! nothing is committed to it until the user picks the best candidate.
+
+use openai::ModelId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    settings::{ChatSettings, TranslatorType},
+    translator::chat::TranslateConfig,
+};
+
+/// Host of the OpenAI API. Other hosts may reject OpenAI-only parameters.
+const OPENAI_HOST: &str = "api.openai.com";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct CompareLane {
+    pub model: ModelId,
+    pub translator_type: TranslatorType,
+    /// Overrides the endpoint configured for `translator_type`. Empty uses
+    /// the configured one.
+    pub api_endpoint: String,
+}
+
+impl Default for CompareLane {
+    fn default() -> Self {
+        Self {
+            model: Default::default(),
+            translator_type: TranslatorType::Chat,
+            api_endpoint: String::new(),
+        }
+    }
+}
+
+impl CompareLane {
+    /// Endpoint the lane sends to.
+    pub fn endpoint<'a>(&'a self, settings: &'a ChatSettings) -> &'a str {
+        match self.translator_type {
+            _ if !self.api_endpoint.is_empty() => &self.api_endpoint,
+            TranslatorType::Chat | TranslatorType::Responses => &settings.api_endpoint,
+            TranslatorType::Messages => &settings.anthropic_api_endpoint,
+        }
+    }
+
+    /// Parameters for the lane's request, from those of the main
+    /// translator. OpenAI-only parameters are dropped for other providers.
+    pub fn config(&self, settings: &ChatSettings, config: &TranslateConfig) -> TranslateConfig {
+        let mut config = TranslateConfig {
+            model: self.model.clone(),
+            ..config.clone()
+        };
+        let openai = self.translator_type != TranslatorType::Messages
+            && reqwest::Url::parse(self.endpoint(settings))
+                .is_ok_and(|url| url.host_str() == Some(OPENAI_HOST));
+        if !openai {
+            config.service_tier = None;
+            config.reasoning_effort = None;
+            config.reasoning_summary = None;
+            config.verbosity = None;
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openai::{ReasoningEffort, ServiceTier};
+
+    use super::*;
+    use crate::settings::Settings;
+
+    #[test]
+    fn openai_only_params_are_dropped_elsewhere() {
+        let settings = Settings::default();
+        let mut config = TranslateConfig::from_settings(&settings);
+        config.service_tier = Some(ServiceTier::Flex);
+        config.reasoning_effort = Some(ReasoningEffort::Low);
+        let lane = |translator_type, api_endpoint: &str| CompareLane {
+            model: ModelId("m".into()),
+            translator_type,
+            api_endpoint: api_endpoint.into(),
+        };
+
+        let openai = lane(TranslatorType::Chat, "https://api.openai.com/v1");
+        let openai = openai.config(&settings.chat, &config);
+        assert_eq!(openai.model, ModelId("m".into()));
+        assert_eq!(openai.service_tier, Some(ServiceTier::Flex));
+        assert_eq!(openai.reasoning_effort, Some(ReasoningEffort::Low));
+
+        for lane in [
+            lane(TranslatorType::Chat, "http://localhost:8080"),
+            lane(TranslatorType::Messages, ""),
+        ] {
+            let config = lane.config(&settings.chat, &config);
+            assert_eq!(config.service_tier, None);
+            assert_eq!(config.reasoning_effort, None);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Project glossary.
+//!
+//! Preferred translations for names and recurring terms. Terms are detected
+//! in each line through the ichiran segmentation, only the relevant ones are
+//! sent with the request, and completed translations are checked for terms
+//! the model ignored.
+
+use std::{collections::HashSet, path::Path};
+
+use ichiran::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct GlossaryTerm {
+    /// Japanese term as written.
+    pub term: String,
+    /// Kana reading, also matched when the line spells the term in kana.
+    pub reading: String,
+    /// Preferred translation.
+    pub translation: String,
+    /// Target language `translation` is in. Empty applies to every target
+    /// language.
+    pub language: String,
+    pub notes: String,
+}
+
+impl GlossaryTerm {
+    /// Whether `translation` uses the preferred translation. Matching is
+    /// case-insensitive; alternatives may be separated by `|`.
+    pub fn is_followed_in(&self, translation: &str) -> bool {
+        let translation = translation.to_lowercase();
+        self.translation
+            .split('|')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .any(|x| translation.contains(&x.to_lowercase()))
+    }
+
+    /// Whether the term applies when translating into `target_language`.
+    pub fn applies_to(&self, target_language: &str) -> bool {
+        self.language.is_empty()
+            || self
+                .language
+                .trim()
+                .eq_ignore_ascii_case(target_language.trim())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Glossary {
+    pub terms: Vec<GlossaryTerm>,
+}
+
+impl Glossary {
+    /// Load a glossary from a TOML file. A missing or malformed file yields
+    /// an empty glossary.
+    pub fn from_file(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|err| {
+                tracing::error!(%err, ?path, "failed to parse glossary");
+                Self::default()
+            }),
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    tracing::error!(%err, ?path, "failed to read glossary");
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, toml::to_string(self).unwrap())
+    }
+
+    /// Terms for `target_language` that occur in `text`. With a
+    /// segmentation, a term matches a word (surface, kana or dictionary form,
+    /// including compound parts) or a run of adjacent words, so names split
+    /// by the segmenter are still found while substrings of unrelated words
+    /// are not. Without one, falls back to substring matching.
+    pub fn detect(
+        &self,
+        text: &str,
+        root: Option<&Root>,
+        target_language: &str,
+    ) -> Vec<GlossaryTerm> {
+        let words = root.map(Words::new);
+        self.terms
+            .iter()
+            .filter(|term| !term.term.is_empty() && term.applies_to(target_language))
+            .filter(|term| {
+                let keys = [&term.term, &term.reading];
+                let mut keys = keys.iter().filter(|x| !x.is_empty());
+                match &words {
+                    Some(words) => keys.any(|key| words.contains(key)),
+                    None => keys.any(|key| text.contains(key.as_str())),
+                }
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Render `terms` as a prompt message.
+pub fn prompt(terms: &[GlossaryTerm]) -> String {
+    let mut prompt = String::from("Use these glossary translations in the next line:");
+    for term in terms {
+        prompt.push_str(&format!("\n- {} → {}", term.term, term.translation));
+        if !term.notes.is_empty() {
+            prompt.push_str(&format!(" ({})", term.notes));
+        }
+    }
+    prompt
+}
+
+/// Words of the best segmentation of a line.
+struct Words {
+    /// Surface text of each top-level word and skipped segment, in order.
+    surfaces: Vec<String>,
+    /// Every form a word can be matched by.
+    forms: HashSet<String>,
+}
+
+impl Words {
+    fn new(root: &Root) -> Self {
+        let mut words = Self {
+            surfaces: Vec::new(),
+            forms: HashSet::new(),
+        };
+        for segment in root.segments() {
+            match segment {
+                Segment::Skipped(skipped) => words.surfaces.push(skipped.clone()),
+                Segment::Clauses(clauses) => {
+                    for romanized in clauses.first().iter().flat_map(|x| x.romanized()) {
+                        words.surfaces.push(romanized.term().text().to_string());
+                        words.visit_term(romanized.term());
+                    }
+                }
+            }
+        }
+        words
+    }
+
+    fn visit_term(&mut self, term: &Term) {
+        let word = term.best();
+        self.forms.insert(word.meta().text().to_string());
+        self.forms.insert(word.meta().kana().to_string());
+        match word {
+            Word::Plain(plain) => {
+                for reading in plain.conj().iter().filter_map(|x| x.reading()) {
+                    // "食べる 【たべる】"
+                    let form = reading.split(" 【").next().unwrap_or(reading);
+                    self.forms.insert(form.to_string());
+                }
+            }
+            Word::Compound(compound) => {
+                for component in compound.components() {
+                    self.visit_term(component);
+                }
+            }
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        if self.forms.contains(key) {
+            return true;
+        }
+        (0..self.surfaces.len()).any(|start| {
+            let mut run = String::new();
+            for surface in &self.surfaces[start..] {
+                run.push_str(surface);
+                if run.len() >= key.len() {
+                    return run == key;
+                }
+            }
+            false
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn word(text: &str, kana: &str) -> serde_json::Value {
+        json!([kana, {"reading": format!("{text} 【{kana}】"), "text": text, "kana": kana,
+            "score": 10, "seq": 1, "gloss": []}, []])
+    }
+
+    /// 田中さん、食べた？ with 田中 split into two unknown words.
+    fn root() -> Root {
+        let ate = json!(["tabeta", {"reading": "食べた 【たべた】", "text": "食べた",
+            "kana": "たべた", "score": 10, "seq": 2, "gloss": [],
+            "conj": [{"prop": [{"pos": "v1", "type": "Past (~ta)"}],
+                      "reading": "食べる 【たべる】", "gloss": [], "readok": true}]}, []]);
+        serde_json::from_value(json!([
+            [[
+                [word("田", "た"), word("中", "なか"), word("さん", "さん")],
+                30
+            ]],
+            "、",
+            [[[ate], 10]],
+        ]))
+        .unwrap()
+    }
+
+    fn term(term: &str, translation: &str) -> GlossaryTerm {
+        GlossaryTerm {
+            term: term.into(),
+            translation: translation.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_terms_through_segmentation() {
+        let glossary = Glossary {
+            terms: vec![
+                term("田中", "Tanaka"),
+                term("さん", "-san"),
+                term("食べる", "eat"),
+                term("中さ", "nonsense"),
+                GlossaryTerm {
+                    reading: "べた".into(),
+                    ..term("ベタ", "cliché")
+                },
+            ],
+        };
+        let text = "田中さん、食べた？";
+        let detected: Vec<_> = glossary
+            .detect(text, Some(&root()), "English")
+            .into_iter()
+            .map(|x| x.translation)
+            .collect();
+        assert_eq!(detected, ["Tanaka", "-san", "eat"]);
+
+        // Without a segmentation, anything that occurs verbatim matches.
+        let detected: Vec<_> = glossary
+            .detect(text, None, "English")
+            .into_iter()
+            .map(|x| x.translation)
+            .collect();
+        assert_eq!(detected, ["Tanaka", "-san", "nonsense", "cliché"]);
+    }
+
+    #[test]
+    fn detects_terms_for_the_target_language() {
+        let glossary = Glossary {
+            terms: vec![
+                GlossaryTerm {
+                    language: "Korean".into(),
+                    ..term("田中", "다나카")
+                },
+                GlossaryTerm {
+                    language: "english".into(),
+                    ..term("田中", "Tanaka")
+                },
+                term("さん", "-san"),
+            ],
+        };
+        let detect = |language| -> Vec<_> {
+            glossary
+                .detect("田中さん", None, language)
+                .into_iter()
+                .map(|x| x.translation)
+                .collect()
+        };
+        assert_eq!(detect("Korean"), ["다나카", "-san"]);
+        assert_eq!(detect("English"), ["Tanaka", "-san"]);
+        assert_eq!(detect("Spanish"), ["-san"]);
+    }
+
+    #[test]
+    fn checks_preferred_translation() {
+        let tanaka = term("田中", "Tanaka | Mr. Tanaka");
+        assert!(tanaka.is_followed_in("Did TANAKA eat?"));
+        assert!(!tanaka.is_followed_in("Did Takana eat?"));
+    }
+
+    #[test]
+    fn prompt_lists_terms_with_notes() {
+        let mut tanaka = term("田中", "Tanaka");
+        tanaka.notes = "surname".into();
+        assert_eq!(
+            prompt(&[tanaka, term("さん", "-san")]),
+            "Use these glossary translations in the next line:\n\
+             - 田中 → Tanaka (surname)\n\
+             - さん → -san"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let glossary = Glossary {
+            terms: vec![term("田中", "Tanaka")],
+        };
+        let text = toml::to_string(&glossary).unwrap();
+        assert_eq!(toml::from_str::<Glossary>(&text).unwrap(), glossary);
+    }
+}
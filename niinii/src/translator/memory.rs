@@ -0,0 +1,421 @@
+//! Translation memory.
+//!
+//! Completed translations are remembered by normalized source text, model,
+//! target language, system prompt and the preceding lines of context, and
+//! served instantly when the same key comes up again. Entries are appended
+//! to a JSON-lines file so the memory survives restarts; later lines
+//! supersede earlier ones, and superseded lines are compacted away on load.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{mpsc, RwLock},
+    thread::JoinHandle,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use enclose::enclose;
+use serde::{Deserialize, Serialize};
+
+use crate::support::hash::fnv1a;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct MemorySettings {
+    pub enabled: bool,
+    /// JSON-lines file backing the memory. Empty keeps it in memory only.
+    pub path: String,
+    /// Number of preceding user lines that must also match for a hit.
+    pub context_lines: usize,
+    /// Minimum similarity (0 to 1) for fuzzy-match suggestions.
+    pub suggestion_threshold: f32,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: "translation_memory.jsonl".into(),
+            context_lines: 1,
+            suggestion_threshold: 0.6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct MemoryKey {
+    /// Normalized source text.
+    pub text: String,
+    pub model: String,
+    /// Entries written before the target language was configurable were all
+    /// English.
+    #[serde(default = "default_target_language")]
+    pub target_language: String,
+    /// Hash of the system prompt.
+    pub prompt: u64,
+    /// Hash of the normalized preceding lines.
+    pub context: u64,
+}
+
+impl MemoryKey {
+    pub fn new<'a>(
+        text: &str,
+        model: &str,
+        target_language: &str,
+        system_prompt: &str,
+        context: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let context: Vec<String> = context.into_iter().map(normalize).collect();
+        Self {
+            text: normalize(text),
+            model: model.into(),
+            target_language: target_language.into(),
+            prompt: fnv1a(system_prompt.as_bytes()),
+            context: fnv1a(context.join("\n").as_bytes()),
+        }
+    }
+}
+
+fn default_target_language() -> String {
+    "English".into()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MemoryEntry {
+    pub key: MemoryKey,
+    /// Source text as it was submitted.
+    pub source: String,
+    pub translation: String,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+}
+
+impl MemoryEntry {
+    pub fn new(key: MemoryKey, source: String, translation: String) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            key,
+            source,
+            translation,
+            created,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub entry: MemoryEntry,
+    /// Similarity to the queried text, between 0 and 1.
+    pub score: f32,
+}
+
+#[derive(Default)]
+pub struct TranslationMemory {
+    path: Option<PathBuf>,
+    entries: RwLock<HashMap<MemoryKey, MemoryEntry>>,
+    /// Thread appending lines to the file in order, so callers never wait
+    /// on disk.
+    writer: Option<(mpsc::Sender<String>, JoinHandle<()>)>,
+}
+
+impl Drop for TranslationMemory {
+    /// Finish writing pending entries.
+    fn drop(&mut self) {
+        if let Some((tx, thread)) = self.writer.take() {
+            drop(tx);
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for TranslationMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranslationMemory")
+            .field("path", &self.path)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl TranslationMemory {
+    /// Load the memory described by `settings`. A missing file is not an
+    /// error; malformed lines are skipped.
+    pub fn open(settings: &MemorySettings) -> Self {
+        if settings.path.is_empty() {
+            return Self::default();
+        }
+        let path = PathBuf::from(&settings.path);
+        let mut entries = HashMap::new();
+        match std::fs::File::open(&path) {
+            Ok(file) => {
+                let mut lines = 0;
+                for line in BufReader::new(file).lines() {
+                    let Ok(line) = line else { break };
+                    lines += 1;
+                    match serde_json::from_str::<MemoryEntry>(&line) {
+                        Ok(entry) => {
+                            entries.insert(entry.key.clone(), entry);
+                        }
+                        Err(err) => tracing::warn!(%err, "skipping translation memory entry"),
+                    }
+                }
+                if lines > entries.len() {
+                    if let Err(err) = compact(&path, &entries) {
+                        tracing::error!(%err, ?path, "failed to compact translation memory");
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => tracing::error!(%err, ?path, "failed to open translation memory"),
+        }
+        let (tx, rx) = mpsc::channel::<String>();
+        let thread = std::thread::spawn(enclose! { (path) move || {
+            for line in rx {
+                let result = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| file.write_all(line.as_bytes()));
+                if let Err(err) = result {
+                    tracing::error!(%err, ?path, "failed to write translation memory");
+                }
+            }
+        }});
+        Self {
+            path: Some(path),
+            entries: RwLock::new(entries),
+            writer: Some((tx, thread)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, key: &MemoryKey) -> Option<MemoryEntry> {
+        self.entries.read().unwrap().get(key).cloned()
+    }
+
+    /// Remember `entry`, replacing any entry with the same key. The entry
+    /// is written to the file in the background.
+    pub fn insert(&self, entry: MemoryEntry) {
+        if let Some((tx, _)) = &self.writer {
+            match serde_json::to_string(&entry) {
+                Ok(line) => {
+                    let _ = tx.send(line + "\n");
+                }
+                Err(err) => tracing::error!(%err, "failed to serialize translation memory entry"),
+            }
+        }
+        self.entries
+            .write()
+            .unwrap()
+            .insert(entry.key.clone(), entry);
+    }
+
+    /// Entries translated into `target_language` whose source text is similar
+    /// to `text`, best first. Only the most recent entry per source text is
+    /// returned.
+    pub fn suggest(
+        &self,
+        text: &str,
+        target_language: &str,
+        threshold: f32,
+        limit: usize,
+    ) -> Vec<Suggestion> {
+        let query = bigrams(&normalize(text));
+        let entries = self.entries.read().unwrap();
+        let mut latest: HashMap<&str, &MemoryEntry> = HashMap::new();
+        for entry in entries
+            .values()
+            .filter(|entry| entry.key.target_language == target_language)
+        {
+            let slot = latest.entry(&entry.key.text).or_insert(entry);
+            if entry.created > slot.created {
+                *slot = entry;
+            }
+        }
+        let mut suggestions: Vec<Suggestion> = latest
+            .into_iter()
+            .filter_map(|(text, entry)| {
+                let score = dice(&query, &bigrams(text));
+                (score >= threshold).then(|| Suggestion {
+                    entry: entry.clone(),
+                    score,
+                })
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.truncate(limit);
+        suggestions
+    }
+}
+
+/// Rewrite the file at `path` with one line per entry, oldest first.
+fn compact(path: &Path, entries: &HashMap<MemoryKey, MemoryEntry>) -> std::io::Result<()> {
+    let mut entries: Vec<&MemoryEntry> = entries.values().collect();
+    entries.sort_by_key(|entry| entry.created);
+    let mut contents = String::new();
+    for entry in entries {
+        contents.push_str(&serde_json::to_string(entry)?);
+        contents.push('\n');
+    }
+    // Written aside and renamed over, so a crash never loses the memory.
+    let tmp = path.with_extension("jsonl.tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Fold full-width ASCII to half-width and drop whitespace, which VN text
+/// hookers insert inconsistently.
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            c => c,
+        })
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+fn bigrams(text: &str) -> HashSet<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() == 1 {
+        return HashSet::from([(chars[0], '\0')]);
+    }
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Sørensen–Dice coefficient over character bigrams.
+fn dice(a: &HashSet<(char, char)>, b: &HashSet<(char, char)>) -> f32 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shared = a.intersection(b).count();
+    2.0 * shared as f32 / (a.len() + b.len()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(text: &str) -> MemoryKey {
+        MemoryKey::new(text, "gpt-5", "English", "Translate.", ["前の行"])
+    }
+
+    #[test]
+    fn normalization_ignores_width_and_whitespace() {
+        assert_eq!(normalize("「ＡＢＣ！」　 です\n"), "「ABC!」です");
+        assert_eq!(key("ＯＫ です"), key("OKです"));
+    }
+
+    #[test]
+    fn key_depends_on_model_language_prompt_and_context() {
+        let base = key("猫です");
+        let key = |model, language, prompt, context: &[&'static str]| {
+            MemoryKey::new("猫です", model, language, prompt, context.iter().copied())
+        };
+        assert_eq!(base, key("gpt-5", "English", "Translate.", &["前の行"]));
+        assert_ne!(base, key("gpt-4o", "English", "Translate.", &["前の行"]));
+        assert_ne!(base, key("gpt-5", "Korean", "Translate.", &["前の行"]));
+        assert_ne!(base, key("gpt-5", "English", "Translate!", &["前の行"]));
+        assert_ne!(base, key("gpt-5", "English", "Translate.", &["次の行"]));
+        assert_ne!(base, key("gpt-5", "English", "Translate.", &[]));
+    }
+
+    #[test]
+    fn legacy_entries_are_english() {
+        let mut entry = serde_json::to_value(MemoryEntry::new(
+            key("猫です"),
+            "猫です".into(),
+            "A cat.".into(),
+        ))
+        .unwrap();
+        entry["key"]
+            .as_object_mut()
+            .unwrap()
+            .remove("target_language");
+        let entry: MemoryEntry = serde_json::from_value(entry).unwrap();
+        assert_eq!(entry.key, key("猫です"));
+    }
+
+    #[test]
+    fn persists_across_reopen() {
+        let path = std::env::temp_dir().join(format!(
+            "niinii-memory-test-{}-{}.jsonl",
+            std::process::id(),
+            fnv1a(b"persists_across_reopen")
+        ));
+        let _ = std::fs::remove_file(&path);
+        let settings = MemorySettings {
+            path: path.to_string_lossy().into(),
+            ..Default::default()
+        };
+        let memory = TranslationMemory::open(&settings);
+        memory.insert(MemoryEntry::new(
+            key("猫です"),
+            "猫です".into(),
+            "A cat.".into(),
+        ));
+        memory.insert(MemoryEntry::new(
+            key("猫です"),
+            "猫です".into(),
+            "It's a cat.".into(),
+        ));
+        // Waits for the entries to be written.
+        drop(memory);
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{garbage\n")
+            .unwrap();
+
+        let reopened = TranslationMemory::open(&settings);
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(
+            reopened.get(&key("猫です")).unwrap().translation,
+            "It's a cat."
+        );
+        drop(reopened);
+        // The superseded and malformed lines were compacted away.
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn suggests_near_identical_lines() {
+        let memory = TranslationMemory::default();
+        for (source, translation) in [
+            ("今日はいい天気ですね", "Nice weather today."),
+            ("今日はいい天気ですね！", "Nice weather today!"),
+            ("明日は雨が降るらしい", "I hear it'll rain tomorrow."),
+        ] {
+            memory.insert(MemoryEntry::new(
+                key(source),
+                source.into(),
+                translation.into(),
+            ));
+        }
+        let suggestions = memory.suggest("今日もいい天気ですね", "English", 0.6, 5);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].entry.source, "今日はいい天気ですね");
+        assert!(suggestions[0].score > suggestions[1].score);
+        assert!(memory.suggest("全然違う", "English", 0.6, 5).is_empty());
+        // Translations into another language are no help.
+        assert!(memory
+            .suggest("今日もいい天気ですね", "Spanish", 0.6, 5)
+            .is_empty());
+    }
+}
@@ -1,3 +1,12 @@
+pub mod budget;
 pub mod chat;
+pub mod client;
+pub mod compare;
+pub mod glossary;
+pub mod memory;
+pub mod pricing;
+pub mod session;
+pub mod summary;
+pub mod template;
 
 pub use chat::{ChatHandle, ChatState, ContextEdit, ExchangeId, ExchangeView, Response};
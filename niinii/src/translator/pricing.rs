@@ -0,0 +1,222 @@
+//! Cost estimates from reported token usage.
+//!
+//! Prices are per million tokens and looked up by model id prefix, longest
+//! match first, so `gpt-5-mini` is not billed as `gpt-5`. The table lives in
+//! `Settings` and is user-editable; the defaults are standard-tier list
+//! prices and will drift.
+
+use std::ops::AddAssign;
+
+use openai::{chat::Usage, ModelId};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct ModelPrice {
+    /// Model id prefix this price applies to.
+    pub model: String,
+    pub input: f64,
+    pub cached_input: f64,
+    /// Also applies to reasoning tokens, which are billed as output.
+    pub output: f64,
+}
+
+impl ModelPrice {
+    fn new(model: &str, input: f64, cached_input: f64, output: f64) -> Self {
+        Self {
+            model: model.into(),
+            input,
+            cached_input,
+            output,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Pricing {
+    pub currency: String,
+    pub models: Vec<ModelPrice>,
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Self {
+            currency: "$".into(),
+            models: vec![
+                ModelPrice::new("gpt-5", 1.25, 0.125, 10.0),
+                ModelPrice::new("gpt-5-mini", 0.25, 0.025, 2.0),
+                ModelPrice::new("gpt-5-nano", 0.05, 0.005, 0.4),
+                ModelPrice::new("gpt-4.1", 2.0, 0.5, 8.0),
+                ModelPrice::new("gpt-4.1-mini", 0.4, 0.1, 1.6),
+                ModelPrice::new("gpt-4.1-nano", 0.1, 0.025, 0.4),
+                ModelPrice::new("gpt-4o", 2.5, 1.25, 10.0),
+                ModelPrice::new("gpt-4o-mini", 0.15, 0.075, 0.6),
+                ModelPrice::new("claude-opus-4", 15.0, 1.5, 75.0),
+                ModelPrice::new("claude-sonnet-4", 3.0, 0.3, 15.0),
+                ModelPrice::new("claude-haiku-4", 1.0, 0.1, 5.0),
+            ],
+        }
+    }
+}
+
+/// Estimated cost of one or more requests, in the table's currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cost {
+    /// Uncached prompt tokens.
+    pub input: f64,
+    pub cached_input: f64,
+    /// Completion tokens, including reasoning.
+    pub output: f64,
+    /// Portion of `output` spent on reasoning.
+    pub reasoning: f64,
+}
+
+impl Cost {
+    pub fn total(&self) -> f64 {
+        self.input + self.cached_input + self.output
+    }
+}
+
+impl AddAssign for Cost {
+    fn add_assign(&mut self, rhs: Self) {
+        self.input += rhs.input;
+        self.cached_input += rhs.cached_input;
+        self.output += rhs.output;
+        self.reasoning += rhs.reasoning;
+    }
+}
+
+/// Token and cost totals over a set of exchanges.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageTotals {
+    pub requests: u32,
+    pub prompt_tokens: u64,
+    pub cached_tokens: u64,
+    pub completion_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub cost: Cost,
+    /// Requests whose model has no price; excluded from `cost`.
+    pub unpriced: u32,
+}
+
+impl UsageTotals {
+    pub fn add(&mut self, pricing: &Pricing, model: &ModelId, usage: &Usage) {
+        self.requests += 1;
+        self.prompt_tokens += usage.prompt_tokens as u64;
+        self.cached_tokens += cached_tokens(usage) as u64;
+        self.completion_tokens += usage.completion_tokens as u64;
+        self.reasoning_tokens += reasoning_tokens(usage) as u64;
+        match pricing.cost(model, usage) {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced += 1,
+        }
+    }
+}
+
+fn cached_tokens(usage: &Usage) -> u32 {
+    usage
+        .prompt_tokens_details
+        .as_ref()
+        .map(|x| x.cached_tokens)
+        .unwrap_or_default()
+}
+
+fn reasoning_tokens(usage: &Usage) -> u32 {
+    usage
+        .completion_tokens_details
+        .as_ref()
+        .map(|x| x.reasoning_tokens)
+        .unwrap_or_default()
+}
+
+impl Pricing {
+    /// Price for a model by longest matching prefix. Vendor prefixes as used
+    /// by routers (e.g. `openai/gpt-4o`) are ignored.
+    pub fn price(&self, model: &ModelId) -> Option<&ModelPrice> {
+        let model = model.as_ref();
+        let model = model.rsplit('/').next().unwrap_or(model);
+        self.models
+            .iter()
+            .filter(|price| !price.model.is_empty() && model.starts_with(&price.model))
+            .max_by_key(|price| price.model.len())
+    }
+
+    pub fn cost(&self, model: &ModelId, usage: &Usage) -> Option<Cost> {
+        const PER: f64 = 1_000_000.0;
+        let price = self.price(model)?;
+        let cached = cached_tokens(usage).min(usage.prompt_tokens);
+        let uncached = usage.prompt_tokens - cached;
+        Some(Cost {
+            input: uncached as f64 * price.input / PER,
+            cached_input: cached as f64 * price.cached_input / PER,
+            output: usage.completion_tokens as f64 * price.output / PER,
+            reasoning: reasoning_tokens(usage) as f64 * price.output / PER,
+        })
+    }
+
+    pub fn format(&self, amount: f64) -> String {
+        format!("{}{:.4}", self.currency, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: u32, cached: u32, completion: u32, reasoning: u32) -> Usage {
+        serde_json::from_value(serde_json::json!({
+            "prompt_tokens": prompt,
+            "completion_tokens": completion,
+            "total_tokens": prompt + completion,
+            "prompt_tokens_details": { "cached_tokens": cached },
+            "completion_tokens_details": { "reasoning_tokens": reasoning },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let pricing = Pricing::default();
+        let price = |model: &str| pricing.price(&ModelId(model.into())).map(|p| &p.model[..]);
+        assert_eq!(price("gpt-5-mini-2025-08-07"), Some("gpt-5-mini"));
+        assert_eq!(price("gpt-5"), Some("gpt-5"));
+        assert_eq!(price("openai/gpt-4o-mini"), Some("gpt-4o-mini"));
+        assert_eq!(price("claude-sonnet-4-5"), Some("claude-sonnet-4"));
+        assert_eq!(price("llama-3.1-8b"), None);
+    }
+
+    #[test]
+    fn cached_and_reasoning_tokens_are_priced() {
+        let pricing = Pricing {
+            currency: "$".into(),
+            models: vec![ModelPrice::new("m", 2.0, 0.5, 8.0)],
+        };
+        let cost = pricing
+            .cost(
+                &ModelId("m".into()),
+                &usage(1_000_000, 400_000, 500_000, 100_000),
+            )
+            .unwrap();
+        assert!((cost.input - 1.2).abs() < 1e-9);
+        assert!((cost.cached_input - 0.2).abs() < 1e-9);
+        assert!((cost.output - 4.0).abs() < 1e-9);
+        assert!((cost.reasoning - 0.8).abs() < 1e-9);
+        assert!((cost.total() - 5.4).abs() < 1e-9);
+        assert_eq!(pricing.format(cost.total()), "$5.4000");
+    }
+
+    #[test]
+    fn totals_track_unpriced_models() {
+        let pricing = Pricing::default();
+        let mut totals = UsageTotals::default();
+        totals.add(&pricing, &ModelId("gpt-4o".into()), &usage(100, 0, 10, 0));
+        totals.add(&pricing, &ModelId("local".into()), &usage(100, 50, 10, 5));
+        assert_eq!(totals.requests, 2);
+        assert_eq!(totals.prompt_tokens, 200);
+        assert_eq!(totals.cached_tokens, 50);
+        assert_eq!(totals.reasoning_tokens, 5);
+        assert_eq!(totals.unpriced, 1);
+        assert!(totals.cost.total() > 0.0);
+    }
+}
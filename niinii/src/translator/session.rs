@@ -0,0 +1,143 @@
+//! Named translator sessions.
+//!
+//! A session is a context buffer plus the system prompt, prompt preset and
+//! game title it was curated for, e.g. one per game or route. Each is stored
+//! as `<name>.json` in the sessions directory.
+
+use std::path::PathBuf;
+
+use openai::chat::Message;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid session name {0:?}")]
+    InvalidName(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    pub system_prompt: String,
+    pub prompt_preset: Option<String>,
+    pub game_title: String,
+    pub context: Vec<Message>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Names of the stored sessions, sorted.
+    pub fn list(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn load(&self, name: &str) -> Result<Session, Error> {
+        let text = std::fs::read_to_string(self.path(name)?)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, name: &str, session: &Session) -> Result<(), Error> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(path, serde_json::to_string_pretty(session)?)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), Error> {
+        Ok(std::fs::remove_file(self.path(name)?)?)
+    }
+
+    /// Names become file names, so anything that could escape the directory
+    /// is rejected.
+    fn path(&self, name: &str) -> Result<PathBuf, Error> {
+        let valid = !name.trim().is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'));
+        if !valid {
+            return Err(Error::InvalidName(name.into()));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openai::chat::Role;
+
+    use super::*;
+
+    fn store(test: &str) -> SessionStore {
+        let dir =
+            std::env::temp_dir().join(format!("niinii-sessions-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        SessionStore::new(dir)
+    }
+
+    #[test]
+    fn saves_lists_and_loads() {
+        let store = store("saves_lists_and_loads");
+        assert!(store.list().is_empty());
+        let session = Session {
+            system_prompt: "Translate.".into(),
+            prompt_preset: Some("Visual novel".into()),
+            game_title: "Ever17".into(),
+            context: vec![Message {
+                role: Role::User,
+                content: Some("雪菜は幼馴染".into()),
+                name: Some("info".into()),
+                ..Default::default()
+            }],
+        };
+        store.save("Route B", &session).unwrap();
+        store.save("ルートA", &Session::default()).unwrap();
+        assert_eq!(store.list(), ["Route B", "ルートA"]);
+
+        let loaded = store.load("Route B").unwrap();
+        assert_eq!(loaded.system_prompt, "Translate.");
+        assert_eq!(loaded.prompt_preset.as_deref(), Some("Visual novel"));
+        assert_eq!(loaded.context[0].name.as_deref(), Some("info"));
+
+        store.delete("ルートA").unwrap();
+        assert_eq!(store.list(), ["Route B"]);
+        std::fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_path_like_names() {
+        let store = store("rejects_path_like_names");
+        for name in ["", "  ", "../escape", "a/b", "a\\b", ".hidden"] {
+            assert!(matches!(
+                store.save(name, &Session::default()),
+                Err(Error::InvalidName(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Rolling summary of turns evicted from the context buffer.
+//!
+//! Instead of being forgotten, evicted turns are folded into a pinned
+//! "story so far" message by a (possibly cheaper) model. Each summary
+//! request merges the previous summary with the newly evicted turns, so the
+//! summary stays bounded while the story grows.
+
+use openai::{
+    chat::{Message, Role},
+    ModelId,
+};
+use serde::{Deserialize, Serialize};
+
+/// `name` of the summary message. Named messages are pinned, so the summary
+/// is never evicted itself.
+pub const NAME: &str = "summary";
+const PREFIX: &str = "Story so far: ";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct SummarySettings {
+    pub enabled: bool,
+    /// Model that writes the summary. Unset uses the translation model.
+    pub model: Option<ModelId>,
+    /// Template for the summarizer's system prompt. Only `target_language`
+    /// is set.
+    pub prompt: String,
+    pub max_tokens: Option<u32>,
+}
+
+impl Default for SummarySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            prompt: "You maintain a running summary of a visual novel that is being translated. \
+                     Merge the new lines into the summary, keeping names, relationships and \
+                     unresolved plot points. Reply with the updated summary only, in \
+                     {{target_language}}, in under 150 words."
+                .into(),
+            max_tokens: Some(512),
+        }
+    }
+}
+
+/// The current summary, if the context has one.
+pub fn current<'a>(context: impl IntoIterator<Item = &'a Message>) -> Option<&'a str> {
+    context
+        .into_iter()
+        .find(|message| message.name.as_deref() == Some(NAME))
+        .and_then(|message| message.content.as_deref())
+        .map(|content| content.strip_prefix(PREFIX).unwrap_or(content))
+}
+
+/// The pinned context message holding `summary`.
+pub fn message(summary: &str) -> Message {
+    Message {
+        role: Role::User,
+        content: Some(format!("{}{}", PREFIX, summary.trim())),
+        name: Some(NAME.into()),
+        ..Default::default()
+    }
+}
+
+/// User turn of a summary request.
+pub fn request(previous: Option<&str>, evicted: &[Message]) -> String {
+    let mut request = format!(
+        "Summary so far:\n{}\n\nNew lines:",
+        previous.unwrap_or("(none)")
+    );
+    for message in evicted {
+        let label = match message.role {
+            Role::User => "Original",
+            Role::Assistant => "Translation",
+            _ => continue,
+        };
+        if let Some(content) = message.content.as_deref().filter(|x| !x.is_empty()) {
+            request.push_str(&format!("\n{}: {}", label, content));
+        }
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_of(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summary_message_round_trips() {
+        let context = [
+            message_of(Role::User, "雪菜は幼馴染"),
+            message(" They met at school. "),
+        ];
+        assert_eq!(current(&context), Some("They met at school."));
+        assert_eq!(current(&context[..1]), None);
+    }
+
+    #[test]
+    fn request_labels_turns() {
+        let evicted = [
+            message_of(Role::User, "おはよう"),
+            message_of(Role::Assistant, "Good morning."),
+            Message::tool_result("call_1", "ignored"),
+        ];
+        assert_eq!(
+            request(Some("They met."), &evicted),
+            "Summary so far:\nThey met.\n\nNew lines:\n\
+             Original: おはよう\nTranslation: Good morning."
+        );
+        assert!(request(None, &[]).starts_with("Summary so far:\n(none)"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Prompt templates.
+//!
+//! The system prompt is rendered per line from a template, so it can refer
+//! to the speaker, the glossary terms in the line and so on. The language
+//! is deliberately small:
+//!
+//! - `{{name}}` is replaced by the variable `name`.
+//! - `{{#if name}}...{{else}}...{{/if}}` renders the first branch if `name`
+//!   is not empty, the second (optional) branch otherwise. Conditionals nest.
+
+use std::str::FromStr;
+
+use ichiran::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, EnumString, IntoStaticStr};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("unknown variable {0:?}")]
+    UnknownVariable(String),
+    #[error("unclosed tag at byte {0}")]
+    UnclosedTag(usize),
+    #[error("{{{{else}}}} outside of {{{{#if}}}}")]
+    UnmatchedElse,
+    #[error("{{{{/if}}}} without {{{{#if}}}}")]
+    UnmatchedEndIf,
+    #[error("{{{{#if {0}}}}} is never closed")]
+    UnclosedIf(&'static str),
+}
+
+/// A named template that can be selected per session.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PromptPreset {
+    pub name: String,
+    pub template: String,
+}
+
+impl PromptPreset {
+    pub fn defaults() -> Vec<Self> {
+        vec![Self {
+            name: "Visual novel".into(),
+            template: "You will translate the following visual novel script\
+                       {{#if game_title}} from {{game_title}}{{/if}} into {{target_language}}.\
+                       {{#if speaker}}\nThe next line is spoken by {{speaker}}.{{/if}}"
+                .into(),
+        }]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum Variable {
+    /// Speaker name split off the line, e.g. 雪菜 in 雪菜「おはよう」.
+    Speaker,
+    /// The previous line in the context buffer.
+    PreviousLine,
+    GameTitle,
+    /// Glossary terms detected in the line. When the template uses these,
+    /// they are not sent separately.
+    Glossary,
+    /// Dictionary glosses of the words in the line.
+    Gloss,
+    TargetLanguage,
+}
+
+/// Values of the template variables for one line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Variables {
+    pub speaker: String,
+    pub previous_line: String,
+    pub game_title: String,
+    pub glossary: String,
+    pub gloss: String,
+    pub target_language: String,
+}
+
+impl Variables {
+    pub fn get(&self, variable: Variable) -> &str {
+        match variable {
+            Variable::Speaker => &self.speaker,
+            Variable::PreviousLine => &self.previous_line,
+            Variable::GameTitle => &self.game_title,
+            Variable::Glossary => &self.glossary,
+            Variable::Gloss => &self.gloss,
+            Variable::TargetLanguage => &self.target_language,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(Variable),
+    If {
+        variable: Variable,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A conditional being parsed. The root frame has no variable.
+struct Frame {
+    variable: Option<Variable>,
+    then: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+impl Frame {
+    fn nodes(&mut self) -> &mut Vec<Node> {
+        self.otherwise.as_mut().unwrap_or(&mut self.then)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let mut stack = vec![Frame {
+            variable: None,
+            then: Vec::new(),
+            otherwise: None,
+        }];
+        let push_text = |stack: &mut Vec<Frame>, text: &str| {
+            if !text.is_empty() {
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .nodes()
+                    .push(Node::Text(text.into()));
+            }
+        };
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            push_text(&mut stack, &rest[..start]);
+            let offset = source.len() - rest.len() + start;
+            let tag_len = rest[start..].find("}}").ok_or(Error::UnclosedTag(offset))?;
+            let tag = rest[start + 2..start + tag_len].trim();
+            if tag == "else" {
+                let frame = stack.last_mut().unwrap();
+                if frame.variable.is_none() || frame.otherwise.is_some() {
+                    return Err(Error::UnmatchedElse);
+                }
+                frame.otherwise = Some(Vec::new());
+            } else if tag == "/if" {
+                if stack.len() == 1 {
+                    return Err(Error::UnmatchedEndIf);
+                }
+                let frame = stack.pop().unwrap();
+                stack.last_mut().unwrap().nodes().push(Node::If {
+                    variable: frame.variable.unwrap(),
+                    then: frame.then,
+                    otherwise: frame.otherwise.unwrap_or_default(),
+                });
+            } else if let Some(name) = tag.strip_prefix("#if ") {
+                stack.push(Frame {
+                    variable: Some(variable(name)?),
+                    then: Vec::new(),
+                    otherwise: None,
+                });
+            } else {
+                let node = Node::Variable(variable(tag)?);
+                stack.last_mut().unwrap().nodes().push(node);
+            }
+            rest = &rest[start + tag_len + 2..];
+        }
+        push_text(&mut stack, rest);
+        if let Some(variable) = stack.last().and_then(|frame| frame.variable) {
+            return Err(Error::UnclosedIf(variable.into()));
+        }
+        Ok(Self {
+            nodes: stack.pop().unwrap().then,
+        })
+    }
+
+    pub fn render(&self, variables: &Variables) -> String {
+        let mut out = String::new();
+        render(&self.nodes, variables, &mut out);
+        out
+    }
+
+    /// Whether the template refers to `variable` anywhere.
+    pub fn uses(&self, variable: Variable) -> bool {
+        fn uses(nodes: &[Node], variable: Variable) -> bool {
+            nodes.iter().any(|node| match node {
+                Node::Text(_) => false,
+                Node::Variable(x) => *x == variable,
+                Node::If {
+                    variable: x,
+                    then,
+                    otherwise,
+                } => *x == variable || uses(then, variable) || uses(otherwise, variable),
+            })
+        }
+        uses(&self.nodes, variable)
+    }
+}
+
+fn variable(name: &str) -> Result<Variable, Error> {
+    let name = name.trim();
+    Variable::from_str(name).map_err(|_| Error::UnknownVariable(name.into()))
+}
+
+fn render(nodes: &[Node], variables: &Variables, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Variable(variable) => out.push_str(variables.get(*variable)),
+            Node::If {
+                variable,
+                then,
+                otherwise,
+            } => {
+                if variables.get(*variable).trim().is_empty() {
+                    render(otherwise, variables, out);
+                } else {
+                    render(then, variables, out);
+                }
+            }
+        }
+    }
+}
+
+/// Split a leading speaker name off a line, as in 【雪菜】「おはよう」 or
+/// 雪菜「おはよう」.
+pub fn split_speaker(text: &str) -> (Option<&str>, &str) {
+    let text = text.trim();
+    if let Some((name, line)) = text
+        .strip_prefix('【')
+        .and_then(|rest| rest.split_once('】'))
+    {
+        if !name.trim().is_empty() {
+            return (Some(name.trim()), line.trim_start());
+        }
+    }
+    if let Some(idx) = text.find(['「', '『']) {
+        let name = text[..idx].trim();
+        let plausible = !name.is_empty()
+            && name.chars().count() <= 16
+            && !name.contains(['。', '、', '！', '？', '…', '」', '』']);
+        if plausible {
+            return (Some(name), &text[idx..]);
+        }
+    }
+    (None, text)
+}
+
+/// Dictionary glosses of the best segmentation, one word per line.
+pub fn gloss_summary(root: &Root) -> String {
+    let mut lines = Vec::new();
+    for segment in root.segments() {
+        let Segment::Clauses(clauses) = segment else {
+            continue;
+        };
+        for romanized in clauses.first().iter().flat_map(|x| x.romanized()) {
+            let word = romanized.term().best();
+            let glosses = glosses(word);
+            if glosses.is_empty() {
+                continue;
+            }
+            let meta = word.meta();
+            let head = if meta.text() == meta.kana() {
+                meta.text().to_string()
+            } else {
+                format!("{} 【{}】", meta.text(), meta.kana())
+            };
+            lines.push(format!("{}: {}", head, glosses.join("; ")));
+        }
+    }
+    lines.join("\n")
+}
+
+/// The first few glosses of a word. Conjugated words fall back to the
+/// glosses of their dictionary form; compounds list one per component.
+fn glosses(word: &Word) -> Vec<&str> {
+    match word {
+        Word::Plain(plain) => {
+            let glosses = match plain.gloss() {
+                [] => plain.conj().first().map(|x| x.gloss()).unwrap_or_default(),
+                glosses => glosses,
+            };
+            glosses.iter().take(3).map(|x| x.gloss()).collect()
+        }
+        Word::Compound(compound) => compound
+            .components()
+            .iter()
+            .filter_map(|x| glosses(x.best()).first().copied())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables {
+            speaker: "雪菜".into(),
+            target_language: "English".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_variables_and_conditionals() {
+        let template = Template::parse(
+            "Into {{ target_language }}.{{#if speaker}} Spoken by {{speaker}}.{{/if}}\
+             {{#if game_title}} From {{game_title}}.{{else}} Unknown game.{{/if}}",
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&variables()),
+            "Into English. Spoken by 雪菜. Unknown game."
+        );
+        assert!(template.uses(Variable::GameTitle));
+        assert!(!template.uses(Variable::Glossary));
+    }
+
+    #[test]
+    fn conditionals_nest() {
+        let template =
+            Template::parse("{{#if speaker}}{{#if gloss}}a{{else}}b{{/if}}{{/if}}").unwrap();
+        assert_eq!(template.render(&variables()), "b");
+        assert_eq!(template.render(&Variables::default()), "");
+    }
+
+    #[test]
+    fn plain_prompt_is_unchanged() {
+        let prompt = "Translate into English. Use {braces} freely.";
+        let template = Template::parse(prompt).unwrap();
+        assert_eq!(template.render(&Variables::default()), prompt);
+    }
+
+    #[test]
+    fn reports_malformed_templates() {
+        for (source, error) in [
+            ("{{speeker}}", Error::UnknownVariable("speeker".into())),
+            ("ab{{speaker", Error::UnclosedTag(2)),
+            ("{{else}}", Error::UnmatchedElse),
+            ("{{/if}}", Error::UnmatchedEndIf),
+            ("{{#if gloss}}", Error::UnclosedIf("gloss")),
+            ("{{#if gloss}}{{else}}{{else}}{{/if}}", Error::UnmatchedElse),
+        ] {
+            assert_eq!(Template::parse(source), Err(error), "{}", source);
+        }
+    }
+
+    #[test]
+    fn default_presets_parse() {
+        for preset in PromptPreset::defaults() {
+            Template::parse(&preset.template).unwrap();
+        }
+    }
+
+    #[test]
+    fn splits_speaker() {
+        assert_eq!(
+            split_speaker("【雪菜】「おはよう」"),
+            (Some("雪菜"), "「おはよう」")
+        );
+        assert_eq!(
+            split_speaker("雪菜「おはよう」"),
+            (Some("雪菜"), "「おはよう」")
+        );
+        assert_eq!(split_speaker("「おはよう」"), (None, "「おはよう」"));
+        assert_eq!(
+            split_speaker("今日は、「晴れ」らしい"),
+            (None, "今日は、「晴れ」らしい")
+        );
+    }
+
+    #[test]
+    fn summarizes_glosses() {
+        let root: Root = serde_json::from_value(json!([
+            [[
+                [
+                    ["neko", {"reading": "猫 【ねこ】", "text": "猫", "kana": "ねこ",
+                        "score": 10, "seq": 1, "gloss": [{"pos": "[n]", "gloss": "cat"}]}, []],
+                    ["da", {"reading": "だ", "text": "だ", "kana": "だ", "score": 1, "seq": 2,
+                        "gloss": [{"pos": "[cop]", "gloss": "be; is"}]}, []]
+                ],
+                11
+            ]]
+        ]))
+        .unwrap();
+        assert_eq!(gloss_summary(&root), "猫 【ねこ】: cat\nだ: be; is");
+    }
+}
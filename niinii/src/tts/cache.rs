@@ -0,0 +1,163 @@
+//! Synthesized audio, kept so that replaying a line does not synthesize it
+//! again. Least recently used clips are evicted once the cache is full.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use super::protocol::{AudioQuery, Prosody};
+use crate::support::hash::fnv1a;
+
+/// Everything that goes into a clip.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// Backend and its configuration, see [`super::engine_id`].
+    engine: String,
+    style: u32,
+    input: Input,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Input {
+    Text { text: String, prosody: [u32; 4] },
+    Query(String),
+}
+
+impl CacheKey {
+    pub fn text(engine: &str, style: u32, text: &str, prosody: &Prosody) -> Self {
+        let Prosody {
+            speed,
+            pitch,
+            intonation,
+            volume,
+        } = prosody;
+        Self {
+            engine: engine.to_string(),
+            style,
+            input: Input::Text {
+                text: text.to_string(),
+                prosody: [speed, pitch, intonation, volume].map(|x| x.to_bits()),
+            },
+        }
+    }
+
+    pub fn query(engine: &str, style: u32, query: &AudioQuery) -> Self {
+        Self {
+            engine: engine.to_string(),
+            style,
+            input: Input::Query(serde_json::to_string(query).unwrap_or_default()),
+        }
+    }
+
+    /// Short name for the clip, used for exported files. Stable across
+    /// builds, so exporting a line again overwrites the same file.
+    pub fn digest(&self) -> String {
+        let input = match &self.input {
+            Input::Text { text, prosody } => format!("text\0{text}\0{prosody:?}"),
+            Input::Query(query) => format!("query\0{query}"),
+        };
+        let key = format!("{}\0{}\0{input}", self.engine, self.style);
+        format!("{:016x}", fnv1a(key.as_bytes()))
+    }
+}
+
+pub struct AudioCache {
+    clips: HashMap<CacheKey, Arc<[u8]>>,
+    /// Least recently used first.
+    order: VecDeque<CacheKey>,
+    size: usize,
+    /// In bytes.
+    capacity: usize,
+}
+
+impl AudioCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            clips: HashMap::new(),
+            order: VecDeque::new(),
+            size: 0,
+            capacity,
+        }
+    }
+
+    pub fn get(&mut self, key: &CacheKey) -> Option<Arc<[u8]>> {
+        let audio = self.clips.get(key)?.clone();
+        self.touch(key);
+        Some(audio)
+    }
+
+    /// Clips larger than the whole cache are not kept.
+    pub fn insert(&mut self, key: CacheKey, audio: Arc<[u8]>) {
+        if audio.len() > self.capacity {
+            return;
+        }
+        if let Some(old) = self.clips.insert(key.clone(), audio.clone()) {
+            self.size -= old.len();
+            self.touch(&key);
+        } else {
+            self.order.push_back(key);
+        }
+        self.size += audio.len();
+        while self.size > self.capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.clips.remove(&oldest) {
+                self.size -= evicted.len();
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(idx) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(idx).unwrap();
+            self.order.push_back(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(text: &str) -> CacheKey {
+        CacheKey::text("engine", 11, text, &Prosody::default())
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = AudioCache::new(10);
+        cache.insert(key("a"), Arc::from([0; 4]));
+        cache.insert(key("b"), Arc::from([1; 4]));
+        assert!(cache.get(&key("a")).is_some());
+        cache.insert(key("c"), Arc::from([2; 4]));
+        assert!(cache.get(&key("b")).is_none());
+        assert_eq!(cache.get(&key("a")).as_deref(), Some(&[0; 4][..]));
+        assert!(cache.get(&key("c")).is_some());
+
+        cache.insert(key("d"), Arc::from([3; 11]));
+        assert!(cache.get(&key("d")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+    }
+
+    #[test]
+    fn keys_tell_apart_delivery() {
+        let slow = Prosody {
+            speed: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(key("a"), key("a"));
+        assert_eq!(key("a").digest(), key("a").digest());
+        assert_ne!(key("a").digest(), key("b").digest());
+        assert_ne!(key("a"), CacheKey::text("engine", 11, "a", &slow));
+        assert_ne!(
+            key("a"),
+            CacheKey::text("engine", 2, "a", &Prosody::default())
+        );
+        assert_ne!(
+            key("a"),
+            CacheKey::text("other", 11, "a", &Prosody::default())
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! https://voicevox.github.io/voicevox_engine/api/
+//!
+//! The VOICEVOX engine HTTP API, also served by AivisSpeech. A line is
+//! turned into an audio query, which is then synthesized.
+
+use std::collections::HashSet;
+
+use openai::ConnectionPolicy;
+use reqwest::{IntoUrl, Url};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::runtime::Handle;
+
+use super::{
+    protocol::{AccentPhrase, AudioQuery, ModelData},
+    Error, TtsBackend,
+};
+
+pub struct Engine {
+    client: reqwest::Client,
+    api_base: Url,
+    runtime: Handle,
+    /// Styles whose models have been loaded.
+    initialized: HashSet<u32>,
+}
+
+impl Engine {
+    pub fn new(endpoint: &str, policy: ConnectionPolicy, runtime: Handle) -> Result<Self, Error> {
+        // `Url::join` replaces the last segment of a base without a trailing
+        // slash.
+        let api_base = format!("{}/", endpoint.trim_end_matches('/')).into_url()?;
+        let client = reqwest::Client::builder()
+            .timeout(policy.timeout)
+            .connect_timeout(policy.connect_timeout)
+            .build()?;
+        Ok(Self {
+            client,
+            api_base,
+            runtime,
+            initialized: HashSet::new(),
+        })
+    }
+
+    fn url(&self, path: &str, query: &[(&str, &str)]) -> Url {
+        let mut url = self.api_base.join(path).unwrap();
+        url.query_pairs_mut().extend_pairs(query);
+        url
+    }
+
+    /// Load the model for `speaker` up front. The engine would otherwise
+    /// load it during synthesis, which can take a while.
+    fn initialize_speaker(&mut self, speaker: u32) -> Result<(), Error> {
+        if self.initialized.contains(&speaker) {
+            return Ok(());
+        }
+        let speaker_param = speaker.to_string();
+        let url = self.url(
+            "initialize_speaker",
+            &[("speaker", &speaker_param), ("skip_reinit", "true")],
+        );
+        self.runtime
+            .block_on(self.client.post(url).send())?
+            .error_for_status()?;
+        self.initialized.insert(speaker);
+        Ok(())
+    }
+
+    /// POST `body` and parse the JSON response.
+    fn post<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        body: Option<&impl Serialize>,
+    ) -> Result<T, Error> {
+        self.runtime.block_on(async {
+            let mut request = self.client.post(url);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            let response = request.send().await?.error_for_status()?;
+            Ok(response.json().await?)
+        })
+    }
+}
+
+impl TtsBackend for Engine {
+    fn speakers(&mut self) -> Result<ModelData, Error> {
+        self.runtime.block_on(async {
+            let url = self.url("speakers", &[]);
+            let response = self.client.get(url).send().await?.error_for_status()?;
+            Ok(response.json().await?)
+        })
+    }
+
+    fn audio_query(&mut self, text: &str, style: u32) -> Result<AudioQuery, Error> {
+        self.initialize_speaker(style)?;
+        let style = style.to_string();
+        let url = self.url("audio_query", &[("text", text), ("speaker", &style)]);
+        self.post(url, None::<&()>)
+    }
+
+    fn mora_data(
+        &mut self,
+        accent_phrases: &[AccentPhrase],
+        style: u32,
+    ) -> Result<Vec<AccentPhrase>, Error> {
+        let style = style.to_string();
+        let url = self.url("mora_data", &[("speaker", &style)]);
+        self.post(url, Some(&accent_phrases))
+    }
+
+    fn synthesis(&mut self, query: &AudioQuery, style: u32) -> Result<Vec<u8>, Error> {
+        self.initialize_speaker(style)?;
+        let style = style.to_string();
+        let url = self.url("synthesis", &[("speaker", &style)]);
+        self.runtime.block_on(async {
+            let response = self
+                .client
+                .post(url)
+                .json(query)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(response.bytes().await?.to_vec())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openai::mock::{MockResponse, MockServer};
+    use serde_json::json;
+
+    use super::*;
+    use crate::tts::protocol::Prosody;
+
+    fn accent_phrases() -> serde_json::Value {
+        json!([{
+            "moras": [{
+                "text": "ア",
+                "consonant": null,
+                "consonant_length": null,
+                "vowel": "a",
+                "vowel_length": 0.125,
+                "pitch": 5.5,
+            }],
+            "accent": 1,
+            "pause_mora": null,
+            "is_interrogative": false,
+        }])
+    }
+
+    fn query() -> serde_json::Value {
+        json!({
+            "accent_phrases": accent_phrases(),
+            "speedScale": 1.0,
+            "pitchScale": 0.0,
+            "intonationScale": 1.0,
+            "volumeScale": 1.0,
+            "prePhonemeLength": 0.125,
+            "postPhonemeLength": 0.125,
+            "outputSamplingRate": 24000,
+            "outputStereo": false,
+            "kana": "ア'",
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn synthesizes_the_audio_query() {
+        let server = MockServer::start().await;
+        server.set_fallback("initialize_speaker", MockResponse::text(200, ""));
+        server.set_fallback("audio_query", MockResponse::json(query()));
+        server.set_fallback("synthesis", MockResponse::bytes("audio/wav", *b"RIFF"));
+        server.enqueue("mora_data", MockResponse::json(accent_phrases()));
+        let mut engine = Engine::new(
+            &server.url(),
+            ConnectionPolicy::default(),
+            Handle::current(),
+        )
+        .unwrap();
+        let prosody = Prosody {
+            speed: 1.5,
+            ..Default::default()
+        };
+        let (audio, query) = tokio::task::spawn_blocking(move || {
+            engine.synthesize("あ", 11, &prosody).unwrap();
+            let mut query = engine.audio_query("あ", 11).unwrap();
+            query.accent_phrases = engine.mora_data(&query.accent_phrases, 11).unwrap();
+            (engine.synthesis(&query, 11), query)
+        })
+        .await
+        .unwrap();
+        assert_eq!(audio.unwrap(), b"RIFF");
+        assert_eq!(serde_json::to_value(&query).unwrap(), self::query());
+
+        // The model is only loaded once.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 6);
+        assert_eq!(
+            requests[0].path,
+            "/initialize_speaker?speaker=11&skip_reinit=true"
+        );
+        assert_eq!(requests[1].path, "/audio_query?text=%E3%81%82&speaker=11");
+        assert_eq!(requests[2].path, "/synthesis?speaker=11");
+        assert_eq!(requests[2].json()["speedScale"], 1.5);
+        assert_eq!(requests[4].path, "/mora_data?speaker=11");
+        assert_eq!(requests[4].json(), accent_phrases());
+        assert_eq!(requests[5].json(), self::query());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn lists_speakers_and_surfaces_errors() {
+        let server = MockServer::start().await;
+        server.enqueue(
+            "speakers",
+            MockResponse::json(json!([{
+                "name": "四国めたん",
+                "speaker_uuid": "7ffcb7ce-00ec-4bdc-82cd-45a8889e43ff",
+                "styles": [{ "name": "ノーマル", "id": 2, "type": "talk" }],
+                "version": "0.15.0",
+            }])),
+        );
+        server.enqueue("initialize_speaker", MockResponse::text(200, ""));
+        server.enqueue("audio_query", MockResponse::text(422, "Unprocessable"));
+        let mut engine = Engine::new(
+            &server.url(),
+            ConnectionPolicy::default(),
+            Handle::current(),
+        )
+        .unwrap();
+        let (speakers, audio) = tokio::task::spawn_blocking(move || {
+            (
+                engine.speakers(),
+                engine.synthesize("こんにちは", 2, &Prosody::default()),
+            )
+        })
+        .await
+        .unwrap();
+        assert_eq!(speakers.unwrap().len(), 1);
+        match audio {
+            Err(Error::Http(err)) => assert_eq!(err.status().map(|s| s.as_u16()), Some(422)),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
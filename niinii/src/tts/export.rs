@@ -0,0 +1,104 @@
+//! Writing clips out as audio files, e.g. for the audio field of a
+//! flashcard.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use super::{cache::CacheKey, program, Error};
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter,
+)]
+pub enum ExportFormat {
+    #[default]
+    #[strum(serialize = "WAV")]
+    Wav,
+    /// Ogg Opus, encoded with ffmpeg.
+    Opus,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "wav",
+            ExportFormat::Opus => "opus",
+        }
+    }
+}
+
+/// Encode `audio` as `format`. WAV from the backend is kept as is, anything
+/// else goes through `ffmpeg`.
+pub fn encode(audio: &[u8], format: ExportFormat, ffmpeg: &str) -> Result<Vec<u8>, Error> {
+    if format == ExportFormat::Wav && audio.starts_with(b"RIFF") {
+        return Ok(audio.to_vec());
+    }
+    let mut command = Command::new(ffmpeg);
+    command.args(["-hide_banner", "-loglevel", "error", "-i", "pipe:0"]);
+    match format {
+        ExportFormat::Wav => command.args(["-f", "wav", "pipe:1"]),
+        ExportFormat::Opus => {
+            command.args(["-c:a", "libopus", "-b:a", "48k", "-f", "opus", "pipe:1"])
+        }
+    };
+    program::pipe(command, audio).map_err(|err| match err {
+        Error::Io(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Error::Command(format!("{} not found", ffmpeg))
+        }
+        err => err,
+    })
+}
+
+/// Write a clip into `dir`, named after its key so that exporting the same
+/// clip twice gives the same file.
+pub fn write(
+    dir: &Path,
+    key: &CacheKey,
+    audio: &[u8],
+    format: ExportFormat,
+    ffmpeg: &str,
+) -> Result<PathBuf, Error> {
+    let encoded = encode(audio, format, ffmpeg)?;
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("niinii-{}.{}", key.digest(), format.extension()));
+    std::fs::write(&path, encoded)?;
+    Ok(path)
+}
+
+/// Anki's reference to a file in its media folder.
+pub fn sound_tag(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    format!("[sound:{}]", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tts::protocol::Prosody;
+
+    #[test]
+    fn writes_wav_as_is() {
+        let dir = std::env::temp_dir().join(format!("niinii-export-{}", std::process::id()));
+        let key = CacheKey::text("engine", 11, "あ", &Prosody::default());
+        let path = write(&dir, &key, b"RIFF....WAVE", ExportFormat::Wav, "ffmpeg").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"RIFF....WAVE");
+        assert_eq!(
+            sound_tag(&path),
+            format!("[sound:niinii-{}.wav]", key.digest())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_encoder_is_reported() {
+        let err = encode(b"ID3", ExportFormat::Opus, "niinii-no-such-ffmpeg").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Command Error: niinii-no-such-ffmpeg not found"
+        );
+    }
+}
@@ -1,13 +1,49 @@
-use std::{path::PathBuf, thread::JoinHandle};
+//! Text-to-speech. A worker thread owns the audio output and a
+//! [`TtsBackend`], which turns lines into audio. Backends:
+//!
+//! - VOICEVOX core, linked in-process (Windows, `voicevox` feature).
+//! - A VOICEVOX-compatible engine over HTTP, e.g. VOICEVOX or AivisSpeech.
+//! - An OpenAI-compatible `/v1/audio/speech` endpoint.
+//! - A local program that reads the line on stdin and writes audio to
+//!   stdout.
+//!
+//! Playback needs the `tts` feature; without it every request fails with
+//! [`Error::NotSupported`].
 
-use once_cell::sync::OnceCell;
-use std::sync::mpsc::Sender;
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
-use crate::settings::Settings;
+use openai::ConnectionPolicy;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+use tokio::{runtime::Handle, sync::oneshot};
 
-use self::protocol::ModelData;
+use crate::settings::{Settings, TtsBackendType, TtsSettings};
 
+use self::{
+    cache::{AudioCache, CacheKey},
+    export::ExportFormat,
+    protocol::{AccentPhrase, AudioQuery, ModelData, Prosody},
+};
+
+pub mod cache;
+pub mod engine;
+pub mod export;
+#[cfg(feature = "tts")]
+mod player;
+pub mod program;
 pub mod protocol;
+pub mod speech;
+#[cfg(feature = "voicevox")]
+pub mod voicevox;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -17,148 +53,680 @@ pub enum Error {
     Channel,
     #[error("Unsupported: closed")]
     NotSupported,
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Speech(#[from] openai::Error),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("VOICEVOX Error: {0}")]
+    Voicevox(&'static str),
+    #[error("Command Error: {0}")]
+    Command(String),
+    #[error("Playback Error: {0}")]
+    Playback(String),
+    #[error("Startup Error: {0}")]
+    Startup(String),
+}
+
+/// Turns text into audio. Driven from the TTS thread one request at a time,
+/// so implementations may block.
+pub trait TtsBackend {
+    /// Voices on offer, grouped by speaker. Empty if the backend cannot
+    /// list them.
+    fn speakers(&mut self) -> Result<ModelData, Error>;
+    /// Synthesize `text` into audio in a container rodio can decode.
+    /// `style` is a style ID from [`speakers`](Self::speakers); backends
+    /// without styles ignore it.
+    ///
+    /// By default the line goes through an audio query with `prosody`
+    /// applied.
+    fn synthesize(&mut self, text: &str, style: u32, prosody: &Prosody) -> Result<Vec<u8>, Error> {
+        let mut query = self.audio_query(text, style)?;
+        prosody.apply(&mut query);
+        self.synthesis(&query, style)
+    }
+    /// How `text` would be read: accent phrases, moras and their pitch.
+    fn audio_query(&mut self, _text: &str, _style: u32) -> Result<AudioQuery, Error> {
+        Err(Error::NotSupported)
+    }
+    /// Pitch and lengths of moras predicted anew, after accents were edited.
+    fn mora_data(
+        &mut self,
+        _accent_phrases: &[AccentPhrase],
+        _style: u32,
+    ) -> Result<Vec<AccentPhrase>, Error> {
+        Err(Error::NotSupported)
+    }
+    /// Synthesize a possibly edited audio query.
+    fn synthesis(&mut self, _query: &AudioQuery, _style: u32) -> Result<Vec<u8>, Error> {
+        Err(Error::NotSupported)
+    }
+}
+
+/// Open the backend selected in `settings`. HTTP backends run their
+/// requests on `runtime`.
+pub fn open(settings: &Settings, runtime: Handle) -> Result<Box<dyn TtsBackend>, Error> {
+    let tts = &settings.tts;
+    Ok(match tts.backend {
+        TtsBackendType::VoicevoxCore => open_vvcore(settings)?,
+        TtsBackendType::VoicevoxEngine => Box::new(engine::Engine::new(
+            &tts.engine_endpoint,
+            connection_policy(settings),
+            runtime,
+        )?),
+        TtsBackendType::OpenAiSpeech => Box::new(speech::Speech::new(settings, runtime)),
+        TtsBackendType::Command => Box::new(program::Program::new(&tts.command)),
+    })
+}
+
+/// Timeouts of HTTP backends, shared with the translator.
+fn connection_policy(settings: &Settings) -> ConnectionPolicy {
+    ConnectionPolicy {
+        timeout: Duration::from_millis(settings.chat.timeout),
+        connect_timeout: Duration::from_millis(settings.chat.connection_timeout),
+    }
+}
+
+#[cfg(feature = "voicevox")]
+fn open_vvcore(settings: &Settings) -> Result<Box<dyn TtsBackend>, Error> {
+    Ok(Box::new(voicevox::Core::new(
+        settings.vv_model_path.as_ref(),
+    )?))
+}
+
+#[cfg(not(feature = "voicevox"))]
+fn open_vvcore(_settings: &Settings) -> Result<Box<dyn TtsBackend>, Error> {
+    Err(Error::NotSupported)
+}
+
+/// Identifies the backend and whatever in its configuration changes how a
+/// line sounds.
+fn engine_id(settings: &Settings) -> String {
+    let tts = &settings.tts;
+    match tts.backend {
+        TtsBackendType::VoicevoxCore => format!("core:{}", settings.vv_model_path),
+        TtsBackendType::VoicevoxEngine => format!("engine:{}", tts.engine_endpoint),
+        TtsBackendType::OpenAiSpeech => format!(
+            "speech:{}:{}:{}:{}",
+            tts.speech_endpoint, tts.speech_model.0, tts.speech_voice, tts.speech_instructions
+        ),
+        TtsBackendType::Command => format!("command:{}", tts.command),
+    }
+}
+
+/// Something to say.
+#[derive(Debug, Clone)]
+pub enum Utterance {
+    Text {
+        text: String,
+        style: u32,
+        prosody: Prosody,
+    },
+    /// An audio query, possibly edited.
+    Query { query: AudioQuery, style: u32 },
+}
+
+impl Utterance {
+    /// `text` in the voice and delivery `settings` give it.
+    pub fn line(settings: &TtsSettings, text: impl Into<String>) -> Self {
+        let text = text.into();
+        Utterance::Text {
+            style: settings.style_for(&text),
+            prosody: settings.prosody,
+            text,
+        }
+    }
+
+    /// What is said, for display.
+    pub fn text(&self) -> String {
+        match self {
+            Utterance::Text { text, .. } => text.clone(),
+            Utterance::Query { query, .. } => query
+                .kana
+                .clone()
+                .unwrap_or_else(|| protocol::kana(&query.accent_phrases)),
+        }
+    }
+
+    fn key(&self, engine: &str) -> CacheKey {
+        match self {
+            Utterance::Text {
+                text,
+                style,
+                prosody,
+            } => CacheKey::text(engine, *style, text, prosody),
+            Utterance::Query { query, style } => CacheKey::query(engine, *style, query),
+        }
+    }
+}
+
+/// Where and how clips are exported.
+struct ExportOptions {
+    dir: PathBuf,
+    format: ExportFormat,
+    ffmpeg: String,
+}
+
+/// Owns the backend on the TTS thread.
+struct Worker {
+    backend: Box<dyn TtsBackend>,
+    cache: AudioCache,
+    engine: String,
+}
+
+#[cfg_attr(not(feature = "tts"), allow(dead_code))]
+impl Worker {
+    /// Audio for `utterance`, synthesized only if it is not cached.
+    fn render(&mut self, utterance: &Utterance) -> Result<(CacheKey, Arc<[u8]>), Error> {
+        let key = utterance.key(&self.engine);
+        if let Some(audio) = self.cache.get(&key) {
+            return Ok((key, audio));
+        }
+        let audio: Arc<[u8]> = match utterance {
+            Utterance::Text {
+                text,
+                style,
+                prosody,
+            } => self.backend.synthesize(text, *style, prosody)?,
+            Utterance::Query { query, style } => self.backend.synthesis(query, *style)?,
+        }
+        .into();
+        self.cache.insert(key.clone(), audio.clone());
+        Ok((key, audio))
+    }
+
+    fn export(
+        &mut self,
+        utterances: &[Utterance],
+        options: &ExportOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        utterances
+            .iter()
+            .map(|utterance| {
+                let (key, audio) = self.render(utterance)?;
+                export::write(&options.dir, &key, &audio, options.format, &options.ffmpeg)
+            })
+            .collect()
+    }
+}
+
+/// What happens to a line requested while another is playing.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter,
+)]
+pub enum PlaybackMode {
+    /// Cut it off.
+    #[default]
+    Interrupt,
+    /// Play after it.
+    Queue,
+}
+
+/// Where playback is at, as last reported by the TTS thread.
+#[derive(Debug, Clone, Default)]
+pub struct Playback {
+    /// Lines in the queue, the one playing first.
+    pub lines: Vec<String>,
+    /// Into the line playing.
+    pub position: Duration,
+    /// Of the line playing, if known.
+    pub duration: Option<Duration>,
+    pub paused: bool,
+    /// A line is being synthesized.
+    pub synthesizing: bool,
 }
 
 #[allow(dead_code)]
 enum Request {
-    Text(String),
+    Speak {
+        utterance: Utterance,
+        mode: PlaybackMode,
+    },
+    Export {
+        utterances: Vec<Utterance>,
+        options: ExportOptions,
+        tx: oneshot::Sender<Result<Vec<PathBuf>, Error>>,
+    },
+    AudioQuery {
+        text: String,
+        style: u32,
+        tx: oneshot::Sender<Result<AudioQuery, Error>>,
+    },
+    MoraData {
+        accent_phrases: Vec<AccentPhrase>,
+        style: u32,
+        tx: oneshot::Sender<Result<Vec<AccentPhrase>, Error>>,
+    },
+    Pause,
+    Resume,
+    Skip,
     Stop,
 }
 
 struct State {
     tx_channel: Sender<Request>,
-    model_data: ModelData,
+    /// Failures of requests that have no one waiting on them.
+    rx_error: Receiver<Error>,
     _thread: JoinHandle<()>,
 }
 
+/// Where opening the backend is at. It is opened on the TTS thread, so the
+/// UI never waits on it; requests made meanwhile queue up.
+enum Startup {
+    Stopped,
+    Starting {
+        state: State,
+        rx_ready: oneshot::Receiver<Result<ModelData, Error>>,
+    },
+    Running {
+        state: State,
+        model_data: Arc<ModelData>,
+    },
+    /// Not retried before [`RETRY_INTERVAL`] has passed.
+    Failed {
+        error: String,
+        at: Instant,
+    },
+}
+
+impl Startup {
+    /// Move on from `Starting` if the TTS thread has reported back.
+    fn poll(&mut self) {
+        let Startup::Starting { rx_ready, .. } = self else {
+            return;
+        };
+        let ready = match rx_ready.try_recv() {
+            Ok(ready) => ready,
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Err(oneshot::error::TryRecvError::Closed) => Err(Error::Channel),
+        };
+        let Startup::Starting { state, .. } = std::mem::replace(self, Startup::Stopped) else {
+            unreachable!()
+        };
+        *self = match ready {
+            Ok(model_data) => Startup::Running {
+                state,
+                model_data: Arc::new(model_data),
+            },
+            Err(err) => {
+                tracing::error!(%err, "failed to start speech");
+                Startup::Failed {
+                    error: err.to_string(),
+                    at: Instant::now(),
+                }
+            }
+        };
+    }
+
+    fn state(&self) -> Option<&State> {
+        match self {
+            Startup::Starting { state, .. } | Startup::Running { state, .. } => Some(state),
+            Startup::Stopped | Startup::Failed { .. } => None,
+        }
+    }
+}
+
+/// How many spoken lines are remembered for export.
+const HISTORY_LEN: usize = 100;
+/// How often the TTS thread reports playback progress.
+#[cfg_attr(not(feature = "tts"), allow(dead_code))]
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+/// How long a backend that failed to open is left alone before trying again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
 pub struct TtsEngine {
-    vvcore_path: PathBuf,
-    state: OnceCell<State>,
+    /// Snapshot the backend is opened with.
+    settings: Settings,
+    startup: Mutex<Startup>,
+    /// Spoken lines, oldest first.
+    history: Mutex<VecDeque<Utterance>>,
+    playback: Arc<Mutex<Playback>>,
 }
 
 impl TtsEngine {
     pub fn new(settings: &Settings) -> Self {
         TtsEngine {
-            vvcore_path: settings.vv_model_path.clone().into(),
-            state: OnceCell::new(),
+            settings: settings.clone(),
+            startup: Mutex::new(Startup::Stopped),
+            history: Mutex::new(VecDeque::new()),
+            playback: Arc::default(),
+        }
+    }
+
+    /// Synthesis and playback failures are reported through
+    /// [`poll_error`](Self::poll_error).
+    pub fn request_tts(&self, utterance: Utterance, mode: PlaybackMode) -> Result<(), Error> {
+        self.send(Request::Speak {
+            utterance: utterance.clone(),
+            mode,
+        })?;
+        let mut history = self.history.lock().unwrap();
+        history.push_back(utterance);
+        while history.len() > HISTORY_LEN {
+            history.pop_front();
         }
+        Ok(())
+    }
+
+    /// Spoken lines, oldest first.
+    pub fn history(&self) -> MutexGuard<'_, VecDeque<Utterance>> {
+        self.history.lock().unwrap()
+    }
+
+    /// Write `utterances` out as audio files, synthesizing those no longer
+    /// cached. Resolves to the paths written.
+    pub fn export(
+        &self,
+        utterances: Vec<Utterance>,
+        settings: &TtsSettings,
+    ) -> Result<oneshot::Receiver<Result<Vec<PathBuf>, Error>>, Error> {
+        let (tx, rx) = oneshot::channel();
+        let options = ExportOptions {
+            dir: PathBuf::from(&settings.export_dir),
+            format: settings.export_format,
+            ffmpeg: settings.ffmpeg_path.clone(),
+        };
+        self.send(Request::Export {
+            utterances,
+            options,
+            tx,
+        })?;
+        Ok(rx)
     }
 
-    pub fn request_tts(&self, text: impl Into<String>) -> Result<(), Error> {
+    /// Resolves to how the backend would read `text`.
+    pub fn audio_query(
+        &self,
+        text: impl Into<String>,
+        style: u32,
+    ) -> Result<oneshot::Receiver<Result<AudioQuery, Error>>, Error> {
         let text = text.into();
-        let state = self.state()?;
-        state
-            .tx_channel
-            .send(Request::Text(text))
-            .map_err(|_| Error::Channel)
+        let (tx, rx) = oneshot::channel();
+        self.send(Request::AudioQuery { text, style, tx })?;
+        Ok(rx)
+    }
+
+    /// Resolves to `accent_phrases` with their pitch and lengths predicted
+    /// anew.
+    pub fn mora_data(
+        &self,
+        accent_phrases: Vec<AccentPhrase>,
+        style: u32,
+    ) -> Result<oneshot::Receiver<Result<Vec<AccentPhrase>, Error>>, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Request::MoraData {
+            accent_phrases,
+            style,
+            tx,
+        })?;
+        Ok(rx)
+    }
+
+    fn send(&self, request: Request) -> Result<(), Error> {
+        let startup = self.start()?;
+        match &*startup {
+            Startup::Failed { error, .. } => Err(Error::Startup(error.clone())),
+            startup => {
+                let state = startup.state().ok_or(Error::Channel)?;
+                state.tx_channel.send(request).map_err(|_| Error::Channel)
+            }
+        }
+    }
+
+    /// Say the last line again, from the start.
+    pub fn replay(&self) -> Result<(), Error> {
+        let last = self.history().back().cloned();
+        match last {
+            Some(utterance) => self.request_tts(utterance, PlaybackMode::Interrupt),
+            None => Ok(()),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.control(Request::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.control(Request::Resume);
+    }
+
+    /// Move on to the next line in the queue.
+    pub fn skip(&self) {
+        self.control(Request::Skip);
     }
 
+    /// Stop playing and clear the queue.
     pub fn stop(&self) {
-        if let Ok(state) = self.state() {
-            let _ = state.tx_channel.send(Request::Stop);
+        self.control(Request::Stop);
+    }
+
+    /// Playback controls do nothing if the backend is not running.
+    fn control(&self, request: Request) {
+        if let Some(state) = self.startup.lock().unwrap().state() {
+            let _ = state.tx_channel.send(request);
         }
     }
 
-    pub fn get_model_data(&self) -> Result<&ModelData, Error> {
-        let state = self.state()?;
-        Ok(&state.model_data)
+    pub fn playback(&self) -> Playback {
+        self.playback.lock().unwrap().clone()
+    }
+
+    /// Next failure of a request made earlier.
+    pub fn poll_error(&self) -> Option<Error> {
+        let startup = self.startup.lock().unwrap();
+        startup.state()?.rx_error.try_recv().ok()
     }
 
-    #[cfg(not(feature = "voicevox"))]
-    fn state(&self) -> Result<&State, Error> {
+    /// Starts the backend if it is not running yet. `None` while it starts.
+    pub fn get_model_data(&self) -> Result<Option<Arc<ModelData>>, Error> {
+        let startup = self.start()?;
+        match &*startup {
+            Startup::Running { model_data, .. } => Ok(Some(model_data.clone())),
+            Startup::Failed { error, .. } => Err(Error::Startup(error.clone())),
+            Startup::Stopped | Startup::Starting { .. } => Ok(None),
+        }
+    }
+
+    /// Voices of the backend, if it has been started.
+    pub fn model_data(&self) -> Option<Arc<ModelData>> {
+        let mut startup = self.startup.lock().unwrap();
+        startup.poll();
+        match &*startup {
+            Startup::Running { model_data, .. } => Some(model_data.clone()),
+            _ => None,
+        }
+    }
+
+    /// Open the backend on the TTS thread unless it is open, opening or
+    /// failed to open within the last [`RETRY_INTERVAL`].
+    fn start(&self) -> Result<MutexGuard<'_, Startup>, Error> {
+        let mut startup = self.startup.lock().unwrap();
+        startup.poll();
+        let retry = match &*startup {
+            Startup::Stopped => true,
+            Startup::Failed { at, .. } => at.elapsed() >= RETRY_INTERVAL,
+            Startup::Starting { .. } | Startup::Running { .. } => false,
+        };
+        if retry {
+            let (state, rx_ready) = self.spawn()?;
+            *startup = Startup::Starting { state, rx_ready };
+        }
+        Ok(startup)
+    }
+
+    #[cfg(not(feature = "tts"))]
+    fn spawn(&self) -> Result<(State, oneshot::Receiver<Result<ModelData, Error>>), Error> {
         let Self {
-            vvcore_path: _vvcore_path,
-            state: _state,
+            settings: _settings,
+            startup: _startup,
+            history: _history,
+            playback: _playback,
         } = self;
         Err(Error::NotSupported)
     }
 
-    #[cfg(feature = "voicevox")]
-    fn state(&self) -> Result<&State, Error> {
-        self.state.get_or_try_init(|| {
-            use tokio::sync::oneshot;
-            use vvcore::*;
+    #[cfg(feature = "tts")]
+    fn spawn(&self) -> Result<(State, oneshot::Receiver<Result<ModelData, Error>>), Error> {
+        use std::sync::mpsc::RecvTimeoutError;
 
-            let models_path = self.vvcore_path.join("model");
-            tracing::debug!(?models_path, "check");
-            if !models_path.try_exists()? {
-                return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
-            }
-            std::env::set_var(
-                "VV_MODELS_ROOT_DIR",
-                models_path.into_os_string().into_string().unwrap(),
-            );
-
-            let open_jtalk_dic_path = self.vvcore_path.join("open_jtalk_dic_utf_8-1.11");
-            tracing::debug!(?open_jtalk_dic_path, "check");
-            if !open_jtalk_dic_path.try_exists()? {
-                return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
-            }
-            let open_jtalk_dic_str =
-                std::ffi::CString::new(open_jtalk_dic_path.into_os_string().into_string().unwrap());
-
-            let (tx_request, rx_request) = std::sync::mpsc::channel();
-            let (tx, rx) = oneshot::channel();
-
-            let thread = std::thread::spawn(move || {
-                let version = VoicevoxCore::get_version();
-                let supported_devices_json = VoicevoxCore::get_supported_devices_json();
-                tracing::debug!(version, supported_devices_json);
-                let metas_json = VoicevoxCore::get_metas_json();
-                let model_data: ModelData = serde_json::from_str(metas_json).unwrap();
-                tracing::debug!(?model_data);
-                tx.send(model_data).unwrap();
-
-                let vvcore = VoicevoxCore::new_from_options(
-                    AccelerationMode::CPU,
-                    0,
-                    false,
-                    open_jtalk_dic_str.unwrap().as_c_str(),
-                )
-                .unwrap();
-
-                let speaker_id: u32 = 11;
-                tracing::debug!(speaker_id, "load_model");
-                vvcore.load_model(speaker_id).unwrap();
-
-                let (_stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
-                let sink = rodio::Sink::try_new(&stream_handle).unwrap();
-
-                while let Ok(message) = rx_request.recv() {
-                    match message {
-                        Request::Text(text) => {
-                            tracing::debug!(speaker_id, text, "speaker");
-                            let wav = vvcore
-                                .tts_simple(&text, speaker_id)
-                                .unwrap()
-                                .as_slice()
-                                .to_vec();
-                            let cursor = std::io::Cursor::new(wav);
-                            let source = rodio::Decoder::new(cursor).unwrap();
-                            if !sink.empty() {
-                                // https://github.com/RustAudio/rodio/pull/494
-                                sink.clear();
-                                sink.play();
-                            }
-                            sink.append(source);
-                        }
-                        Request::Stop => {
-                            if !sink.empty() {
-                                sink.clear();
-                                sink.play();
-                            }
-                        }
+        let settings = self.settings.clone();
+        let engine = engine_id(&settings);
+        let runtime = Handle::current();
+        let playback = self.playback.clone();
+        let (tx_request, rx_request) = std::sync::mpsc::channel();
+        let (tx_error, rx_error) = std::sync::mpsc::channel();
+        let (tx, rx) = oneshot::channel();
+
+        // The backend is opened on the thread that uses it, since
+        // VOICEVOX core must stay on one thread.
+        let thread = std::thread::spawn(move || {
+            let opened = open(&settings, runtime)
+                .and_then(|backend| Ok((backend, player::Player::open(playback)?)));
+            let (backend, mut player) = match opened {
+                Ok(opened) => opened,
+                Err(err) => {
+                    let _ = tx.send(Err(err));
+                    return;
+                }
+            };
+            let mut worker = Worker {
+                backend,
+                cache: AudioCache::new(settings.tts.cache_size << 20),
+                engine,
+            };
+            // A backend that cannot list its voices is unlikely to speak
+            // either, so this fails the start and it is tried again later.
+            let model_data = match worker.backend.speakers() {
+                Ok(model_data) => model_data,
+                Err(err) => {
+                    let _ = tx.send(Err(err));
+                    return;
+                }
+            };
+            tracing::debug!(?model_data);
+            let _ = tx.send(Ok(model_data));
+
+            loop {
+                let request = match rx_request.recv_timeout(PROGRESS_INTERVAL) {
+                    Ok(request) => request,
+                    Err(RecvTimeoutError::Timeout) => {
+                        player.update(false);
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                let result = match request {
+                    Request::Speak { utterance, mode } => {
+                        tracing::debug!(?utterance, ?mode, "speak");
+                        player.update(true);
+                        worker
+                            .render(&utterance)
+                            .and_then(|(_, audio)| player.play(utterance.text(), audio, mode))
+                    }
+                    Request::Export {
+                        utterances,
+                        options,
+                        tx,
+                    } => {
+                        let _ = tx.send(worker.export(&utterances, &options));
+                        Ok(())
+                    }
+                    Request::AudioQuery { text, style, tx } => {
+                        let _ = tx.send(worker.backend.audio_query(&text, style));
+                        Ok(())
+                    }
+                    Request::MoraData {
+                        accent_phrases,
+                        style,
+                        tx,
+                    } => {
+                        let _ = tx.send(worker.backend.mora_data(&accent_phrases, style));
+                        Ok(())
+                    }
+                    Request::Pause => {
+                        player.pause();
+                        Ok(())
                     }
+                    Request::Resume => {
+                        player.resume();
+                        Ok(())
+                    }
+                    Request::Skip => {
+                        player.skip();
+                        Ok(())
+                    }
+                    Request::Stop => {
+                        player.stop();
+                        Ok(())
+                    }
+                };
+                if let Err(err) = result {
+                    tracing::error!(%err, "speech failed");
+                    let _ = tx_error.send(err);
                 }
-            });
+                player.update(false);
+            }
+        });
 
-            let model_data = rx.blocking_recv().unwrap();
+        let state = State {
+            tx_channel: tx_request,
+            rx_error,
+            _thread: thread,
+        };
+        Ok((state, rx))
+    }
+}
 
-            Ok::<_, Error>(State {
-                tx_channel: tx_request,
-                model_data,
-                _thread: thread,
-            })
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Says every line as its length, counting how often it was asked to.
+    #[derive(Default)]
+    struct Counter {
+        calls: Arc<Mutex<usize>>,
+    }
+
+    impl TtsBackend for Counter {
+        fn speakers(&mut self) -> Result<ModelData, Error> {
+            Ok(ModelData::new())
+        }
+
+        fn synthesize(
+            &mut self,
+            text: &str,
+            _style: u32,
+            _prosody: &Prosody,
+        ) -> Result<Vec<u8>, Error> {
+            *self.calls.lock().unwrap() += 1;
+            Ok(vec![text.len() as u8])
+        }
+    }
+
+    #[test]
+    fn lines_are_synthesized_once() {
+        let backend = Counter::default();
+        let calls = backend.calls.clone();
+        let mut worker = Worker {
+            backend: Box::new(backend),
+            cache: AudioCache::new(1 << 20),
+            engine: "counter".into(),
+        };
+        let line = |text: &str, speed| Utterance::Text {
+            text: text.into(),
+            style: 11,
+            prosody: Prosody {
+                speed,
+                ..Default::default()
+            },
+        };
+        let (key, audio) = worker.render(&line("あ", 1.0)).unwrap();
+        assert_eq!(&*audio, [3]);
+        let (again, _) = worker.render(&line("あ", 1.0)).unwrap();
+        assert_eq!(key, again);
+        assert_eq!(*calls.lock().unwrap(), 1);
+        worker.render(&line("あ", 1.5)).unwrap();
+        assert_eq!(*calls.lock().unwrap(), 2);
     }
 }
@@ -0,0 +1,90 @@
+//! Audio output on the TTS thread.
+
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rodio::Source;
+
+use super::{Error, Playback, PlaybackMode};
+
+struct Clip {
+    text: String,
+    duration: Option<Duration>,
+}
+
+pub(super) struct Player {
+    _stream: rodio::OutputStream,
+    sink: rodio::Sink,
+    /// Clips in the sink, the one playing first.
+    clips: VecDeque<Clip>,
+    status: Arc<Mutex<Playback>>,
+}
+
+impl Player {
+    pub fn open(status: Arc<Mutex<Playback>>) -> Result<Self, Error> {
+        let stream = rodio::OutputStreamBuilder::open_default_stream()
+            .map_err(|err| Error::Playback(err.to_string()))?;
+        let sink = rodio::Sink::connect_new(stream.mixer());
+        Ok(Self {
+            _stream: stream,
+            sink,
+            clips: VecDeque::new(),
+            status,
+        })
+    }
+
+    pub fn play(
+        &mut self,
+        text: String,
+        audio: Arc<[u8]>,
+        mode: PlaybackMode,
+    ) -> Result<(), Error> {
+        let source = rodio::Decoder::new(Cursor::new(audio))
+            .map_err(|err| Error::Playback(err.to_string()))?;
+        let duration = source.total_duration();
+        if mode == PlaybackMode::Interrupt {
+            self.stop();
+        }
+        self.sink.append(source);
+        self.clips.push_back(Clip { text, duration });
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if !self.sink.empty() {
+            // https://github.com/RustAudio/rodio/pull/494
+            self.sink.clear();
+            self.sink.play();
+        }
+        self.clips.clear();
+    }
+
+    pub fn pause(&self) {
+        self.sink.pause();
+    }
+
+    pub fn resume(&self) {
+        self.sink.play();
+    }
+
+    pub fn skip(&self) {
+        self.sink.skip_one();
+    }
+
+    /// Drop finished clips and publish where playback is at.
+    pub fn update(&mut self, synthesizing: bool) {
+        while self.clips.len() > self.sink.len() {
+            self.clips.pop_front();
+        }
+        let mut status = self.status.lock().unwrap();
+        status.lines = self.clips.iter().map(|clip| clip.text.clone()).collect();
+        status.position = self.sink.get_pos();
+        status.duration = self.clips.front().and_then(|clip| clip.duration);
+        status.paused = self.sink.is_paused();
+        status.synthesizing = synthesizing;
+    }
+}
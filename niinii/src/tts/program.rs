@@ -0,0 +1,108 @@
+//! A local program that reads the line on stdin and writes audio to stdout,
+//! e.g. `espeak-ng -v ja --stdin --stdout`.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use super::{
+    protocol::{ModelData, Prosody},
+    Error, TtsBackend,
+};
+
+pub struct Program {
+    command: String,
+}
+
+impl Program {
+    /// `command` is run by the platform shell.
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+impl TtsBackend for Program {
+    fn speakers(&mut self) -> Result<ModelData, Error> {
+        Ok(ModelData::new())
+    }
+
+    fn synthesize(
+        &mut self,
+        text: &str,
+        _style: u32,
+        _prosody: &Prosody,
+    ) -> Result<Vec<u8>, Error> {
+        if self.command.trim().is_empty() {
+            return Err(Error::Command("no command set".into()));
+        }
+        pipe(shell(&self.command), text.as_bytes())
+    }
+}
+
+/// Run `command` with `input` on stdin and collect its stdout.
+pub(super) fn pipe(mut command: Command, input: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Written from another thread so a program that starts writing before
+    // it has read everything cannot deadlock against us.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    // A program that does not read stdin closes it early.
+    if let Ok(Err(err)) = writer.join() {
+        tracing::debug!(%err, "failed to write to command");
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Command(format!(
+            "{}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_is_piped_through_the_command() {
+        let mut program = Program::new("cat");
+        assert_eq!(
+            program
+                .synthesize("こんにちは", 0, &Prosody::default())
+                .unwrap(),
+            "こんにちは".as_bytes()
+        );
+    }
+
+    #[test]
+    fn failure_is_reported_with_stderr() {
+        let mut program = Program::new("echo no voice >&2; exit 3");
+        match program.synthesize("こんにちは", 0, &Prosody::default()) {
+            Err(Error::Command(err)) => assert!(err.ends_with(": no voice"), "{err}"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
@@ -4,14 +4,232 @@ pub type ModelData = Vec<Model>;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Model {
-    name: String,
-    styles: Vec<Style>,
-    speaker_uuid: String,
-    version: String,
+    pub name: String,
+    pub styles: Vec<Style>,
+    pub speaker_uuid: String,
+    pub version: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Style {
-    name: String,
-    id: u32,
+    pub name: String,
+    pub id: u32,
+}
+
+/// Display name of style `id`, e.g. `四国めたん (ノーマル)`.
+pub fn style_name(models: &[Model], id: u32) -> Option<String> {
+    models.iter().find_map(|model| {
+        let style = model.styles.iter().find(|style| style.id == id)?;
+        Some(format!("{} ({})", model.name, style.name))
+    })
+}
+
+/// How VOICEVOX will read a line, as returned by `audio_query`. Edited and
+/// handed back for synthesis.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioQuery {
+    #[serde(rename = "accent_phrases")]
+    pub accent_phrases: Vec<AccentPhrase>,
+    pub speed_scale: f32,
+    pub pitch_scale: f32,
+    pub intonation_scale: f32,
+    pub volume_scale: f32,
+    pub pre_phoneme_length: f32,
+    pub post_phoneme_length: f32,
+    pub output_sampling_rate: u32,
+    pub output_stereo: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kana: Option<String>,
+    /// Fields only some engines know about, passed through untouched.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct AccentPhrase {
+    pub moras: Vec<Mora>,
+    /// 1-based index of the mora after which pitch drops. Equal to the
+    /// number of moras if it never does.
+    pub accent: usize,
+    /// Pause after the phrase.
+    pub pause_mora: Option<Mora>,
+    #[serde(default)]
+    pub is_interrogative: bool,
+}
+
+impl AccentPhrase {
+    pub fn text(&self) -> String {
+        self.moras.iter().map(|mora| mora.text.as_str()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Mora {
+    /// In katakana.
+    pub text: String,
+    pub consonant: Option<String>,
+    pub consonant_length: Option<f32>,
+    /// Upper case if devoiced.
+    pub vowel: String,
+    pub vowel_length: f32,
+    /// Zero if devoiced.
+    pub pitch: f32,
+}
+
+impl Mora {
+    pub fn is_devoiced(&self) -> bool {
+        matches!(self.vowel.as_str(), "A" | "I" | "U" | "E" | "O")
+    }
+}
+
+/// Accent phrases in the AquesTalk-style notation VOICEVOX reads as kana,
+/// e.g. `コンニチワ'/ミ_ナサン`. Re-reading this gives pitch and lengths
+/// that match edited accents.
+pub fn kana(accent_phrases: &[AccentPhrase]) -> String {
+    let mut kana = String::new();
+    for (idx, phrase) in accent_phrases.iter().enumerate() {
+        for (i, mora) in phrase.moras.iter().enumerate() {
+            if mora.is_devoiced() {
+                kana.push('_');
+            }
+            kana.push_str(&mora.text);
+            if i + 1 == phrase.accent {
+                kana.push('\'');
+            }
+        }
+        if phrase.is_interrogative {
+            kana.push('？');
+        }
+        if idx + 1 < accent_phrases.len() {
+            kana.push(if phrase.pause_mora.is_some() {
+                '、'
+            } else {
+                '/'
+            });
+        }
+    }
+    kana
+}
+
+/// Overall delivery, on top of the reading.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Prosody {
+    pub speed: f32,
+    /// Shift, where 0 leaves the pitch as is.
+    pub pitch: f32,
+    pub intonation: f32,
+    pub volume: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch: 0.0,
+            intonation: 1.0,
+            volume: 1.0,
+        }
+    }
+}
+
+impl Prosody {
+    pub fn apply(&self, query: &mut AudioQuery) {
+        query.speed_scale = self.speed;
+        query.pitch_scale = self.pitch;
+        query.intonation_scale = self.intonation;
+        query.volume_scale = self.volume;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn mora(text: &str, consonant: Option<&str>, vowel: &str) -> serde_json::Value {
+        json!({
+            "text": text,
+            "consonant": consonant,
+            "consonant_length": consonant.map(|_| 0.0625),
+            "vowel": vowel,
+            "vowel_length": 0.125,
+            "pitch": 5.5,
+        })
+    }
+
+    /// こんにちは、皆さん？ as read by VOICEVOX engine.
+    fn query() -> serde_json::Value {
+        json!({
+            "accent_phrases": [
+                {
+                    "moras": [
+                        mora("コ", Some("k"), "o"),
+                        mora("ン", None, "N"),
+                        mora("ニ", Some("n"), "i"),
+                        mora("チ", Some("ch"), "i"),
+                        mora("ワ", Some("w"), "a"),
+                    ],
+                    "accent": 5,
+                    "pause_mora": mora("、", None, "pau"),
+                    "is_interrogative": false,
+                },
+                {
+                    "moras": [
+                        mora("ミ", Some("m"), "i"),
+                        mora("ナ", Some("n"), "a"),
+                        mora("サ", Some("s"), "a"),
+                        mora("ン", None, "N"),
+                    ],
+                    "accent": 2,
+                    "pause_mora": null,
+                    "is_interrogative": true,
+                },
+            ],
+            "speedScale": 1.0,
+            "pitchScale": 0.0,
+            "intonationScale": 1.0,
+            "volumeScale": 1.0,
+            "prePhonemeLength": 0.125,
+            "postPhonemeLength": 0.125,
+            "pauseLengthScale": 1.0,
+            "outputSamplingRate": 24000,
+            "outputStereo": false,
+            "kana": "コンニチワ'、ミナ'サン？",
+        })
+    }
+
+    #[test]
+    fn audio_query_round_trips_unknown_fields() {
+        let query: AudioQuery = serde_json::from_value(query()).unwrap();
+        assert_eq!(query.accent_phrases[1].text(), "ミナサン");
+        assert_eq!(query.extra["pauseLengthScale"], 1.0);
+        assert_eq!(serde_json::to_value(&query).unwrap(), self::query());
+    }
+
+    #[test]
+    fn kana_marks_accents_pauses_and_devoicing() {
+        let mut query: AudioQuery = serde_json::from_value(query()).unwrap();
+        assert_eq!(kana(&query.accent_phrases), "コンニチワ'、ミナ'サン？");
+        query.accent_phrases[0].accent = 1;
+        query.accent_phrases[1].moras[0].vowel = "I".into();
+        query.accent_phrases[0].pause_mora = None;
+        assert_eq!(kana(&query.accent_phrases), "コ'ンニチワ/_ミナ'サン？");
+    }
+
+    #[test]
+    fn prosody_sets_the_scales() {
+        let mut query: AudioQuery = serde_json::from_value(query()).unwrap();
+        let prosody = Prosody {
+            speed: 1.5,
+            pitch: 0.05,
+            ..Default::default()
+        };
+        prosody.apply(&mut query);
+        assert_eq!(query.speed_scale, 1.5);
+        assert_eq!(query.pitch_scale, 0.05);
+        assert_eq!(query.intonation_scale, 1.0);
+    }
 }
@@ -0,0 +1,62 @@
+//! OpenAI-compatible `/v1/audio/speech`.
+
+use openai::{
+    audio::{AudioFormat, SpeechRequest},
+    ModelId,
+};
+use tokio::runtime::Handle;
+
+use super::{
+    protocol::{ModelData, Prosody},
+    Error, TtsBackend,
+};
+use crate::settings::Settings;
+
+pub struct Speech {
+    client: openai::Client,
+    model: ModelId,
+    voice: String,
+    instructions: Option<String>,
+    runtime: Handle,
+}
+
+impl Speech {
+    /// Credentials and timeouts are shared with the translator.
+    pub fn new(settings: &Settings, runtime: Handle) -> Self {
+        let c = &settings.chat;
+        let tts = &settings.tts;
+        let endpoint = if tts.speech_endpoint.is_empty() {
+            &c.api_endpoint
+        } else {
+            &tts.speech_endpoint
+        };
+        let policy = super::connection_policy(settings);
+        Self {
+            client: openai::Client::new(&settings.openai_api_key, endpoint.as_str(), policy),
+            model: tts.speech_model.clone(),
+            voice: tts.speech_voice.clone(),
+            instructions: Some(tts.speech_instructions.clone()).filter(|x| !x.is_empty()),
+            runtime,
+        }
+    }
+}
+
+impl TtsBackend for Speech {
+    /// Voices are free-form names, so there is nothing to list.
+    fn speakers(&mut self) -> Result<ModelData, Error> {
+        Ok(ModelData::new())
+    }
+
+    fn synthesize(&mut self, text: &str, _style: u32, prosody: &Prosody) -> Result<Vec<u8>, Error> {
+        let request = SpeechRequest::builder()
+            .model(self.model.clone())
+            .input(text.to_string())
+            .voice(self.voice.clone())
+            .maybe_instructions(self.instructions.clone())
+            .maybe_speed(Some(prosody.speed).filter(|speed| *speed != 1.0))
+            // Decodable without extra codecs.
+            .response_format(AudioFormat::Wav)
+            .build();
+        Ok(self.runtime.block_on(self.client.speech(request))?)
+    }
+}
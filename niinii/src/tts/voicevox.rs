@@ -0,0 +1,133 @@
+//! VOICEVOX core, linked in-process.
+
+use std::{ffi::CString, path::Path};
+
+use vvcore::*;
+
+use super::{
+    protocol::{self, AccentPhrase, AudioQuery, ModelData},
+    Error, TtsBackend,
+};
+
+impl From<ResultCode> for Error {
+    fn from(code: ResultCode) -> Self {
+        Error::Voicevox(VoicevoxCore::error_result_to_message(code))
+    }
+}
+
+pub struct Core {
+    vvcore: VoicevoxCore,
+}
+
+impl Core {
+    /// Initialize VOICEVOX core from the models and Open JTalk dictionary
+    /// under `path`. Only one instance may exist per process.
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        let models_path = path.join("model");
+        tracing::debug!(?models_path, "check");
+        if !models_path.try_exists()? {
+            return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
+        }
+        std::env::set_var("VV_MODELS_ROOT_DIR", &models_path);
+
+        let open_jtalk_dic_path = path.join("open_jtalk_dic_utf_8-1.11");
+        tracing::debug!(?open_jtalk_dic_path, "check");
+        if !open_jtalk_dic_path.try_exists()? {
+            return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
+        }
+        let open_jtalk_dic_str = open_jtalk_dic_path
+            .into_os_string()
+            .into_string()
+            .map_err(|_| Error::Voicevox("Open JTalk dictionary path is not valid UTF-8"))?;
+        let open_jtalk_dic_str = CString::new(open_jtalk_dic_str)
+            .map_err(|_| Error::Voicevox("Open JTalk dictionary path contains NUL"))?;
+
+        let version = VoicevoxCore::get_version();
+        let supported_devices_json = VoicevoxCore::get_supported_devices_json();
+        tracing::debug!(version, supported_devices_json);
+
+        let vvcore = VoicevoxCore::new_from_options(
+            AccelerationMode::CPU,
+            0,
+            false,
+            open_jtalk_dic_str.as_c_str(),
+        )?;
+
+        Ok(Self { vvcore })
+    }
+}
+
+impl Core {
+    /// Models are loaded on first use, since each holds onto memory.
+    fn load(&self, style: u32) -> Result<(), Error> {
+        if !self.vvcore.is_model_loaded(style) {
+            tracing::debug!(speaker_id = style, "load_model");
+            self.vvcore.load_model(style)?;
+        }
+        Ok(())
+    }
+
+    fn query(&self, text: &str, style: u32, kana: bool) -> Result<AudioQuery, Error> {
+        check_text(text)?;
+        self.load(style)?;
+        let json = self
+            .vvcore
+            .audio_query(text, style, AudioQueryOptions { kana })?;
+        Ok(serde_json::from_str(json.as_str())?)
+    }
+}
+
+/// Core passes text on as a C string and panics on an interior NUL, which
+/// would take the TTS thread down with it.
+fn check_text(text: &str) -> Result<(), Error> {
+    if text.contains('\0') {
+        return Err(Error::Voicevox("text contains NUL"));
+    }
+    Ok(())
+}
+
+impl TtsBackend for Core {
+    fn speakers(&mut self) -> Result<ModelData, Error> {
+        Ok(serde_json::from_str(VoicevoxCore::get_metas_json())?)
+    }
+
+    fn audio_query(&mut self, text: &str, style: u32) -> Result<AudioQuery, Error> {
+        self.query(text, style, false)
+    }
+
+    /// Core has no call for this, so the phrases are written out as kana
+    /// and read again.
+    fn mora_data(
+        &mut self,
+        accent_phrases: &[AccentPhrase],
+        style: u32,
+    ) -> Result<Vec<AccentPhrase>, Error> {
+        let kana = protocol::kana(accent_phrases);
+        Ok(self.query(&kana, style, true)?.accent_phrases)
+    }
+
+    fn synthesis(&mut self, query: &AudioQuery, style: u32) -> Result<Vec<u8>, Error> {
+        self.load(style)?;
+        // JSON escapes any NUL in the query's strings.
+        let json = serde_json::to_string(query)?;
+        let options = SynthesisOptions {
+            enable_interrogative_upspeak: true,
+        };
+        let wav = self.vvcore.synthesis(&json, style, options)?;
+        Ok(wav.as_slice().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_with_nul_is_rejected() {
+        assert!(check_text("こんにちは").is_ok());
+        assert!(matches!(
+            check_text("こんに\0ちは"),
+            Err(Error::Voicevox(_))
+        ));
+    }
+}
@@ -0,0 +1,112 @@
+use std::{path::PathBuf, sync::Arc};
+
+use futures::FutureExt;
+use imgui::*;
+use tokio::task::JoinHandle;
+
+use crate::accent::{self, AccentStore, Import};
+
+use super::mixins::{accent_text, ellipses, help_marker};
+
+/// Imports pitch accent dictionaries into the local store.
+#[derive(Debug, Default)]
+pub struct AccentView {
+    pub open: bool,
+    source: String,
+    pending: Option<JoinHandle<Result<Import, accent::Error>>>,
+    imported: Option<Result<Import, String>>,
+    lookup: [String; 2],
+}
+
+impl AccentView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show_menu_item(&mut self, ui: &Ui) {
+        if ui.menu_item("Pitch accent") {
+            self.open = true;
+        }
+    }
+
+    fn poll(&mut self) {
+        let Some(handle) = self.pending.as_mut() else {
+            return;
+        };
+        if let Some(poll) = handle.now_or_never() {
+            self.pending = None;
+            self.imported = Some(match poll {
+                Ok(result) => result.map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            });
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, accents: &Arc<AccentStore>) {
+        if !self.open {
+            return;
+        }
+        self.poll();
+        let Some(_window) = ui
+            .window("Pitch accent")
+            .size([400.0, 200.0], Condition::FirstUseEver)
+            .opened(&mut self.open)
+            .begin()
+        else {
+            return;
+        };
+
+        ui.text(format!("{} entries", accents.len()));
+        ui.input_text("Dictionary", &mut self.source).build();
+        ui.same_line();
+        help_marker(
+            ui,
+            "Tab-separated text, reading and accent per line, \
+             as in Kanjium's accents.txt",
+        );
+        ui.disabled(self.pending.is_some() || self.source.is_empty(), || {
+            if ui.button("Import") {
+                let accents = accents.clone();
+                let source = PathBuf::from(&self.source);
+                self.imported = None;
+                self.pending = Some(tokio::task::spawn_blocking(move || accents.import(&source)));
+            }
+        });
+        if self.pending.is_some() {
+            ui.same_line();
+            ui.text_disabled(format!("Importing{}", ellipses(ui)));
+        }
+        match &self.imported {
+            Some(Ok(import)) => {
+                ui.same_line();
+                ui.text(format!(
+                    "Imported {} entries, skipped {} lines",
+                    import.entries, import.skipped
+                ));
+            }
+            Some(Err(err)) => ui.text_colored([1.0, 0.4, 0.4, 1.0], err),
+            None => {}
+        }
+
+        ui.separator();
+        let [text, reading] = &mut self.lookup;
+        ui.input_text("Text", text).build();
+        ui.input_text("Reading", reading).build();
+        let reading = if reading.is_empty() {
+            &*text
+        } else {
+            &*reading
+        };
+        match accents.get(text, reading) {
+            Some(found) => {
+                for &accent in found.iter() {
+                    accent_text(ui, reading, accent);
+                    ui.same_line();
+                    ui.text_disabled(format!("[{}]", accent));
+                }
+            }
+            None if !text.is_empty() => ui.text_disabled("Not found"),
+            None => {}
+        }
+    }
+}
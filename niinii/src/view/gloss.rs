@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use futures::FutureExt;
@@ -8,13 +9,20 @@ use imgui::*;
 use tokio::task::JoinHandle;
 use tracing::Instrument;
 
+use super::accent::AccentView;
 use super::index::IndexView;
 use super::mixins::*;
+use super::search::SearchView;
+use crate::accent::AccentStore;
 use crate::parser::{self, Parser, SyntaxTree};
 use crate::renderer::context::{Context, ContextFlags};
 use crate::settings::{RubyTextType, Settings};
 use crate::support::regex::CachedRegex;
-use crate::view::{raw::RawView, term::TermView};
+use crate::view::{
+    raw::RawView,
+    term::{word_info, EntryLoader, TermView},
+};
+use crate::vocab::{VocabLists, WordColoring};
 
 const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_millis(33);
 
@@ -33,6 +41,8 @@ enum View {
 pub enum GlossEvent {
     ClipboardReceived(String),
     Failed(parser::Error),
+    /// A speak button was clicked on this reading.
+    Speak(String),
 }
 
 /// Returned from `show_input` so the caller can dispatch. The input row owns
@@ -59,12 +69,19 @@ pub struct GlossView {
     selected_clause: RefCell<HashMap<Segment, i32>>,
     show_raw: bool,
     show_glossary: bool,
+
+    accents: Arc<AccentStore>,
+    accent_view: AccentView,
+    vocab: VocabLists,
+    search_view: SearchView,
+    entries: EntryLoader,
 }
 
 impl GlossView {
     pub async fn new(settings: &Settings) -> Self {
+        let parser = Parser::new(settings).await;
         Self {
-            parser: Parser::new(settings).await,
+            parser: parser.clone(),
             pending_ast: None,
             pending_kanji: None,
             match_regex: CachedRegex::default(),
@@ -77,6 +94,11 @@ impl GlossView {
             selected_clause: RefCell::new(HashMap::new()),
             show_raw: false,
             show_glossary: false,
+            accents: Arc::new(AccentStore::open(&settings.accent)),
+            accent_view: AccentView::new(),
+            vocab: VocabLists::open(&settings.vocab),
+            search_view: SearchView::new(parser.clone()),
+            entries: EntryLoader::new(parser),
         }
     }
 
@@ -97,12 +119,9 @@ impl GlossView {
         &self.input_text
     }
 
-    /// Preprocess `text` through the configured match/replace regex and spawn
-    /// a parse. Aborts any prior in-flight parse. The preview text is shown
-    /// immediately; `poll` will transition to the parsed AST on completion.
-    /// Returns the post-regex text on success, or `None` if the regex yielded
-    /// empty text and nothing was spawned.
-    pub fn request(
+    /// Apply the configured match/replace regex to `text` and normalize it
+    /// for parsing. Returns `None` if nothing is left.
+    pub fn preprocess(
         &mut self,
         text: &str,
         settings: &Settings,
@@ -118,9 +137,22 @@ impl GlossView {
             .replace(|c: char| c == '\n' || c == '\r', "")
             .trim()
             .to_owned();
-        if text.is_empty() {
+        Ok(Some(text).filter(|x| !x.is_empty()))
+    }
+
+    /// Preprocess `text` through the configured match/replace regex and spawn
+    /// a parse. Aborts any prior in-flight parse. The preview text is shown
+    /// immediately; `poll` will transition to the parsed AST on completion.
+    /// Returns the post-regex text on success, or `None` if the regex yielded
+    /// empty text and nothing was spawned.
+    pub fn request(
+        &mut self,
+        text: &str,
+        settings: &Settings,
+    ) -> Result<Option<String>, parser::Error> {
+        let Some(text) = self.preprocess(text, settings)? else {
             return Ok(None);
-        }
+        };
 
         if let Some(prev) = self.pending_ast.take() {
             prev.abort();
@@ -245,6 +277,7 @@ impl GlossView {
         ui: &Ui,
         settings: &Settings,
         romanized: &Romanized,
+        spoken: &mut Option<String>,
     ) -> bool {
         let mut opened = true;
         ui.window(&romanized.term().text().to_string())
@@ -254,8 +287,19 @@ impl GlossView {
             .opened(&mut opened)
             .build(|| {
                 if let Some(View::Interpret { ast: gloss, .. }) = &self.view {
-                    TermView::new(&gloss.jmdict_data, &gloss.kanji_info, romanized, 0.0)
-                        .ui(ctx, ui, settings);
+                    let kana = TermView::new(
+                        &gloss.jmdict_data,
+                        &gloss.kanji_info,
+                        &self.accents,
+                        &self.vocab,
+                        &self.entries,
+                        romanized,
+                        0.0,
+                    )
+                    .ui(ctx, ui, settings);
+                    if kana.is_some() {
+                        *spoken = kana;
+                    }
                 }
             });
         opened
@@ -264,8 +308,18 @@ impl GlossView {
     fn term_tooltip(&self, ctx: &mut Context, ui: &Ui, settings: &Settings, romanized: &Romanized) {
         ui.tooltip(|| {
             if let Some(View::Interpret { ast: gloss, .. }) = &self.view {
-                TermView::new(&gloss.jmdict_data, &gloss.kanji_info, romanized, 30.0)
-                    .ui(ctx, ui, settings)
+                // Tooltips cannot be clicked, so nothing is spoken from here.
+                TermView::new(
+                    &gloss.jmdict_data,
+                    &gloss.kanji_info,
+                    &self.accents,
+                    &self.vocab,
+                    &self.entries,
+                    romanized,
+                    30.0,
+                )
+                .without_full_entry()
+                .ui(ctx, ui, settings);
             }
         });
     }
@@ -289,6 +343,7 @@ impl GlossView {
             },
             KanjiStyle {
                 highlight: false,
+                tint: None,
                 stroke: !preview && settings.stroke_text,
                 preview,
                 underline: UnderlineMode::None,
@@ -311,6 +366,7 @@ impl GlossView {
             },
             KanjiStyle {
                 highlight: true,
+                tint: None,
                 stroke: false,
                 preview: true,
                 underline: UnderlineMode::None,
@@ -318,6 +374,24 @@ impl GlossView {
         );
     }
 
+    /// Highlight colour of `term` under the configured colouring.
+    fn word_tint(&self, settings: &Settings, term: &Term) -> Option<[f32; 4]> {
+        let color = match settings.vocab.coloring {
+            WordColoring::None => return None,
+            WordColoring::Jlpt => jlpt_color(word_info(&self.vocab, term.best()).jlpt?),
+            WordColoring::Frequency => rank_color(word_info(&self.vocab, term.best()).rank?),
+        };
+        let [r, g, b, _] = color;
+        Some([r, g, b, 0.45])
+    }
+
+    fn ruby_accent(&self, settings: &Settings, term: &Term) -> Option<u8> {
+        if !settings.accent.ruby {
+            return None;
+        }
+        self.accents.first(term.text(), term.kana())
+    }
+
     fn add_romanized(
         &self,
         ctx: &mut Context,
@@ -331,7 +405,12 @@ impl GlossView {
 
         let fg_text = match ruby_text {
             RubyTextType::None => RubyTextMode::None,
-            RubyTextType::Furigana if term.text() != term.kana() => RubyTextMode::Text(term.kana()),
+            RubyTextType::Furigana if term.text() != term.kana() => {
+                match self.ruby_accent(settings, term) {
+                    Some(accent) => RubyTextMode::Accent(term.kana(), accent),
+                    None => RubyTextMode::Text(term.kana()),
+                }
+            }
             RubyTextType::Romaji => RubyTextMode::Text(romanized.romaji()),
             _ => RubyTextMode::Pad,
         };
@@ -342,6 +421,7 @@ impl GlossView {
             fg_text,
             KanjiStyle {
                 highlight: true,
+                tint: self.word_tint(settings, term),
                 stroke: settings.stroke_text,
                 preview: false,
                 underline,
@@ -435,6 +515,7 @@ impl GlossView {
 
     pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &Settings) {
         ui.text(""); // anchor for line wrapping
+        let mut spoken = None;
         match &self.view {
             Some(View::Interpret { ast }) => {
                 self.add_root(ctx, ui, settings, &ast.root);
@@ -452,7 +533,9 @@ impl GlossView {
                         .size([300., 110.], Condition::FirstUseEver)
                         .opened(&mut self.show_glossary)
                         .build(|| {
-                            IndexView::new(ast).ui(ctx, ui, settings);
+                            let mut index =
+                                IndexView::new(ast, &self.accents, &self.vocab, &self.entries);
+                            spoken = index.ui(ctx, ui, settings);
                         });
                 }
             }
@@ -469,9 +552,15 @@ impl GlossView {
         ui.new_line();
 
         // show all term windows, close if requested (this is actually witchcraft)
+        self.accent_view.ui(ui, &self.accents);
+        if let Some(kana) = self.search_view.ui(ctx, ui, &self.accents, &self.vocab) {
+            spoken = Some(kana);
+        }
+
         self.show_term_window
             .borrow_mut()
-            .retain(|romanized| self.term_window(ctx, ui, settings, romanized));
+            .retain(|romanized| self.term_window(ctx, ui, settings, romanized, &mut spoken));
+        self.events.extend(spoken.map(GlossEvent::Speak));
     }
 
     pub fn show_menu(&mut self, _ctx: &mut Context, ui: &Ui) {
@@ -485,5 +574,7 @@ impl GlossView {
         {
             self.show_glossary = true;
         }
+        self.accent_view.show_menu_item(ui);
+        self.search_view.show_menu_item(ui);
     }
 }
@@ -3,24 +3,42 @@ use std::collections::HashSet;
 use ichiran::prelude::*;
 use imgui::*;
 
-use crate::{parser::SyntaxTree, renderer::context::Context, settings::Settings};
+use crate::{
+    accent::AccentStore, parser::SyntaxTree, renderer::context::Context, settings::Settings,
+    vocab::VocabLists,
+};
 
-use super::term::TermView;
+use super::term::{EntryLoader, TermView};
 
 pub struct IndexView<'a> {
     ast: &'a SyntaxTree,
+    accents: &'a AccentStore,
+    vocab: &'a VocabLists,
+    entries: &'a EntryLoader,
     seen_terms: HashSet<String>,
+    spoken: Option<String>,
 }
 impl<'a> IndexView<'a> {
-    pub fn new(ast: &'a SyntaxTree) -> Self {
+    pub fn new(
+        ast: &'a SyntaxTree,
+        accents: &'a AccentStore,
+        vocab: &'a VocabLists,
+        entries: &'a EntryLoader,
+    ) -> Self {
         Self {
             ast,
+            accents,
+            vocab,
+            entries,
             seen_terms: HashSet::new(),
+            spoken: None,
         }
     }
-    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &Settings) {
+    /// Returns the reading to speak, if one was asked for.
+    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &Settings) -> Option<String> {
         let _wrap_token = ui.push_text_wrap_pos();
         self.add_root(ctx, ui, settings, &self.ast.root);
+        self.spoken.take()
     }
     fn add_root(&mut self, ctx: &mut Context, ui: &Ui, settings: &Settings, root: &Root) {
         for segment in root.segments() {
@@ -53,8 +71,17 @@ impl<'a> IndexView<'a> {
                 continue;
             }
             self.seen_terms.insert(romanized.term().text().to_string());
-            TermView::new(&self.ast.jmdict_data, &self.ast.kanji_info, romanized, 0.0)
-                .ui(ctx, ui, settings);
+            let spoken = TermView::new(
+                &self.ast.jmdict_data,
+                &self.ast.kanji_info,
+                self.accents,
+                self.vocab,
+                self.entries,
+                romanized,
+                0.0,
+            )
+            .ui(ctx, ui, settings);
+            self.spoken = spoken.or(self.spoken.take());
             ui.separator()
         }
     }
@@ -3,13 +3,21 @@ use imgui::*;
 
 use crate::renderer::context::{Context, TextStyle};
 
+use super::mixins::speak_button;
+
 pub struct KanjiView<'a> {
     kanji: &'a Kanji,
     wrap_w: f32,
+    /// Reading whose speak button was clicked.
+    spoken: Option<String>,
 }
 impl<'a> KanjiView<'a> {
     pub fn new(kanji: &'a Kanji, wrap_w: f32) -> Self {
-        KanjiView { kanji, wrap_w }
+        KanjiView {
+            kanji,
+            wrap_w,
+            spoken: None,
+        }
     }
 
     fn add_kanji(&mut self, ctx: &mut Context, ui: &Ui, kanji: &Kanji) {
@@ -51,6 +59,10 @@ impl<'a> KanjiView<'a> {
                 ui.text(format!("{}", reading.rtype()));
 
                 ui.table_next_column();
+                if speak_button(ui, reading.kana()) {
+                    self.spoken = Some(reading.kana().to_owned());
+                }
+                ui.same_line();
                 let mut kana = reading.kana().to_owned();
                 if reading.prefix() {
                     kana.push('-')
@@ -87,8 +99,10 @@ impl<'a> KanjiView<'a> {
         }
     }
 
-    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui) {
+    /// Returns the reading to speak, if one was asked for.
+    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui) -> Option<String> {
         let _wrap_token = ui.push_text_wrap_pos_with_pos(ui.current_font_size() * self.wrap_w);
         self.add_kanji(ctx, ui, self.kanji);
+        self.spoken.take()
     }
 }
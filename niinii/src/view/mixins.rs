@@ -3,7 +3,9 @@ use std::f32::consts::PI;
 use imgui::{DrawListMut, MouseCursor, StyleColor, Ui};
 use strum::IntoEnumIterator;
 
+use crate::accent;
 use crate::renderer::context::{Context, TextStyle};
+use crate::vocab::Jlpt;
 
 pub fn help_marker(ui: &Ui, text: &str) {
     ui.text_colored(ui.style_color(StyleColor::TextDisabled), "[?]");
@@ -25,6 +27,8 @@ pub enum UnderlineMode {
 
 pub enum RubyTextMode<'a> {
     Text(&'a str),
+    /// Kana with the pitch pattern of an accent marked over it.
+    Accent(&'a str, u8),
     Pad,
     None,
 }
@@ -82,9 +86,14 @@ pub fn stroke_text_with_highlight(
 ) {
     ui.new_line();
     let tokens = text.split_inclusive(char::is_whitespace);
-    for token in tokens {
+    let mut line_break = false;
+    for raw in tokens {
+        // Hard line breaks end the line rather than being drawn.
+        let token = raw.trim_end_matches(['\r', '\n']);
         let sz = ui.calc_text_size(token);
-        wrap_line_with_spacing(ui, sz[0], 0.0);
+        if !line_break {
+            wrap_line_with_spacing(ui, sz[0], 0.0);
+        }
         let p = ui.cursor_screen_pos();
         if let Some(highlight) = highlight {
             draw_list
@@ -103,7 +112,8 @@ pub fn stroke_text_with_highlight(
                 back: StyleColor::TitleBg,
             },
         );
-        ui.dummy(sz)
+        ui.dummy(sz);
+        line_break = token.len() < raw.len();
     }
 }
 
@@ -113,6 +123,8 @@ pub fn stroke_text(ui: &Ui, draw_list: &DrawListMut, text: &str, thick: f32) {
 
 pub struct KanjiStyle {
     pub highlight: bool,
+    /// Highlight colour in place of the style's.
+    pub tint: Option<[f32; 4]>,
     pub stroke: bool,
     pub preview: bool,
     pub underline: UnderlineMode,
@@ -126,12 +138,13 @@ pub fn draw_kanji_text(
 ) -> bool {
     let KanjiStyle {
         highlight,
+        tint,
         stroke,
         preview,
         underline,
     } = style;
     let ruby_sz = match ruby_text {
-        RubyTextMode::Text(text) => ui.calc_text_size(text),
+        RubyTextMode::Text(text) | RubyTextMode::Accent(text, _) => ui.calc_text_size(text),
         RubyTextMode::Pad => [0.0, ui.text_line_height()],
         RubyTextMode::None => [0.0, 0.0],
     };
@@ -172,9 +185,12 @@ pub fn draw_kanji_text(
         }
     };
 
-    if let RubyTextMode::Text(text) = ruby_text {
+    if let RubyTextMode::Text(text) | RubyTextMode::Accent(text, _) = ruby_text {
         let cx = x + w / 2.0 - ruby_sz[0] / 2.0;
         maybe_stroke_text(text, [cx, y], 1.0);
+        if let RubyTextMode::Accent(_, accent) = ruby_text {
+            draw_downstep(ui, &draw_list, text, accent, [cx, y]);
+        }
     }
 
     let cx = x + w / 2.0 - kanji_sz[0] / 2.0;
@@ -185,7 +201,7 @@ pub fn draw_kanji_text(
             .add_rect(
                 [cx, y],
                 [cx + kanji_sz[0], y + kanji_sz[1]],
-                ui.style_color(StyleColor::TextSelectedBg),
+                tint.unwrap_or(ui.style_color(StyleColor::TextSelectedBg)),
             )
             .rounding(5.0)
             .filled(true)
@@ -233,6 +249,64 @@ pub fn draw_kanji_text(
         )
 }
 
+/// Line over the high morae of `kana` drawn at `pos`, with a tick down
+/// where the pitch drops.
+pub fn draw_downstep(ui: &Ui, draw_list: &DrawListMut, kana: &str, accent: u8, pos: [f32; 2]) {
+    let color = ui.style_color(StyleColor::PlotLinesHovered);
+    let thick = 1.5;
+    let tick = ui.text_line_height() * 0.4;
+    let [mut x, y] = pos;
+    for (i, mora) in accent::morae(kana).into_iter().enumerate() {
+        let w = ui.calc_text_size(mora)[0];
+        if accent::is_high(accent as usize, i) {
+            draw_list
+                .add_line([x, y], [x + w, y], color)
+                .thickness(thick)
+                .build();
+            if accent as usize == i + 1 {
+                draw_list
+                    .add_line([x + w, y], [x + w, y + tick], color)
+                    .thickness(thick)
+                    .build();
+            }
+        }
+        x += w;
+    }
+}
+
+/// Kana with its pitch pattern marked over it.
+pub fn accent_text(ui: &Ui, kana: &str, accent: u8) {
+    ui.text(kana);
+    draw_downstep(
+        ui,
+        &ui.get_window_draw_list(),
+        kana,
+        accent,
+        ui.item_rect_min(),
+    );
+}
+
+/// From easy to hard.
+const DIFFICULTY: [[f32; 4]; 5] = [
+    [0.40, 0.80, 0.40, 1.0],
+    [0.60, 0.85, 0.30, 1.0],
+    [0.90, 0.80, 0.30, 1.0],
+    [0.95, 0.55, 0.25, 1.0],
+    [0.90, 0.35, 0.35, 1.0],
+];
+
+pub fn jlpt_color(level: Jlpt) -> [f32; 4] {
+    DIFFICULTY[level as usize]
+}
+
+pub fn rank_color(rank: u32) -> [f32; 4] {
+    let band = [1000, 3000, 8000, 20000]
+        .iter()
+        .filter(|&&bound| rank > bound)
+        .count();
+    DIFFICULTY[band]
+}
+
 pub fn wrap_line_with_spacing(ui: &Ui, expected_width: f32, spacing: f32) -> bool {
     let max_width = ui.window_content_region_max()[0];
     let visible_x = ui.window_pos()[0] + max_width;
@@ -374,3 +448,16 @@ pub fn drag_handle(ui: &Ui) {
         ui.set_mouse_cursor(Some(MouseCursor::Hand));
     }
 }
+
+/// Button that asks for `kana` to be spoken. Hidden without TTS support.
+pub fn speak_button(ui: &Ui, kana: &str) -> bool {
+    if !cfg!(feature = "tts") {
+        return false;
+    }
+    let _id = ui.push_id(kana);
+    let clicked = ui.small_button("Speak");
+    if ui.is_item_hovered() {
+        ui.tooltip_text(format!("Listen to {}", kana));
+    }
+    clicked
+}
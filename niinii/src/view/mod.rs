@@ -1,10 +1,15 @@
+pub mod accent;
 pub mod gloss;
 pub mod index;
 pub mod inject;
 pub mod kanji;
 pub mod mixins;
+pub mod pronunciation;
 pub mod raw;
+pub mod script;
+pub mod search;
 pub mod settings;
+pub mod speech;
 pub mod style_editor;
 pub mod term;
 pub mod translator;
@@ -0,0 +1,227 @@
+use imgui::*;
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use crate::{
+    accent::is_high,
+    settings::Settings,
+    tts::{
+        protocol::{self, AccentPhrase, AudioQuery},
+        Error, TtsEngine, Utterance,
+    },
+};
+
+use super::mixins::{ellipses, help_marker};
+
+type Pending<T> = Option<oneshot::Receiver<Result<T, Error>>>;
+
+/// A line's reading as VOICEVOX sees it, with accents and pitch that can be
+/// edited and listened to.
+#[derive(Debug, Default)]
+pub struct PronunciationView {
+    pub open: bool,
+    text: String,
+    /// Style the query was made with.
+    style: u32,
+    query: Option<AudioQuery>,
+    /// Reading requested of the backend.
+    pending_query: Pending<AudioQuery>,
+    /// Pitch and lengths requested after the accents were edited.
+    pending_moras: Pending<Vec<AccentPhrase>>,
+    error: Option<String>,
+}
+
+impl PronunciationView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the window on the reading of `text`.
+    pub fn load(&mut self, settings: &Settings, tts: &TtsEngine, text: &str) {
+        self.open = true;
+        if !text.is_empty() {
+            self.text = text.to_string();
+            self.read(settings, tts);
+        }
+    }
+
+    fn read(&mut self, settings: &Settings, tts: &TtsEngine) {
+        self.style = settings.tts.style_for(&self.text);
+        let rx = tts.audio_query(self.text.clone(), self.style);
+        self.pending_query = self.check(rx);
+        self.pending_moras = None;
+    }
+
+    /// Take in whatever the backend has answered since the last frame.
+    fn poll(&mut self, settings: &Settings) {
+        if let Some(query) = poll(&mut self.pending_query) {
+            self.query = self.check(query).map(|mut query| {
+                settings.tts.prosody.apply(&mut query);
+                query
+            });
+        }
+        if let Some(moras) = poll(&mut self.pending_moras) {
+            if let (Some(accent_phrases), Some(query)) = (self.check(moras), &mut self.query) {
+                query.accent_phrases = accent_phrases;
+            }
+        }
+    }
+
+    fn check<T>(&mut self, result: Result<T, Error>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.error = None;
+                Some(value)
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                None
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, settings: &Settings, tts: &TtsEngine) {
+        if !self.open {
+            return;
+        }
+        self.poll(settings);
+        let Some(_window) = ui
+            .window("Pronunciation")
+            .size([400.0, 300.0], Condition::FirstUseEver)
+            .opened(&mut self.open)
+            .begin()
+        else {
+            return;
+        };
+
+        ui.set_next_item_width(ui.current_font_size() * -4.0);
+        let entered = ui
+            .input_text("##text", &mut self.text)
+            .enter_returns_true(true)
+            .build();
+        ui.same_line();
+        if ui.button("Read") || entered {
+            self.read(settings, tts);
+        }
+        if let Some(error) = &self.error {
+            ui.text_colored([1.0, 0.4, 0.4, 1.0], error);
+        }
+        if self.pending_query.is_some() {
+            ui.text_disabled(format!("Reading{}", ellipses(ui)));
+            return;
+        }
+        let Some(mut query) = self.query.take() else {
+            return;
+        };
+
+        if ui.button("Speak") {
+            let utterance = Utterance::Query {
+                query: query.clone(),
+                style: self.style,
+            };
+            let result = tts.request_tts(utterance, settings.tts.playback);
+            self.check(result);
+        }
+        ui.same_line();
+        ui.text_disabled(protocol::kana(&query.accent_phrases));
+        ui.same_line();
+        help_marker(
+            ui,
+            "Click a mora to move the accent there; pitch drops after it. \
+             Highlighted moras are high.",
+        );
+
+        ui.separator();
+        let mut edited = false;
+        for (idx, phrase) in query.accent_phrases.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(idx);
+            for (i, mora) in phrase.moras.iter().enumerate() {
+                if i > 0 {
+                    ui.same_line_with_spacing(0.0, 0.0);
+                }
+                let high = is_high(phrase.accent, i);
+                let _color = high.then(|| {
+                    ui.push_style_color(
+                        StyleColor::Button,
+                        ui.style_color(StyleColor::ButtonActive),
+                    )
+                });
+                let _id = ui.push_id_usize(i);
+                if ui.button(&mora.text) && phrase.accent != i + 1 {
+                    phrase.accent = i + 1;
+                    edited = true;
+                }
+            }
+            ui.same_line();
+            edited |= ui.checkbox("?", &mut phrase.is_interrogative);
+        }
+        if edited {
+            let rx = tts.mora_data(query.accent_phrases.clone(), self.style);
+            self.pending_moras = self.check(rx);
+        }
+
+        if CollapsingHeader::new("Pitch and length").build(ui) {
+            draw_moras(ui, &mut query);
+        }
+        if CollapsingHeader::new("Delivery").build(ui) {
+            for (label, value, min, max) in [
+                ("Speed", &mut query.speed_scale, 0.5, 2.0),
+                ("Pitch", &mut query.pitch_scale, -0.15, 0.15),
+                ("Intonation", &mut query.intonation_scale, 0.0, 2.0),
+                ("Volume", &mut query.volume_scale, 0.0, 2.0),
+            ] {
+                ui.slider_config(label, min, max)
+                    .display_format("%.2f")
+                    .flags(SliderFlags::ALWAYS_CLAMP)
+                    .build(value);
+            }
+        }
+        self.query = Some(query);
+    }
+}
+
+/// Result of `pending` once the backend has answered.
+fn poll<T>(pending: &mut Pending<T>) -> Option<Result<T, Error>> {
+    let rx = pending.as_mut()?;
+    let result = match rx.try_recv() {
+        Ok(result) => result,
+        Err(TryRecvError::Empty) => return None,
+        Err(TryRecvError::Closed) => Err(Error::Channel),
+    };
+    *pending = None;
+    Some(result)
+}
+
+fn draw_moras(ui: &Ui, query: &mut AudioQuery) {
+    let Some(_t) = ui.begin_table_header_with_flags(
+        "moras",
+        [
+            TableColumnSetup::new("Mora"),
+            TableColumnSetup::new("Pitch"),
+            TableColumnSetup::new("Length"),
+        ],
+        TableFlags::SIZING_STRETCH_PROP,
+    ) else {
+        return;
+    };
+    let moras = query
+        .accent_phrases
+        .iter_mut()
+        .flat_map(|phrase| phrase.moras.iter_mut());
+    for (idx, mora) in moras.enumerate() {
+        let _id = ui.push_id_usize(idx);
+        ui.table_next_column();
+        ui.text(&mora.text);
+        ui.table_next_column();
+        ui.set_next_item_width(ui.content_region_avail()[0]);
+        ui.disabled(mora.is_devoiced(), || {
+            ui.slider_config("##pitch", 3.0, 6.5)
+                .display_format("%.2f")
+                .build(&mut mora.pitch);
+        });
+        ui.table_next_column();
+        ui.set_next_item_width(ui.content_region_avail()[0]);
+        ui.slider_config("##length", 0.0, 0.3)
+            .display_format("%.3f")
+            .build(&mut mora.vowel_length);
+    }
+}
@@ -0,0 +1,142 @@
+use imgui::*;
+
+use crate::settings::Settings;
+
+use super::mixins::help_marker;
+
+/// Steps through a text file one line at a time, glossing each line as if it
+/// had been copied to the clipboard. Knowing what comes next lets the
+/// translator prefetch upcoming lines.
+#[derive(Debug, Default)]
+pub struct ScriptView {
+    lines: Vec<String>,
+    cursor: Option<usize>,
+    pub open: bool,
+}
+
+impl ScriptView {
+    pub fn new(settings: &Settings) -> Self {
+        let mut view = Self::default();
+        view.load(&settings.script_path);
+        view.cursor = settings.script_line.filter(|&line| line < view.lines.len());
+        view
+    }
+
+    fn load(&mut self, path: &str) {
+        self.lines.clear();
+        self.cursor = None;
+        if path.is_empty() {
+            return;
+        }
+        match std::fs::read_to_string(path) {
+            Ok(script) => {
+                self.lines = script
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
+            }
+            Err(err) => tracing::warn!(%err, path, "failed to read script"),
+        }
+    }
+
+    pub fn show_menu_item(&mut self, ui: &Ui) {
+        if ui.menu_item("Script") {
+            self.open = true;
+        }
+    }
+
+    /// Up to `n` lines after the current one, starting with the current line
+    /// itself if `include_current` is set.
+    pub fn upcoming(&self, include_current: bool, n: usize) -> &[String] {
+        let start = match self.cursor {
+            Some(cursor) if include_current => cursor,
+            Some(cursor) => cursor + 1,
+            None => 0,
+        };
+        let start = start.min(self.lines.len());
+        let end = (start + n).min(self.lines.len());
+        &self.lines[start..end]
+    }
+
+    /// Returns the line to show if the reader moved to a new one.
+    pub fn ui(&mut self, ui: &Ui, settings: &mut Settings) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+        let Some(_window) = ui
+            .window("Script")
+            .size([400.0, 300.0], Condition::FirstUseEver)
+            .opened(&mut self.open)
+            .begin()
+        else {
+            return None;
+        };
+
+        if ui
+            .input_text("Path", &mut settings.script_path)
+            .enter_returns_true(true)
+            .build()
+        {
+            self.load(&settings.script_path);
+        }
+        ui.same_line();
+        if ui.button("Reload") {
+            self.load(&settings.script_path);
+        }
+        ui.same_line();
+        help_marker(
+            ui,
+            "A text file with one line of dialogue per line. Blank lines are \
+             skipped.",
+        );
+
+        let mut goto = None;
+        {
+            let _disabled = ui.begin_disabled(self.cursor.is_none_or(|cursor| cursor == 0));
+            if ui.arrow_button("##prev", Direction::Left) {
+                goto = self.cursor.map(|cursor| cursor - 1);
+            }
+        }
+        ui.same_line();
+        {
+            let next = self.cursor.map_or(0, |cursor| cursor + 1);
+            let _disabled = ui.begin_disabled(next >= self.lines.len());
+            if ui.arrow_button("##next", Direction::Right) {
+                goto = Some(next);
+            }
+        }
+        ui.same_line();
+        match self.cursor {
+            Some(cursor) => ui.text(format!("{} / {}", cursor + 1, self.lines.len())),
+            None => ui.text_disabled(format!("- / {}", self.lines.len())),
+        }
+
+        ui.separator();
+        // Keep the line stepped to with the arrows in view.
+        let scroll_to = goto;
+        ui.child_window("lines").build(|| {
+            for (i, line) in self.lines.iter().enumerate() {
+                let _id = ui.push_id_usize(i);
+                if ui
+                    .selectable_config(line)
+                    .selected(self.cursor == Some(i))
+                    .allow_double_click(true)
+                    .build()
+                    && ui.is_mouse_double_clicked(MouseButton::Left)
+                {
+                    goto = Some(i);
+                }
+                if scroll_to == Some(i) {
+                    ui.set_scroll_here_y();
+                }
+            }
+        });
+
+        let cursor = goto?;
+        self.cursor = Some(cursor);
+        settings.script_line = Some(cursor);
+        self.lines.get(cursor).cloned()
+    }
+}
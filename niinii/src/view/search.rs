@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use futures::FutureExt;
+use ichiran::prelude::*;
+use imgui::*;
+use itertools::Itertools;
+use strum_macros::{EnumIter, IntoStaticStr};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+use crate::{
+    accent::AccentStore,
+    parser::{self, Parser},
+    renderer::context::Context,
+    vocab::VocabLists,
+};
+
+use super::{
+    mixins::{combo_enum, ellipses, help_marker, speak_button},
+    term::{draw_accent, draw_entry, draw_kanji_chars, draw_vocab},
+};
+
+/// Most entries listed per query.
+const RESULT_LIMIT: u32 = 50;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, IntoStaticStr, EnumIter)]
+pub enum SearchMode {
+    #[default]
+    Auto,
+    Kanji,
+    Kana,
+    Romaji,
+    English,
+}
+
+impl SearchMode {
+    /// What to look `query` up by. Auto goes by the script of the query;
+    /// Latin text may be romaji or English, so it is looked up as both.
+    /// Romaji is looked up in hiragana and in katakana, for loanwords.
+    fn queries(self, query: &str) -> Vec<(SearchKey, String)> {
+        let query = query.trim();
+        let romaji = || {
+            romaji_to_kana(query)
+                .into_iter()
+                .chain(romaji_to_katakana(query))
+                .map(|kana| (SearchKey::Kana, kana))
+        };
+        match self {
+            SearchMode::Auto if query.chars().any(|c| is_kanji(&c)) => {
+                vec![(SearchKey::Kanji, query.into())]
+            }
+            SearchMode::Auto if query.chars().all(|c| is_hiragana(&c) || is_katakana(&c)) => {
+                vec![(SearchKey::Kana, query.into())]
+            }
+            SearchMode::Auto => romaji()
+                .into_iter()
+                .chain([(SearchKey::Gloss, query.into())])
+                .collect(),
+            SearchMode::Kanji => vec![(SearchKey::Kanji, query.into())],
+            SearchMode::Kana => vec![(SearchKey::Kana, query.into())],
+            SearchMode::Romaji => romaji().into_iter().collect(),
+            SearchMode::English => vec![(SearchKey::Gloss, query.into())],
+        }
+    }
+}
+
+/// Katakana for `romaji`, with long vowels written ー as in loanwords, so
+/// `raamen` or `rāmen` gives ラーメン.
+fn romaji_to_katakana(romaji: &str) -> Option<String> {
+    let mut long = String::new();
+    for c in romaji.trim().to_lowercase().chars() {
+        let vowel = match c {
+            'ā' | 'â' => 'a',
+            'ī' | 'î' => 'i',
+            'ū' | 'û' => 'u',
+            'ē' | 'ê' => 'e',
+            'ō' | 'ô' => 'o',
+            c if "aiueo".contains(c) && long.ends_with(c) => '-',
+            c => {
+                long.push(c);
+                continue;
+            }
+        };
+        if vowel != '-' {
+            long.push(vowel);
+        }
+        long.push('-');
+    }
+    let hiragana = romaji_to_kana(&long)?;
+    Some(
+        hiragana
+            .chars()
+            .map(|c| {
+                if is_hiragana(&c) {
+                    char::from_u32(c as u32 + 0x60).unwrap_or(c)
+                } else {
+                    c
+                }
+            })
+            .collect(),
+    )
+}
+
+/// The selected entry and what it takes to draw it.
+struct Selected {
+    entry: Entry,
+    jmdict_data: JmDictData,
+    kanji_info: HashMap<char, Kanji>,
+}
+
+/// Searches JMdict by spelling, reading or meaning, independently of the
+/// text being glossed.
+pub struct SearchView {
+    pub open: bool,
+    parser: Parser,
+    query: String,
+    mode: SearchMode,
+    pending: Option<JoinHandle<Result<Vec<SearchResult>, parser::Error>>>,
+    results: Option<Result<Vec<SearchResult>, String>>,
+    /// Sequence number of the entry shown below the results.
+    selected: Option<u32>,
+    pending_entry: Option<JoinHandle<Result<Option<Selected>, parser::Error>>>,
+    entry: Option<Result<Option<Selected>, String>>,
+}
+
+impl SearchView {
+    pub fn new(parser: Parser) -> Self {
+        Self {
+            open: false,
+            parser,
+            query: String::new(),
+            mode: SearchMode::default(),
+            pending: None,
+            results: None,
+            selected: None,
+            pending_entry: None,
+            entry: None,
+        }
+    }
+
+    pub fn show_menu_item(&mut self, ui: &Ui) {
+        if ui.menu_item("Dictionary") {
+            self.open = true;
+        }
+    }
+
+    fn search(&mut self) {
+        let queries = self.mode.queries(&self.query);
+        if let Some(prev) = self.pending.take() {
+            prev.abort();
+        }
+        if queries.is_empty() {
+            self.results = Some(Err(format!("\"{}\" is not romaji", self.query.trim())));
+            return;
+        }
+        let parser = self.parser.clone();
+        self.pending = Some(tokio::spawn(
+            async move { parser.search(&queries, RESULT_LIMIT).await }
+                .instrument(tracing::debug_span!("search")),
+        ));
+    }
+
+    /// Fetch the entry of `result`, along with the kanji of its spellings.
+    fn select(&mut self, result: &SearchResult) {
+        if let Some(prev) = self.pending_entry.take() {
+            prev.abort();
+        }
+        let seq = result.seq();
+        self.selected = Some(seq);
+        self.entry = None;
+        let parser = self.parser.clone();
+        let kanji = result.kanji().concat();
+        self.pending_entry = Some(tokio::spawn(
+            async move {
+                let (entry, jmdict_data, kanji_info) = tokio::try_join!(
+                    parser.entry(seq),
+                    parser.jmdict_data(),
+                    parser.parse_kanji(&kanji),
+                )?;
+                Ok(entry.map(|entry| Selected {
+                    entry,
+                    jmdict_data,
+                    kanji_info,
+                }))
+            }
+            .instrument(tracing::debug_span!("search_entry")),
+        ));
+    }
+
+    fn poll(&mut self) {
+        if let Some(poll) = self
+            .pending
+            .as_mut()
+            .and_then(|handle| handle.now_or_never())
+        {
+            self.pending = None;
+            // Otherwise aborted by a follow-up search.
+            if let Ok(results) = poll {
+                self.results = Some(results.map_err(|err| err.to_string()));
+            }
+        }
+        if let Some(poll) = self
+            .pending_entry
+            .as_mut()
+            .and_then(|handle| handle.now_or_never())
+        {
+            self.pending_entry = None;
+            if let Ok(entry) = poll {
+                self.entry = Some(entry.map_err(|err| err.to_string()));
+            }
+        }
+    }
+
+    /// Returns a reading to speak if a speak button was clicked.
+    pub fn ui(
+        &mut self,
+        ctx: &mut Context,
+        ui: &Ui,
+        accents: &AccentStore,
+        vocab: &VocabLists,
+    ) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+        self.poll();
+        let Some(_window) = ui
+            .window("Dictionary")
+            .size([450.0, 500.0], Condition::FirstUseEver)
+            .opened(&mut self.open)
+            .begin()
+        else {
+            return None;
+        };
+
+        ui.set_next_item_width(ui.current_font_size() * -12.0);
+        let entered = ui
+            .input_text("##query", &mut self.query)
+            .hint("Kanji, kana, romaji or English")
+            .enter_returns_true(true)
+            .build();
+        ui.same_line();
+        ui.set_next_item_width(ui.current_font_size() * 5.0);
+        combo_enum(ui, "##mode", &mut self.mode);
+        ui.same_line();
+        if (ui.button("Search") || entered) && !self.query.trim().is_empty() {
+            self.search();
+        }
+        ui.same_line();
+        help_marker(
+            ui,
+            "Kanji and kana match the start of a spelling or reading, \
+             English matches anywhere in a meaning. Auto decides by the \
+             script of the query and looks up Latin text both as romaji, \
+             in hiragana and katakana, and as English.",
+        );
+        if self.pending.is_some() {
+            ui.text_disabled(format!("Searching{}", ellipses(ui)));
+        }
+
+        let mut select = None;
+        match &self.results {
+            Some(Ok(results)) if results.is_empty() => ui.text_disabled("No matches"),
+            Some(Ok(results)) => {
+                let height = ui.content_region_avail()[1] * 0.4;
+                if let Some(_child) = ui.child_window("results").size([0.0, height]).begin() {
+                    for (idx, result) in results.iter().enumerate() {
+                        let _id = ui.push_id_usize(idx);
+                        if ui
+                            .selectable_config(headline(result))
+                            .selected(self.selected == Some(result.seq()))
+                            .build()
+                        {
+                            select = Some(result.clone());
+                        }
+                        ui.same_line();
+                        ui.text_disabled(result.gloss().iter().take(3).join("; "));
+                    }
+                }
+            }
+            Some(Err(err)) => ui.text_colored([1.0, 0.4, 0.4, 1.0], err),
+            None => {}
+        }
+        if let Some(result) = select {
+            self.select(&result);
+        }
+
+        ui.separator();
+        if self.pending_entry.is_some() {
+            ui.text_disabled(format!("Loading{}", ellipses(ui)));
+        }
+        let mut spoken = None;
+        match &self.entry {
+            Some(Ok(Some(selected))) => {
+                if let Some(_child) = ui.child_window("entry").begin() {
+                    spoken = draw_selected(ctx, ui, accents, vocab, selected);
+                }
+            }
+            Some(Ok(None)) => ui.text_disabled("Not in JMdict"),
+            Some(Err(err)) => ui.text_colored([1.0, 0.4, 0.4, 1.0], err),
+            None => {}
+        }
+        spoken
+    }
+}
+
+/// The headword of `selected` with its reading, pitch accent and list
+/// ranks, then the whole entry. Returns the reading if it was to be spoken.
+fn draw_selected(
+    ctx: &mut Context,
+    ui: &Ui,
+    accents: &AccentStore,
+    vocab: &VocabLists,
+    selected: &Selected,
+) -> Option<String> {
+    let entry = &selected.entry;
+    let kana = entry.kana().first().map_or("", |kana| kana.text());
+    let text = entry.kanji().first().map_or(kana, |kanji| kanji.text());
+    let mut spoken = None;
+    draw_kanji_chars(ctx, ui, &selected.kanji_info, text);
+    if speak_button(ui, kana) {
+        spoken = Some(kana.to_string());
+    }
+    ui.same_line();
+    if !draw_accent(ui, accents, text, kana) {
+        ui.text(kana);
+    }
+    let info = vocab.get(Some(entry.seq()), text, kana);
+    if !info.is_empty() {
+        ui.same_line();
+        draw_vocab(ui, info);
+    }
+    draw_entry(ui, &selected.jmdict_data, entry);
+    spoken
+}
+
+/// `食べる【たべる】`, or just the reading for words written in kana.
+fn headline(result: &SearchResult) -> String {
+    let kana = result.kana().join("、");
+    if result.kanji().is_empty() {
+        kana
+    } else {
+        format!("{}【{}】", result.kanji().join("、"), kana)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_mode_goes_by_script() {
+        let queries = |query| SearchMode::Auto.queries(query);
+        assert_eq!(queries("食べ"), [(SearchKey::Kanji, "食べ".into())]);
+        assert_eq!(queries("ラーメン"), [(SearchKey::Kana, "ラーメン".into())]);
+        assert_eq!(
+            queries("sushi "),
+            [
+                (SearchKey::Kana, "すし".into()),
+                (SearchKey::Kana, "スシ".into()),
+                (SearchKey::Gloss, "sushi".into())
+            ]
+        );
+        assert_eq!(queries("cat"), [(SearchKey::Gloss, "cat".into())]);
+        assert!(SearchMode::Romaji.queries("cat").is_empty());
+    }
+
+    #[test]
+    fn romaji_is_also_looked_up_in_katakana() {
+        assert_eq!(romaji_to_katakana("raamen").as_deref(), Some("ラーメン"));
+        assert_eq!(romaji_to_katakana("rāmen").as_deref(), Some("ラーメン"));
+        assert_eq!(romaji_to_katakana("ra-men").as_deref(), Some("ラーメン"));
+        assert_eq!(
+            romaji_to_katakana("konpyuutaa").as_deref(),
+            Some("コンピューター")
+        );
+        assert_eq!(romaji_to_katakana("cat"), None);
+    }
+}
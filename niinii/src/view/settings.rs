@@ -2,7 +2,11 @@ use imgui::*;
 
 use crate::{
     renderer::context::{Context, ContextFlags},
-    settings::Settings,
+    settings::{Settings, TtsBackendType, Voice},
+    tts::{
+        protocol::{style_name, Prosody},
+        TtsEngine,
+    },
 };
 
 use super::mixins::{self, checkbox_option, combo_enum};
@@ -23,7 +27,7 @@ impl SettingsView {
         }
     }
 
-    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &mut Settings) {
+    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &mut Settings, tts: &TtsEngine) {
         if !self.open {
             return;
         }
@@ -89,6 +93,37 @@ impl SettingsView {
             ui.same_line();
             mixins::help_marker(ui, "Search for different ways to interpret a phrase");
             ui.checkbox("Stroke text", &mut settings.stroke_text);
+            ui.input_text("Pitch accent store*", &mut settings.accent.path)
+                .build();
+            ui.same_line();
+            mixins::help_marker(
+                ui,
+                "Where pitch accent dictionaries are imported to, from Gloss > Pitch accent",
+            );
+            ui.checkbox("Pitch accent in furigana", &mut settings.accent.ruby);
+            ui.input_text("Frequency list*", &mut settings.vocab.frequency_path)
+                .build();
+            ui.same_line();
+            mixins::help_marker(
+                ui,
+                "Tab-separated words in order of frequency, or a Yomichan frequency bank (.json)",
+            );
+            ui.checkbox("Frequency list gives counts*", &mut settings.vocab.frequency_counts);
+            ui.same_line();
+            mixins::help_marker(
+                ui,
+                "Whether the list gives how often each word occurs rather than its rank. \
+                 Yomichan banks differ; check the bank's description.",
+            );
+            ui.input_text("JLPT list*", &mut settings.vocab.jlpt_path)
+                .build();
+            ui.same_line();
+            mixins::help_marker(
+                ui,
+                "Tab-separated word, reading and level per line, \
+                 or a JSON array of {word, reading, level} objects",
+            );
+            combo_enum(ui, "Colour words by", &mut settings.vocab.coloring);
         }
         if CollapsingHeader::new("Translation")
             .default_open(true)
@@ -101,21 +136,97 @@ impl SettingsView {
                 .build();
             ui.input_text("OpenAI API endpoint*", &mut settings.chat.api_endpoint)
                 .build();
+            ui.input_text("Anthropic API key*", &mut settings.anthropic_api_key)
+                .password(true)
+                .build();
+            ui.input_text(
+                "Anthropic API endpoint*",
+                &mut settings.chat.anthropic_api_endpoint,
+            )
+            .build();
             ui.slider_config("OpenAI connection timeout (ms)*", 100, 10000)
                 .build(&mut settings.chat.connection_timeout);
             ui.slider_config("OpenAI timeout (ms)*", 100, 10000)
                 .build(&mut settings.chat.timeout);
         }
 
-        if cfg!(feature = "voicevox")
+        if cfg!(feature = "tts")
             && CollapsingHeader::new("Text-to-speech")
                 .default_open(true)
                 .build(ui)
         {
-            ui.input_text("VOICEVOX*", &mut settings.vv_model_path)
-                .build();
+            combo_enum(ui, "Backend*", &mut settings.tts.backend);
+            match settings.tts.backend {
+                TtsBackendType::VoicevoxCore => {
+                    ui.input_text("VOICEVOX*", &mut settings.vv_model_path)
+                        .build();
+                    ui.same_line();
+                    mixins::help_marker(ui, "Path of VOICEVOX models");
+                }
+                TtsBackendType::VoicevoxEngine => {
+                    ui.input_text("Engine endpoint*", &mut settings.tts.engine_endpoint)
+                        .build();
+                    ui.same_line();
+                    mixins::help_marker(
+                        ui,
+                        "VOICEVOX engine or a compatible server, e.g. AivisSpeech on port 10101",
+                    );
+                }
+                TtsBackendType::OpenAiSpeech => {
+                    ui.input_text("Speech endpoint*", &mut settings.tts.speech_endpoint)
+                        .hint(&settings.chat.api_endpoint)
+                        .build();
+                    ui.input_text("Speech model*", &mut settings.tts.speech_model.0)
+                        .build();
+                    ui.input_text("Voice*", &mut settings.tts.speech_voice)
+                        .build();
+                    ui.input_text_multiline(
+                        "Instructions*",
+                        &mut settings.tts.speech_instructions,
+                        [0.0, ui.current_font_size() * 3.0],
+                    )
+                    .build();
+                    ui.slider_config("Speed", 0.25, 4.0)
+                        .display_format("%.2f")
+                        .flags(SliderFlags::ALWAYS_CLAMP)
+                        .build(&mut settings.tts.prosody.speed);
+                }
+                TtsBackendType::Command => {
+                    ui.input_text("Command*", &mut settings.tts.command)
+                        .build();
+                    ui.same_line();
+                    mixins::help_marker(
+                        ui,
+                        "Run by the shell. The line is written to stdin and audio is read \
+                        from stdout, e.g. `espeak-ng -v ja --stdin --stdout`",
+                    );
+                }
+            }
+            if matches!(
+                settings.tts.backend,
+                TtsBackendType::VoicevoxCore | TtsBackendType::VoicevoxEngine
+            ) {
+                combo_style(ui, "Voice", tts, &mut settings.tts.style);
+                draw_prosody(ui, &mut settings.tts.prosody);
+                ui.text("Speaker voices");
+                ui.same_line();
+                mixins::help_marker(
+                    ui,
+                    "Voices for particular speakers, matched against the name before \
+                    the line, as in 雪菜「おはよう」",
+                );
+                draw_voices(ui, tts, &mut settings.tts.voices, settings.tts.style);
+            }
+            let mut cache_size = settings.tts.cache_size as i32;
+            if ui
+                .input_int("Audio cache (MiB)*", &mut cache_size)
+                .step(16)
+                .build()
+            {
+                settings.tts.cache_size = cache_size.max(0) as usize;
+            }
             ui.same_line();
-            mixins::help_marker(ui, "Path of VOICEVOX models");
+            mixins::help_marker(ui, "Synthesized lines are kept so replaying them is instant");
             checkbox_option(ui, &mut settings.auto_tts_regex, |ui, auto_tts_regex| {
                 ui.set_next_item_width(ui.current_font_size() * -8.0);
                 ui.input_text("Auto TTS regex", auto_tts_regex).build();
@@ -160,3 +271,93 @@ impl SettingsView {
         ui.text_disabled("* Restart to apply these changes");
     }
 }
+
+fn draw_prosody(ui: &Ui, prosody: &mut Prosody) {
+    for (label, value, min, max) in [
+        ("Speed", &mut prosody.speed, 0.5, 2.0),
+        ("Pitch", &mut prosody.pitch, -0.15, 0.15),
+        ("Intonation", &mut prosody.intonation, 0.0, 2.0),
+        ("Volume", &mut prosody.volume, 0.0, 2.0),
+    ] {
+        ui.slider_config(label, min, max)
+            .display_format("%.2f")
+            .flags(SliderFlags::ALWAYS_CLAMP)
+            .build(value);
+    }
+}
+
+/// Pick one of the backend's styles. Opening the combo starts the backend
+/// to list them.
+fn combo_style(ui: &Ui, label: &str, tts: &TtsEngine, style: &mut u32) {
+    let preview = tts
+        .model_data()
+        .and_then(|models| style_name(&models, *style))
+        .unwrap_or_else(|| format!("#{}", style));
+    let Some(_token) = ui.begin_combo(label, preview) else {
+        return;
+    };
+    let models = match tts.get_model_data() {
+        Ok(Some(models)) => models,
+        Ok(None) => {
+            ui.text_disabled("Starting...");
+            return;
+        }
+        Err(err) => {
+            ui.text_disabled(err.to_string());
+            return;
+        }
+    };
+    if models.is_empty() {
+        ui.text_disabled("No voices listed");
+    }
+    for model in models.iter() {
+        for s in &model.styles {
+            let selected = s.id == *style;
+            if selected {
+                ui.set_item_default_focus();
+            }
+            let label = format!("{} ({})##{}", model.name, s.name, s.id);
+            if ui.selectable_config(label).selected(selected).build() {
+                *style = s.id;
+            }
+        }
+    }
+}
+
+fn draw_voices(ui: &Ui, tts: &TtsEngine, voices: &mut Vec<Voice>, default_style: u32) {
+    let Some(_t) = ui.begin_table_header_with_flags(
+        "voices",
+        [
+            TableColumnSetup::new(""),
+            TableColumnSetup::new("Speaker"),
+            TableColumnSetup::new("Voice"),
+        ],
+        TableFlags::SIZING_STRETCH_PROP,
+    ) else {
+        return;
+    };
+    let mut delete = None;
+    for (idx, voice) in voices.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(idx);
+        ui.table_next_column();
+        if ui.button_with_size("\u{00d7}", [ui.frame_height(), 0.0]) {
+            delete = Some(idx);
+        }
+        ui.table_next_column();
+        ui.set_next_item_width(ui.content_region_avail()[0]);
+        ui.input_text("##speaker", &mut voice.speaker).build();
+        ui.table_next_column();
+        ui.set_next_item_width(ui.content_region_avail()[0]);
+        combo_style(ui, "##style", tts, &mut voice.style);
+    }
+    if let Some(idx) = delete {
+        voices.remove(idx);
+    }
+    ui.table_next_column();
+    if ui.button_with_size("+", [ui.frame_height(), 0.0]) {
+        voices.push(Voice {
+            speaker: String::new(),
+            style: default_style,
+        });
+    }
+}
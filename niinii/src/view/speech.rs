@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use imgui::*;
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use crate::{
+    settings::Settings,
+    tts::{self, export, TtsEngine},
+};
+
+use super::mixins::{combo_enum, ellipses, help_marker};
+
+type Export = Result<Vec<PathBuf>, tts::Error>;
+
+/// Playback controls and the lines spoken so far, which can be exported as
+/// audio files.
+#[derive(Debug, Default)]
+pub struct SpeechView {
+    pub open: bool,
+    /// Selected range of the history, inclusive.
+    selection: Option<(usize, usize)>,
+    pending: Option<oneshot::Receiver<Export>>,
+    exported: Option<Export>,
+}
+
+impl SpeechView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show_menu_item(&mut self, ui: &Ui) {
+        if ui.menu_item("Speech") {
+            self.open = true;
+        }
+    }
+
+    fn poll(&mut self) {
+        let Some(rx) = &mut self.pending else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Closed) => Err(tts::Error::Channel),
+        };
+        self.pending = None;
+        self.exported = Some(result);
+    }
+
+    pub fn ui(&mut self, ui: &Ui, settings: &mut Settings, tts: &TtsEngine) {
+        if !self.open {
+            return;
+        }
+        self.poll();
+        let Some(_window) = ui
+            .window("Speech")
+            .size([400.0, 300.0], Condition::FirstUseEver)
+            .opened(&mut self.open)
+            .begin()
+        else {
+            return;
+        };
+
+        draw_playback(ui, settings, tts);
+        ui.separator();
+
+        let history = tts.history();
+        let last = history.len().checked_sub(1);
+        let selection = self
+            .selection
+            .filter(|(start, end)| *start.max(end) < history.len())
+            .map(|(start, end)| (start.min(end), start.max(end)));
+
+        let mut export = None;
+        ui.disabled(self.pending.is_some(), || {
+            ui.disabled(last.is_none(), || {
+                if ui.button("Export last line") {
+                    export = last.map(|last| (last, last));
+                }
+            });
+            ui.same_line();
+            ui.disabled(selection.is_none(), || {
+                if ui.button("Export selected") {
+                    export = selection;
+                }
+            });
+        });
+        ui.same_line();
+        help_marker(
+            ui,
+            "Click a line to select it, shift+click to select a range. \
+             Files are named by a hash of the line, voice and delivery, so \
+             exporting a line twice gives the same file.",
+        );
+        ui.set_next_item_width(ui.current_font_size() * 6.0);
+        combo_enum(ui, "##format", &mut settings.tts.export_format);
+        ui.same_line();
+        ui.input_text("Directory", &mut settings.tts.export_dir)
+            .build();
+        if settings.tts.export_format != export::ExportFormat::Wav {
+            ui.input_text("ffmpeg", &mut settings.tts.ffmpeg_path)
+                .build();
+        }
+
+        if self.pending.is_some() {
+            ui.text_disabled(format!("Exporting{}", ellipses(ui)));
+        }
+        match &self.exported {
+            Some(Ok(paths)) => {
+                ui.text(format!("Wrote {} files", paths.len()));
+                ui.same_line();
+                if ui.small_button("Copy as Anki field") {
+                    let tags: Vec<String> =
+                        paths.iter().map(|path| export::sound_tag(path)).collect();
+                    ui.set_clipboard_text(tags.concat());
+                }
+            }
+            Some(Err(err)) => ui.text_colored([1.0, 0.4, 0.4, 1.0], err.to_string()),
+            None => {}
+        }
+
+        ui.separator();
+        if let Some(_child) = ui.child_window("history").begin() {
+            for (idx, utterance) in history.iter().enumerate() {
+                let _id = ui.push_id_usize(idx);
+                let selected = selection.is_some_and(|(start, end)| (start..=end).contains(&idx));
+                if ui
+                    .selectable_config(utterance.text())
+                    .selected(selected)
+                    .build()
+                {
+                    self.selection = match self.selection {
+                        Some((start, _)) if ui.io().key_shift => Some((start, idx)),
+                        _ => Some((idx, idx)),
+                    };
+                }
+            }
+        }
+
+        if let Some((start, end)) = export {
+            let utterances = history.range(start..=end).cloned().collect();
+            drop(history);
+            match tts.export(utterances, &settings.tts) {
+                Ok(rx) => {
+                    self.pending = Some(rx);
+                    self.exported = None;
+                }
+                Err(err) => self.exported = Some(Err(err)),
+            }
+        }
+    }
+}
+
+fn draw_playback(ui: &Ui, settings: &mut Settings, tts: &TtsEngine) {
+    let playback = tts.playback();
+    if playback.paused {
+        if ui.button("Resume") {
+            tts.resume();
+        }
+    } else if ui.button("Pause") {
+        tts.pause();
+    }
+    ui.same_line();
+    if ui.button("Stop") {
+        tts.stop();
+    }
+    ui.same_line();
+    ui.disabled(playback.lines.len() < 2, || {
+        if ui.button("Skip") {
+            tts.skip();
+        }
+    });
+    ui.same_line();
+    ui.disabled(tts.history().is_empty(), || {
+        if ui.button("Replay") {
+            if let Err(err) = tts.replay() {
+                tracing::error!(%err, "replay failed");
+            }
+        }
+    });
+    ui.same_line();
+    ui.set_next_item_width(ui.current_font_size() * 6.0);
+    combo_enum(ui, "Mode", &mut settings.tts.playback);
+    ui.same_line();
+    help_marker(
+        ui,
+        "What happens to a line spoken while another is playing: \
+         interrupt cuts the playing line off, queue plays it afterwards",
+    );
+
+    match playback.lines.first() {
+        Some(line) => {
+            let position = playback.position.as_secs_f32();
+            let (fraction, overlay) = match playback.duration {
+                Some(duration) => (
+                    position / duration.as_secs_f32().max(f32::EPSILON),
+                    format!("{:.1}s / {:.1}s", position, duration.as_secs_f32()),
+                ),
+                None => (0.0, format!("{:.1}s", position)),
+            };
+            ProgressBar::new(fraction.min(1.0))
+                .overlay_text(overlay)
+                .build(ui);
+            ui.text_wrapped(line);
+        }
+        None => ui.text_disabled("Not speaking"),
+    }
+    if playback.synthesizing {
+        ui.text_disabled(format!("Synthesizing{}", ellipses(ui)));
+    }
+    for line in playback.lines.iter().skip(1) {
+        ui.bullet_text(line);
+    }
+}
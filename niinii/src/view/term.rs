@@ -1,63 +1,143 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
+use futures::FutureExt;
 use ichiran::prelude::*;
 use imgui::*;
+use tokio::task::JoinHandle;
 
 use super::kanji::KanjiView;
 use super::mixins::*;
+use crate::accent::AccentStore;
+use crate::parser::{self, Parser};
 use crate::renderer::context::Context;
 use crate::settings::Settings;
+use crate::vocab::{VocabLists, WordInfo};
+
+/// Most entries kept loaded. ichiran caches more behind this.
+const LOADED_LIMIT: usize = 64;
+/// How long a failed fetch is shown before it is tried again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Complete JMdict entries for the "Full entry" section of term views,
+/// fetched in the background the first time each is expanded.
+pub struct EntryLoader {
+    parser: Parser,
+    pending: RefCell<HashMap<u32, JoinHandle<Result<Option<Entry>, parser::Error>>>>,
+    /// Oldest first, at most [`LOADED_LIMIT`].
+    loaded: RefCell<VecDeque<(u32, Option<Entry>)>>,
+    /// Fetches that failed, and when.
+    failed: RefCell<HashMap<u32, (String, Instant)>>,
+}
+impl EntryLoader {
+    pub fn new(parser: Parser) -> Self {
+        Self {
+            parser,
+            pending: RefCell::new(HashMap::new()),
+            loaded: RefCell::new(VecDeque::new()),
+            failed: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The entry with sequence number `seq`, or `None` while it is loading.
+    /// A failed fetch is tried again after [`RETRY_INTERVAL`].
+    fn get(&self, seq: u32) -> Option<Result<Option<Entry>, String>> {
+        let loaded = self.loaded.borrow();
+        if let Some((_, entry)) = loaded.iter().find(|(loaded, _)| *loaded == seq) {
+            return Some(Ok(entry.clone()));
+        }
+        drop(loaded);
+        let mut failed = self.failed.borrow_mut();
+        if let Some((err, at)) = failed.get(&seq) {
+            if at.elapsed() < RETRY_INTERVAL {
+                return Some(Err(err.clone()));
+            }
+            failed.remove(&seq);
+        }
+        let mut pending = self.pending.borrow_mut();
+        let handle = pending.entry(seq).or_insert_with(|| {
+            let parser = self.parser.clone();
+            tokio::spawn(async move { parser.entry(seq).await })
+        });
+        let poll = handle.now_or_never()?;
+        pending.remove(&seq);
+        let entry = match poll {
+            Ok(result) => result.map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        match &entry {
+            Ok(entry) => {
+                let mut loaded = self.loaded.borrow_mut();
+                loaded.push_back((seq, entry.clone()));
+                if loaded.len() > LOADED_LIMIT {
+                    loaded.pop_front();
+                }
+            }
+            Err(err) => {
+                failed.insert(seq, (err.clone(), Instant::now()));
+            }
+        }
+        Some(entry)
+    }
+}
 
 pub struct TermView<'a> {
     jmdict_data: &'a JmDictData,
     kanji_info: &'a HashMap<char, Kanji>,
+    accents: &'a AccentStore,
+    vocab: &'a VocabLists,
+    entries: &'a EntryLoader,
     romaji: &'a Romanized,
     wrap_w: f32,
+    /// Offer the complete entry of each word.
+    full_entry: bool,
+    /// Reading whose speak button was clicked.
+    spoken: RefCell<Option<String>>,
 }
 impl<'a> TermView<'a> {
     pub fn new(
         jmdict_data: &'a JmDictData,
         kanji_info: &'a HashMap<char, Kanji>,
+        accents: &'a AccentStore,
+        vocab: &'a VocabLists,
+        entries: &'a EntryLoader,
         romaji: &'a Romanized,
         wrap_w: f32,
     ) -> Self {
         Self {
             jmdict_data,
             kanji_info,
+            accents,
+            vocab,
+            entries,
             romaji,
             wrap_w,
+            full_entry: true,
+            spoken: RefCell::new(None),
         }
     }
 
-    fn add_pos(&self, _ctx: &mut Context, ui: &Ui, pos: &str) {
-        ui.text_colored(ui.style_color(StyleColor::NavHighlight), pos);
-        if ui.is_item_hovered() {
-            if let Some(kwpos) = self.jmdict_data.kwpos_by_kw.get(pos) {
-                ui.tooltip_text(kwpos.descr.as_str());
-            }
+    /// Leave out the "Full entry" section, e.g. where it cannot be expanded.
+    pub fn without_full_entry(mut self) -> Self {
+        self.full_entry = false;
+        self
+    }
+
+    fn speak(&self, kana: Option<String>) {
+        if kana.is_some() {
+            *self.spoken.borrow_mut() = kana;
         }
     }
 
-    fn add_glosses(&self, ctx: &mut Context, ui: &Ui, glosses: &[Gloss]) {
+    fn add_glosses(&self, ui: &Ui, glosses: &[Gloss]) {
         for (i, gloss) in glosses.iter().enumerate() {
             // index
             ui.text(format!("{}.", i + 1));
             ui.same_line();
             ui.group(|| {
                 // part-of-speech
-                ui.text("[");
-                ui.same_line_with_spacing(0.0, 0.0);
-                let pos_split = gloss.pos_split();
-                for (i, pos) in pos_split.iter().enumerate() {
-                    self.add_pos(ctx, ui, pos);
-                    ui.same_line_with_spacing(0.0, 0.0);
-                    if i != pos_split.len() - 1 {
-                        ui.text(",");
-                        ui.same_line_with_spacing(0.0, 0.0);
-                    }
-                }
-                ui.text("]");
-                ui.same_line();
+                draw_pos_list(ui, self.jmdict_data, &gloss.pos_split());
                 // gloss
                 ui.text(gloss.gloss());
                 // info
@@ -68,10 +148,6 @@ impl<'a> TermView<'a> {
         }
     }
 
-    fn kanji_tooltip(&self, ctx: &mut Context, ui: &Ui, kanji: &Kanji) {
-        ui.tooltip(|| KanjiView::new(kanji, 25.0).ui(ctx, ui));
-    }
-
     fn add_word(
         &self,
         ctx: &mut Context,
@@ -88,42 +164,29 @@ impl<'a> TermView<'a> {
             if let Some(chr) = &meta.text().chars().next() {
                 let kanji = self.kanji_info.get(chr);
                 if let Some(kanji) = kanji {
-                    KanjiView::new(kanji, 30.0).ui(ctx, ui)
+                    self.speak(KanjiView::new(kanji, 30.0).ui(ctx, ui));
                 }
             }
         } else if show_kanji {
-            for chr in meta.text().chars() {
-                let kanji = self.kanji_info.get(&chr);
-                {
-                    let _style_token = ui.push_style_var(StyleVar::ItemSpacing([0.0, 4.0]));
-                    let text = format!("{}", chr);
-                    ui.same_line();
-                    draw_kanji_text(
-                        ui,
-                        ctx,
-                        &text,
-                        RubyTextMode::None,
-                        KanjiStyle {
-                            highlight: kanji.is_some(),
-                            stroke: false,
-                            preview: false,
-                            underline: UnderlineMode::None,
-                        },
-                    );
-                }
+            draw_kanji_chars(ctx, ui, self.kanji_info, meta.text());
 
-                if let Some(kanji) = kanji {
-                    if ui.is_item_hovered() {
-                        self.kanji_tooltip(ctx, ui, kanji);
-                    }
-                }
+            if speak_button(ui, meta.kana()) {
+                self.speak(Some(meta.kana().to_owned()));
             }
-
-            if meta.kana() != meta.text() {
+            ui.same_line();
+            if draw_accent(ui, self.accents, meta.text(), meta.kana()) {
+                ui.same_line();
+                ui.text(romaji);
+            } else if meta.kana() != meta.text() {
                 ui.text(format!("{}\u{ff0f}{}", meta.kana(), romaji));
             } else {
                 ui.text(romaji);
             }
+            let info = word_info(self.vocab, word);
+            if !info.is_empty() {
+                ui.same_line();
+                draw_vocab(ui, info);
+            }
         }
 
         match word {
@@ -132,9 +195,9 @@ impl<'a> TermView<'a> {
                 if [10285144, 10285148, 10285150, 10285187].contains(&plain.seq().unwrap_or(0)) {
                     return;
                 }
-                self.add_glosses(ctx, ui, plain.gloss());
+                self.add_glosses(ui, plain.gloss());
                 for conj in plain.conj() {
-                    self.add_conj(ctx, ui, conj);
+                    self.add_conj(ui, conj);
                 }
                 if let Some(suffix) = plain.suffix() {
                     ui.bullet();
@@ -148,6 +211,9 @@ impl<'a> TermView<'a> {
                         ui.text_colored([0., 1., 1., 1.], "ordinal");
                     }
                 }
+                if let Some(seq) = plain.seq().filter(|_| self.full_entry) {
+                    self.add_full_entry(ui, seq);
+                }
             }
             Word::Compound(compound) => {
                 ui.text(format!("Compound {}", compound.compound().join(" + ")));
@@ -165,6 +231,18 @@ impl<'a> TermView<'a> {
         }
     }
 
+    fn add_full_entry(&self, ui: &Ui, seq: u32) {
+        if !CollapsingHeader::new(format!("Full entry##{}", seq)).build(ui) {
+            return;
+        }
+        match self.entries.get(seq) {
+            None => ui.text_disabled(format!("Loading{}", ellipses(ui))),
+            Some(Ok(Some(entry))) => draw_entry(ui, self.jmdict_data, &entry),
+            Some(Ok(None)) => ui.text_disabled("Not in JMdict"),
+            Some(Err(err)) => ui.text_colored([1.0, 0.4, 0.4, 1.0], err),
+        }
+    }
+
     fn add_term(
         &self,
         ctx: &mut Context,
@@ -187,7 +265,7 @@ impl<'a> TermView<'a> {
         }
     }
 
-    fn add_conj(&self, ctx: &mut Context, ui: &Ui, conj: &Conjugation) {
+    fn add_conj(&self, ui: &Ui, conj: &Conjugation) {
         for vias in conj.flatten() {
             let base = *vias.first().unwrap();
 
@@ -211,7 +289,7 @@ impl<'a> TermView<'a> {
                             }
                             ui.text("[");
                             ui.same_line_with_spacing(0.0, 0.0);
-                            self.add_pos(ctx, ui, prop.pos());
+                            draw_pos(ui, self.jmdict_data, prop.pos());
                             ui.same_line_with_spacing(0.0, 0.0);
                             ui.text("]");
                             ui.same_line();
@@ -236,12 +314,17 @@ impl<'a> TermView<'a> {
                         }
                     }
                 }
-                self.add_glosses(ctx, ui, base.gloss());
+                if let Some(reading) = base.reading() {
+                    let (text, kana) = split_reading(reading);
+                    draw_accent(ui, self.accents, text, kana);
+                }
+                self.add_glosses(ui, base.gloss());
             }
         }
     }
 
-    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &Settings) {
+    /// Returns the reading to speak, if one was asked for.
+    pub fn ui(&mut self, ctx: &mut Context, ui: &Ui, settings: &Settings) -> Option<String> {
         // TODO: figure out ないでしょ layout issue with compound + alternative
         let _wrap_token = ui.push_text_wrap_pos_with_pos(ui.current_font_size() * self.wrap_w);
         self.add_term(
@@ -252,5 +335,196 @@ impl<'a> TermView<'a> {
             self.romaji.romaji(),
             true,
         );
+        self.spoken.take()
+    }
+}
+
+/// Draw the characters of `text` large, with kanji details on hover.
+pub(super) fn draw_kanji_chars(
+    ctx: &mut Context,
+    ui: &Ui,
+    kanji_info: &HashMap<char, Kanji>,
+    text: &str,
+) {
+    for chr in text.chars() {
+        let kanji = kanji_info.get(&chr);
+        {
+            let _style_token = ui.push_style_var(StyleVar::ItemSpacing([0.0, 4.0]));
+            let text = format!("{}", chr);
+            ui.same_line();
+            draw_kanji_text(
+                ui,
+                ctx,
+                &text,
+                RubyTextMode::None,
+                KanjiStyle {
+                    highlight: kanji.is_some(),
+                    tint: None,
+                    stroke: false,
+                    preview: false,
+                    underline: UnderlineMode::None,
+                },
+            );
+        }
+
+        if let Some(kanji) = kanji {
+            if ui.is_item_hovered() {
+                ui.tooltip(|| {
+                    KanjiView::new(kanji, 25.0).ui(ctx, ui);
+                });
+            }
+        }
+    }
+}
+
+/// Mark the pitch accent of `text` read as `kana`, if it is known.
+pub(super) fn draw_accent(ui: &Ui, accents: &AccentStore, text: &str, kana: &str) -> bool {
+    let Some(accents) = accents.get(text, kana) else {
+        return false;
+    };
+    accent_text(ui, kana, accents[0]);
+    ui.same_line();
+    let accents: Vec<String> = accents.iter().map(u8::to_string).collect();
+    ui.text_disabled(format!("[{}]", accents.join(",")));
+    if ui.is_item_hovered() {
+        ui.tooltip_text("Pitch drops after this mora, 0 if it does not drop");
+    }
+    true
+}
+
+pub(super) fn draw_vocab(ui: &Ui, info: WordInfo) {
+    if let Some(level) = info.jlpt {
+        ui.text_colored(jlpt_color(level), <&str>::from(level));
+        if ui.is_item_hovered() {
+            ui.tooltip_text("JLPT level");
+        }
+        if info.rank.is_some() {
+            ui.same_line();
+        }
+    }
+    if let Some(rank) = info.rank {
+        ui.text_colored(rank_color(rank), format!("#{}", rank));
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Frequency rank");
+        }
+    }
+}
+
+fn draw_pos(ui: &Ui, jmdict_data: &JmDictData, pos: &str) {
+    ui.text_colored(ui.style_color(StyleColor::NavHighlight), pos);
+    if ui.is_item_hovered() {
+        if let Some(kwpos) = jmdict_data.kwpos_by_kw.get(pos) {
+            ui.tooltip_text(kwpos.descr.as_str());
+        }
+    }
+}
+
+/// Draw `[pos1,pos2]` and leave the cursor on the same line.
+fn draw_pos_list(ui: &Ui, jmdict_data: &JmDictData, pos_split: &[&str]) {
+    ui.text("[");
+    ui.same_line_with_spacing(0.0, 0.0);
+    for (i, pos) in pos_split.iter().enumerate() {
+        draw_pos(ui, jmdict_data, pos);
+        ui.same_line_with_spacing(0.0, 0.0);
+        if i != pos_split.len() - 1 {
+            ui.text(",");
+            ui.same_line_with_spacing(0.0, 0.0);
+        }
+    }
+    ui.text("]");
+    ui.same_line();
+}
+
+/// Every spelling, reading and sense of `entry`. Uncommon forms are dimmed.
+pub(super) fn draw_entry(ui: &Ui, jmdict_data: &JmDictData, entry: &Entry) {
+    if !entry.kanji().is_empty() {
+        ui.text_disabled("Written");
+        for kanji in entry.kanji() {
+            ui.same_line();
+            if kanji.common().is_some() {
+                ui.text(kanji.text());
+            } else {
+                ui.text_disabled(kanji.text());
+            }
+        }
+    }
+    ui.text_disabled("Read");
+    for kana in entry.kana() {
+        ui.same_line();
+        if kana.common().is_some() {
+            ui.text(kana.text());
+        } else {
+            ui.text_disabled(kana.text());
+        }
+        if ui.is_item_hovered() && !entry.kanji().is_empty() {
+            if kana.nokanji() {
+                ui.tooltip_text("Not a true reading of the kanji");
+            } else if !kana.restr().is_empty() {
+                ui.tooltip_text(format!("Only for {}", kana.restr().join("\u{3001}")));
+            }
+        }
+    }
+
+    for (i, sense) in entry.sense().iter().enumerate() {
+        ui.text(format!("{}.", i + 1));
+        ui.same_line();
+        ui.group(|| {
+            let pos = sense.pos();
+            if !pos.is_empty() {
+                draw_pos_list(ui, jmdict_data, &pos);
+            }
+            ui.text(sense.gloss().join("; "));
+            let tags = [sense.misc(), sense.field(), sense.dial()].concat();
+            if !tags.is_empty() {
+                ui.text_disabled(format!("{{{}}}", tags.join(", ")));
+            }
+            for (label, props) in [
+                ("Only for", [sense.stagk(), sense.stagr()].concat()),
+                ("Note", sense.info()),
+                ("See also", sense.xref()),
+                ("Antonym", sense.ant()),
+            ] {
+                if !props.is_empty() {
+                    ui.text_disabled(format!("{}: {}", label, props.join(", ")));
+                }
+            }
+        });
+    }
+}
+
+/// Split a reading like `食べる 【たべる】` into text and kana. Words written in
+/// kana have no bracketed part.
+fn split_reading(reading: &str) -> (&str, &str) {
+    match reading.split_once(" \u{3010}") {
+        Some((text, kana)) => (text, kana.trim_end_matches('\u{3011}')),
+        None => (reading, reading),
+    }
+}
+
+/// What the vocabulary lists say about `word`. Conjugated words are looked up
+/// by their dictionary form if they are not listed themselves.
+pub(super) fn word_info(vocab: &VocabLists, word: &Word) -> WordInfo {
+    let meta = word.meta();
+    let seq = match word {
+        Word::Plain(plain) => plain.seq(),
+        Word::Compound(_) => None,
+    };
+    let info = vocab.get(seq, meta.text(), meta.kana());
+    let Word::Plain(plain) = word else {
+        return info;
+    };
+    if !info.is_empty() {
+        return info;
     }
+    plain
+        .conj()
+        .iter()
+        .flat_map(|conj| conj.flatten())
+        .filter_map(|vias| vias.first()?.reading())
+        .map(|reading| {
+            let (text, kana) = split_reading(reading);
+            vocab.get(None, text, kana)
+        })
+        .find(|info| !info.is_empty())
+        .unwrap_or(info)
 }
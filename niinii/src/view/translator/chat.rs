@@ -1,14 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
+use ichiran::prelude::Root;
 use imgui::*;
 use openai::chat::{Message, Role, Usage};
 use openai::ModelId;
+use strum::IntoEnumIterator;
 
 use crate::{
-    settings::{Settings, TranslatorType},
-    translator::chat::{
-        self, ChatHandle, ContextEdit, ExchangeId, ExchangeView, MsgId, Response, TranslateConfig,
+    parser::SyntaxTree,
+    renderer::context::{Context, FontFace},
+    settings::{ChatSettings, Settings, TranslatorType},
+    translator::{
+        budget::{self, Budget, Window},
+        chat::{
+            self, ChatHandle, ChatState, ContextEdit, ExchangeId, ExchangeView, MsgId, Response,
+            TranslateConfig,
+        },
+        compare::CompareLane,
+        glossary::{self, Glossary, GlossaryTerm},
+        memory::{self, MemorySettings, Suggestion},
+        pricing::{ModelPrice, Pricing, UsageTotals},
+        session::{Session, SessionStore},
+        template::{self, PromptPreset, Template, Variable, Variables},
     },
     view::mixins::{
         checkbox_option, checkbox_option_with_default, combo_enum, combo_list, drag_handle,
@@ -24,32 +39,281 @@ pub struct TranslatorWindow {
     translator: ChatHandle,
     current: Option<ExchangeId>,
     buffers: HashMap<MsgId, String>,
+    /// Fuzzy matches from the translation memory for the current exchange.
+    suggestions: Option<(ExchangeId, Vec<Suggestion>)>,
+    glossary: Glossary,
+    sessions: SessionStore,
+    /// Name typed into the "Save as" field.
+    session_name: String,
+    /// What autosave last wrote.
+    saved: Option<SavedSession>,
+    /// Names of the stored sessions, listed when the Session menu opens.
+    session_names: Option<Vec<String>>,
+    /// Candidates of the last comparison, in lane order.
+    comparison: Vec<ExchangeId>,
+    /// Template variables of the last submitted line, for the preview.
+    variables: Variables,
+    /// Name typed into the "Save as preset" field.
+    preset_name: String,
+    /// Number of leading exchanges that have all ended and were checked for
+    /// glyphs missing from the font atlas.
+    glyphs_scanned_upto: usize,
+    /// Completed responses past those already checked for missing glyphs.
+    glyphs_scanned: HashSet<ExchangeId>,
+    /// Context messages already checked for missing glyphs, as of
+    /// `glyphs_context_revision`.
+    glyphs_scanned_context: HashSet<MsgId>,
+    glyphs_context_revision: u64,
+    /// Model to regenerate with instead of the configured one.
+    regenerate_model: Option<ModelId>,
+    /// Temperature to regenerate with instead of the configured one.
+    regenerate_temperature: Option<f32>,
     pub open: bool,
 }
 
 impl TranslatorWindow {
-    pub fn new(settings: &Settings) -> Self {
+    /// Spawns the backend and restores the last session, including its
+    /// prompt settings.
+    pub fn new(settings: &mut Settings) -> Self {
         let translator = match settings.translator_type {
-            TranslatorType::Chat => chat::spawn(settings),
+            TranslatorType::Chat | TranslatorType::Messages | TranslatorType::Responses => {
+                chat::spawn(settings)
+            }
         };
+        let sessions = SessionStore::new(&settings.chat.sessions_dir);
+        if let Some(name) = settings.chat.session.clone() {
+            match sessions.load(&name) {
+                Ok(session) => {
+                    let context = restore_session(&mut settings.chat, session);
+                    translator.load_session(Some(name), context);
+                }
+                Err(err) => {
+                    tracing::error!(%err, %name, "failed to restore session");
+                    settings.chat.session = None;
+                }
+            }
+        }
         Self {
             translator,
             current: None,
             buffers: HashMap::new(),
+            suggestions: None,
+            glossary: Glossary::from_file(&settings.chat.glossary_path),
+            sessions,
+            session_name: String::new(),
+            saved: None,
+            session_names: None,
+            comparison: Vec::new(),
+            variables: Variables::default(),
+            preset_name: String::new(),
+            glyphs_scanned_upto: 0,
+            glyphs_scanned: HashSet::new(),
+            glyphs_scanned_context: HashSet::new(),
+            glyphs_context_revision: 0,
+            regenerate_model: None,
+            regenerate_temperature: None,
             open: false,
         }
     }
 
+    /// Add glyphs the translations use to the font atlas, and switch to the
+    /// font face for the target language. Only the Japanese set is loaded up
+    /// front, so translations into Chinese or Korean would otherwise render as
+    /// placeholders. Responses are scanned once they complete, since each new
+    /// glyph rebuilds the atlas.
+    pub fn add_unknown_glyphs(&mut self, ctx: &mut Context, settings: &Settings) {
+        ctx.set_font_face(FontFace::for_language(&settings.chat.target_language));
+        let state = self.translator.state();
+        for ex in &state.exchanges[self.glyphs_scanned_upto..] {
+            if let Response::Completed { content, .. } = &ex.response {
+                if self.glyphs_scanned.insert(ex.id) {
+                    ctx.add_unknown_glyphs_from_text(content);
+                }
+            }
+        }
+        // Exchanges never change once ended, so only those after the first
+        // one still running need remembering.
+        while let Some(ex) = state
+            .exchanges
+            .get(self.glyphs_scanned_upto)
+            .filter(|ex| ex.response.is_terminal())
+        {
+            self.glyphs_scanned.remove(&ex.id);
+            self.glyphs_scanned_upto += 1;
+        }
+        // Forget messages that were cleared, replaced or evicted.
+        if self.glyphs_context_revision != state.context_revision {
+            self.glyphs_scanned_context
+                .retain(|id| state.context.iter().any(|entry| entry.id == *id));
+            self.glyphs_context_revision = state.context_revision;
+        }
+        for entry in &state.context {
+            if self.glyphs_scanned_context.insert(entry.id) {
+                if let Some(content) = &entry.message.content {
+                    ctx.add_unknown_glyphs_from_text(content);
+                }
+            }
+        }
+    }
+
     /// Cancel any in-flight translation and submit a new one. The new id
-    /// becomes the "current" exchange rendered in the main UI.
-    pub fn translate(&mut self, settings: &Settings, text: String) {
+    /// becomes the "current" exchange rendered in the main UI. `ast` is used
+    /// to detect glossary terms if it is the segmentation of `text`.
+    pub fn translate(&mut self, settings: &Settings, text: String, ast: Option<&SyntaxTree>) {
         if let Some(prev) = self.current {
             self.translator.cancel(prev);
         }
-        let config = Arc::new(TranslateConfig::from_settings(settings));
+        let config = self.config(settings, &text, ast);
         self.current = Some(self.translator.translate(text, config));
     }
 
+    /// Translate the current exchange's text again after the same context,
+    /// bypassing the translation memory. Earlier attempts are kept as
+    /// alternates.
+    pub fn regenerate(&mut self, settings: &Settings, ast: Option<&SyntaxTree>) {
+        let Some(prev) = self.current else { return };
+        let state = self.translator.state();
+        let Some(ex) = state.exchange(prev) else {
+            return;
+        };
+        let text = ex.user_message.content.as_deref().unwrap_or_default();
+        self.translator.cancel(prev);
+        let mut config = (*self.config(settings, text, ast)).clone();
+        if let Some(model) = &self.regenerate_model {
+            config.model = model.clone();
+        }
+        if let Some(temperature) = self.regenerate_temperature {
+            config.temperature = Some(temperature);
+        }
+        self.current = Some(self.translator.regenerate(prev, Arc::new(config)));
+    }
+
+    /// Commit an earlier attempt at the current line in place of the one
+    /// that is. It becomes the current exchange.
+    fn choose(&mut self, settings: &Settings, id: ExchangeId) {
+        let config = Arc::new(TranslateConfig::from_settings(settings));
+        self.translator.choose(id, config);
+        self.current = Some(id);
+    }
+
+    /// Send `text` to every comparison lane and open the window to show
+    /// the candidates side by side.
+    pub fn compare(&mut self, settings: &Settings, text: String, ast: Option<&SyntaxTree>) {
+        for id in self.comparison.drain(..) {
+            self.translator.cancel(id);
+        }
+        let config = self.config(settings, &text, ast);
+        let lanes = settings
+            .chat
+            .compare_lanes
+            .iter()
+            .map(|lane| {
+                let client = self.translator.client().for_lane(settings, lane);
+                (client, Arc::new(lane.config(&settings.chat, &config)))
+            })
+            .collect();
+        self.comparison = self.translator.compare(text, lanes);
+        self.open = true;
+    }
+
+    pub fn can_compare(&self, settings: &Settings) -> bool {
+        !settings.chat.compare_lanes.is_empty()
+    }
+
+    /// Commit a comparison candidate to the context buffer. It becomes the
+    /// current exchange.
+    fn pick(&mut self, settings: &Settings, id: ExchangeId) {
+        let config = Arc::new(TranslateConfig::from_settings(settings));
+        self.translator.pick(id, config);
+        self.current = Some(id);
+    }
+
+    /// Snapshot the settings for `text`, rendering the prompt template with
+    /// the line's variables.
+    fn config(
+        &mut self,
+        settings: &Settings,
+        text: &str,
+        ast: Option<&SyntaxTree>,
+    ) -> Arc<TranslateConfig> {
+        let root = ast
+            .filter(|ast| ast.original_text == text)
+            .map(|ast| &ast.root);
+        // When regenerating, the line itself is still in the buffer.
+        let previous_line = self
+            .translator
+            .state()
+            .context
+            .iter()
+            .rev()
+            .filter(|e| e.message.role == Role::User && e.message.name.is_none())
+            .filter_map(|e| e.message.content.clone())
+            .find(|content| content != text)
+            .unwrap_or_default();
+        let (config, variables) = self.line_config(settings, text, root, previous_line);
+        self.variables = variables;
+        Arc::new(config)
+    }
+
+    fn line_config(
+        &self,
+        settings: &Settings,
+        text: &str,
+        root: Option<&Root>,
+        previous_line: String,
+    ) -> (TranslateConfig, Variables) {
+        let c = &settings.chat;
+        let mut config = TranslateConfig::from_settings(settings);
+        config.glossary = self.glossary.detect(text, root, &c.target_language);
+        let variables = Variables {
+            speaker: template::split_speaker(text)
+                .0
+                .unwrap_or_default()
+                .to_string(),
+            previous_line,
+            game_title: c.game_title.clone(),
+            glossary: if config.glossary.is_empty() {
+                String::new()
+            } else {
+                glossary::prompt(&config.glossary)
+            },
+            gloss: root.map(template::gloss_summary).unwrap_or_default(),
+            target_language: c.target_language.clone(),
+        };
+        config.set_prompt(c.prompt_template(), &variables);
+        (config, variables)
+    }
+
+    /// Translate `lines` ahead of time, in the order they will be shown.
+    /// Lines are matched against later translations by their text.
+    pub fn prefetch(&mut self, settings: &Settings, lines: Vec<String>) {
+        let state = self.translator.state();
+        let mut previous_line = self
+            .current
+            .and_then(|id| state.exchange(id))
+            .and_then(|ex| ex.user_message.content.clone())
+            .unwrap_or_default();
+        let lines = lines
+            .into_iter()
+            .map(|text| {
+                let previous_line = std::mem::replace(&mut previous_line, text.clone());
+                let (config, _) = self.line_config(settings, &text, None, previous_line);
+                (text, Arc::new(config))
+            })
+            .collect();
+        self.translator.prefetch(lines);
+    }
+
+    pub fn can_regenerate(&self) -> bool {
+        self.current.is_some_and(|id| {
+            let state = self.translator.state();
+            state
+                .exchange(id)
+                .is_some_and(|ex| ex.response.is_terminal())
+                && state.regenerate_context(id).is_some()
+        })
+    }
+
     /// Forget the current exchange without cancelling it. Used when a new
     /// gloss arrives and the user has not opted into auto-translate.
     pub fn clear_current(&mut self) {
@@ -57,12 +321,12 @@ impl TranslatorWindow {
     }
 
     /// Render the usage bar for the current exchange, if any.
-    pub fn draw_current_usage(&self, ui: &Ui) {
+    pub fn draw_current_usage(&self, ui: &Ui, settings: &Settings) {
         let Some(id) = self.current else { return };
         let state = self.translator.state();
         if let Some(ex) = state.exchange(id) {
             if let Some(usage) = &ex.usage {
-                draw_usage(ui, &ex.model, usage);
+                draw_usage(ui, &ex.model, usage, &settings.chat.pricing);
             }
         }
     }
@@ -82,7 +346,41 @@ impl TranslatorWindow {
         }
     }
 
+    /// Write the context buffer and system prompt back to the current
+    /// session when either changed. Skipped while the backend has not caught
+    /// up with a session switch, so one session is never saved over another.
+    fn autosave(&mut self, settings: &ChatSettings) {
+        let state = self.translator.state();
+        let Some(name) = settings.session.as_ref() else {
+            return;
+        };
+        if state.session.as_ref() != Some(name) {
+            return;
+        }
+        if self
+            .saved
+            .as_ref()
+            .is_some_and(|saved| saved.matches(name, state.context_revision, settings))
+        {
+            return;
+        }
+        let session = session_of(&state, settings);
+        match self.sessions.save(name, &session) {
+            Ok(()) => {
+                self.saved = Some(SavedSession {
+                    name: name.clone(),
+                    context_revision: state.context_revision,
+                    system_prompt: session.system_prompt,
+                    prompt_preset: session.prompt_preset,
+                    game_title: session.game_title,
+                })
+            }
+            Err(err) => tracing::error!(%err, %name, "failed to save session"),
+        }
+    }
+
     pub fn ui(&mut self, ui: &Ui, settings: &mut Settings) {
+        self.autosave(&settings.chat);
         if !self.open {
             return;
         }
@@ -99,12 +397,86 @@ impl TranslatorWindow {
         let state = handle.state();
         let chatgpt = &mut settings.chat;
 
+        let mut totals = UsageTotals::default();
+        for ex in &state.exchanges {
+            if let Some(usage) = &ex.usage {
+                totals.add(&chatgpt.pricing, &ex.model, usage);
+            }
+        }
+
+        let mut session_action = None;
         ui.menu_bar(|| {
+            if let Some(_menu) = ui.begin_menu("Session") {
+                let names = self
+                    .session_names
+                    .get_or_insert_with(|| self.sessions.list());
+                session_action = draw_session_menu(
+                    ui,
+                    names,
+                    &mut self.session_name,
+                    chatgpt.session.as_deref(),
+                );
+            } else {
+                self.session_names = None;
+            }
             if ui.menu_item("Clear") {
                 handle.clear_context();
             }
+            ui.text_disabled(format!(
+                "Session: {} ({} requests)",
+                chatgpt.pricing.format(totals.cost.total()),
+                totals.requests
+            ));
+            if state.summarizing {
+                ui.text_disabled("(summarizing)");
+            }
+            if !state.speculation.is_empty() {
+                ui.text_disabled(format!("({} prefetched)", state.speculation.len()));
+            }
+            if let Some(response_id) = &state.response_id {
+                ui.text_disabled("(chained)");
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(format!(
+                        "Context is continued server-side from {}",
+                        response_id
+                    ));
+                }
+            }
         });
 
+        if let Some(action) = session_action {
+            apply_session_action(action, handle, &self.sessions, chatgpt, &state);
+            self.session_names = None;
+        }
+
+        if ui.collapsing_header("Prompt", TreeNodeFlags::empty()) {
+            draw_prompt(ui, chatgpt, &mut self.preset_name, &self.variables);
+        }
+
+        let mut alternate_action = None;
+        if ui.collapsing_header("Attempts", TreeNodeFlags::empty()) {
+            alternate_action = draw_alternates(
+                ui,
+                &state,
+                self.current,
+                &mut self.regenerate_model,
+                &mut self.regenerate_temperature,
+                &chatgpt.model,
+            );
+        }
+
+        let mut compare_action = None;
+        if ui.collapsing_header("Compare", TreeNodeFlags::empty()) {
+            compare_action = draw_compare(
+                ui,
+                &mut chatgpt.compare_lanes,
+                &state,
+                &self.comparison,
+                self.current.is_some(),
+                &chatgpt.pricing,
+            );
+        }
+
         if ui.collapsing_header("Tuning", TreeNodeFlags::DEFAULT_OPEN) {
             let align = 10.0;
             if let Some(_token) = ui.begin_table("##", 2) {
@@ -125,7 +497,45 @@ impl TranslatorWindow {
                 ui.same_line();
                 help_marker(
                     ui,
-                    "Target/threshold. For limiting context size while optimizing usage of prefix caches",
+                    "Target/threshold, counting the system prompt and tools. For limiting \
+                     context size while optimizing usage of prefix caches",
+                );
+                ui.table_next_column();
+                ui.checkbox("Summarize evicted turns", &mut chatgpt.summary.enabled);
+                ui.same_line();
+                help_marker(
+                    ui,
+                    "Fold turns evicted from the context into a pinned \"story so far\" \
+                     message instead of dropping them",
+                );
+                ui.table_next_column();
+                ui.disabled(!chatgpt.summary.enabled, || {
+                    checkbox_option_with_default(
+                        ui,
+                        &mut chatgpt.summary.model,
+                        chatgpt.model.clone(),
+                        |ui, model| {
+                            ui.set_next_item_width(ui.current_font_size() * -align);
+                            combo_list(ui, "Summary model", &state.models, model);
+                        },
+                    );
+                });
+                ui.table_next_column();
+                checkbox_option_with_default(
+                    ui,
+                    &mut chatgpt.prefetch_lines,
+                    3,
+                    |ui, prefetch_lines| {
+                        ui.set_next_item_width(ui.current_font_size() * -align);
+                        ui.input_scalar("Prefetch lines", prefetch_lines).build();
+                    },
+                );
+                ui.same_line();
+                help_marker(
+                    ui,
+                    "When reading from a script, translate this many upcoming lines \
+                     in the background. Prefetched lines count against the budget \
+                     even if they are never shown.",
                 );
                 ui.table_next_column();
                 checkbox_option(ui, &mut chatgpt.max_tokens, |ui, max_tokens| {
@@ -189,6 +599,22 @@ impl TranslatorWindow {
                 ui.same_line();
                 help_marker(ui, "Effort for reasoning models (GPT-5 and o3 models)");
                 ui.table_next_column();
+                checkbox_option_with_default(
+                    ui,
+                    &mut chatgpt.reasoning_summary,
+                    openai::responses::ReasoningSummary::Auto,
+                    |ui, reasoning_summary| {
+                        ui.set_next_item_width(ui.current_font_size() * -align);
+                        combo_enum(ui, "Reasoning summary", reasoning_summary);
+                    },
+                );
+                ui.same_line();
+                help_marker(
+                    ui,
+                    "Ask the Responses API to summarize the model's reasoning. \
+                     Reasoning from other servers is shown whenever they send it.",
+                );
+                ui.table_next_column();
                 checkbox_option_with_default(
                     ui,
                     &mut chatgpt.verbosity,
@@ -201,6 +627,51 @@ impl TranslatorWindow {
             }
         }
 
+        if ui.collapsing_header("Memory", TreeNodeFlags::empty()) {
+            let current = self.current.and_then(|id| state.exchange(id));
+            if let Some(ex) = current {
+                if self.suggestions.as_ref().map(|(id, _)| *id) != Some(ex.id) {
+                    let text = ex.user_message.content.as_deref().unwrap_or_default();
+                    let suggestions = state.memory.suggest(
+                        text,
+                        &chatgpt.target_language,
+                        chatgpt.memory.suggestion_threshold,
+                        8,
+                    );
+                    self.suggestions = Some((ex.id, suggestions));
+                }
+            }
+            draw_memory(
+                ui,
+                &mut chatgpt.memory,
+                state.memory.len(),
+                current.zip(self.suggestions.as_ref().map(|(_, s)| s.as_slice())),
+            );
+        }
+
+        if ui.collapsing_header("Glossary", TreeNodeFlags::empty()) {
+            let detected = self
+                .current
+                .and_then(|id| state.exchange(id))
+                .map(|ex| ex.glossary.as_slice())
+                .unwrap_or_default();
+            draw_glossary(
+                ui,
+                &mut self.glossary,
+                &mut chatgpt.glossary_path,
+                &chatgpt.target_language,
+                detected,
+            );
+        }
+
+        if ui.collapsing_header("Usage", TreeNodeFlags::empty()) {
+            draw_totals(ui, &totals, &chatgpt.pricing);
+            ui.separator();
+            draw_budget(ui, &mut chatgpt.budget, &chatgpt.pricing, &state);
+            ui.separator();
+            draw_pricing(ui, &mut chatgpt.pricing);
+        }
+
         ui.child_window("context_window").build(|| {
             if let Some(_t) = ui.begin_table_header_with_flags(
                 "context",
@@ -231,7 +702,7 @@ impl TranslatorWindow {
                 ui.table_next_column();
                 ui.input_text_multiline(
                     "##",
-                    &mut chatgpt.system_prompt,
+                    chatgpt.prompt_template_mut(),
                     [ui.content_region_avail()[0], 200.0],
                 )
                 .build();
@@ -339,6 +810,483 @@ impl TranslatorWindow {
                 ui.text_disabled("drag and drop by handle to reorder");
             }
         });
+
+        match compare_action {
+            Some(CompareAction::Run) => {
+                let text = self
+                    .current
+                    .and_then(|id| state.exchange(id))
+                    .and_then(|ex| ex.user_message.content.clone());
+                if let Some(text) = text {
+                    self.compare(settings, text, None);
+                }
+            }
+            Some(CompareAction::Pick(id)) => self.pick(settings, id),
+            None => {}
+        }
+        match alternate_action {
+            Some(AlternateAction::Regenerate) => self.regenerate(settings, None),
+            Some(AlternateAction::Show(id)) => self.current = Some(id),
+            Some(AlternateAction::Choose(id)) => self.choose(settings, id),
+            None => {}
+        }
+    }
+}
+
+/// Render the prompt preset selector, the settings-level template variables
+/// and a preview of the prompt as rendered for the last line.
+fn draw_prompt(ui: &Ui, settings: &mut ChatSettings, name: &mut String, variables: &Variables) {
+    const NO_PRESET: &str = "(session prompt)";
+    let width = ui.current_font_size() * 16.0;
+    ui.set_next_item_width(width);
+    let selected = settings.prompt_preset.as_deref().unwrap_or(NO_PRESET);
+    if let Some(_combo) = ui.begin_combo("Preset", selected) {
+        if ui
+            .selectable_config(NO_PRESET)
+            .selected(settings.prompt_preset.is_none())
+            .build()
+        {
+            settings.prompt_preset = None;
+        }
+        for preset in &settings.prompt_presets {
+            let selected = settings.prompt_preset.as_ref() == Some(&preset.name);
+            if ui
+                .selectable_config(&preset.name)
+                .selected(selected)
+                .build()
+            {
+                settings.prompt_preset = Some(preset.name.clone());
+            }
+        }
+    }
+    ui.same_line();
+    let names: Vec<&str> = Variable::iter().map(<&str>::from).collect();
+    help_marker(
+        ui,
+        &format!(
+            "The system prompt is a template, edited in the context table below. \
+             {{{{name}}}} inserts a variable and {{{{#if name}}}}...{{{{else}}}}...{{{{/if}}}} \
+             renders a branch depending on whether it is empty.\n\n\
+             Variables: {}\n\n\
+             Variables that change per line also change the system prompt, \
+             which defeats prompt caching.",
+            names.join(", ")
+        ),
+    );
+    if let Some(preset) = settings.prompt_preset.clone() {
+        ui.same_line();
+        if ui.button("Delete preset") {
+            settings.prompt_presets.retain(|x| x.name != preset);
+            settings.prompt_preset = None;
+        }
+    }
+    ui.set_next_item_width(width);
+    ui.input_text("##preset_name", name)
+        .hint("Preset name")
+        .build();
+    ui.same_line();
+    if ui.button("Save as preset") && !name.trim().is_empty() {
+        let preset = PromptPreset {
+            name: name.trim().to_string(),
+            template: settings.prompt_template().to_string(),
+        };
+        settings.prompt_presets.retain(|x| x.name != preset.name);
+        settings.prompt_preset = Some(preset.name.clone());
+        settings.prompt_presets.push(preset);
+        name.clear();
+    }
+    ui.set_next_item_width(width);
+    ui.input_text("Game title", &mut settings.game_title)
+        .build();
+    ui.set_next_item_width(width);
+    ui.input_text("Target language", &mut settings.target_language)
+        .build();
+    ui.same_line();
+    help_marker(
+        ui,
+        "Language translations are written in, e.g. English, Simplified \
+         Chinese, Korean or Spanish. Filled into {{target_language}}, selects \
+         the glossary terms that apply and keeps translation memory separate \
+         per language.",
+    );
+
+    ui.separator();
+    ui.text_disabled("Preview for the last line:");
+    match Template::parse(settings.prompt_template()) {
+        Ok(template) => {
+            let variables = Variables {
+                game_title: settings.game_title.clone(),
+                target_language: settings.target_language.clone(),
+                ..variables.clone()
+            };
+            ui.text_wrapped(template.render(&variables));
+        }
+        Err(err) => ui.text_colored(
+            ui.style_color(StyleColor::PlotLinesHovered),
+            format!("Invalid template: {}", err),
+        ),
+    }
+}
+
+enum AlternateAction {
+    /// Regenerate the current line.
+    Regenerate,
+    /// Make an earlier attempt at the line the current exchange.
+    Show(ExchangeId),
+    Choose(ExchangeId),
+}
+
+/// Render the attempts at the current line, one at a time, and the
+/// parameters to regenerate it with.
+fn draw_alternates(
+    ui: &Ui,
+    state: &ChatState,
+    current: Option<ExchangeId>,
+    model: &mut Option<ModelId>,
+    temperature: &mut Option<f32>,
+    default_model: &ModelId,
+) -> Option<AlternateAction> {
+    let mut action = None;
+    let width = ui.current_font_size() * 12.0;
+    checkbox_option_with_default(ui, model, default_model.clone(), |ui, model| {
+        ui.set_next_item_width(width);
+        combo_list(ui, "Model", &state.models, model);
+    });
+    checkbox_option_with_default(ui, temperature, 1.0, |ui, temperature| {
+        ui.set_next_item_width(width);
+        ui.slider_config("Temperature", 0.0f32, 2.0f32)
+            .display_format("%.2f")
+            .flags(SliderFlags::ALWAYS_CLAMP)
+            .build(temperature);
+    });
+    let regenerable = current.is_some_and(|id| {
+        state
+            .exchange(id)
+            .is_some_and(|ex| ex.response.is_terminal())
+            && state.regenerate_context(id).is_some()
+    });
+    ui.disabled(!regenerable, || {
+        if ui.button("Regenerate") {
+            action = Some(AlternateAction::Regenerate);
+        }
+    });
+    ui.same_line();
+    help_marker(
+        ui,
+        "Translate the current line again after the same context. Earlier \
+         attempts are kept; the latest replaces the line in the context until \
+         another is chosen. Unchecked parameters follow the settings.",
+    );
+
+    let Some(ex) = current.and_then(|id| state.exchange(id)) else {
+        return action;
+    };
+    let alternates = state.alternates(ex.id);
+    let Some(idx) = alternates.iter().position(|x| x.id == ex.id) else {
+        return action;
+    };
+    ui.separator();
+    ui.disabled(idx == 0, || {
+        if ui.arrow_button("##prev", Direction::Left) {
+            action = Some(AlternateAction::Show(alternates[idx - 1].id));
+        }
+    });
+    ui.same_line();
+    ui.disabled(idx + 1 == alternates.len(), || {
+        if ui.arrow_button("##next", Direction::Right) {
+            action = Some(AlternateAction::Show(alternates[idx + 1].id));
+        }
+    });
+    ui.same_line();
+    ui.text(format!(
+        "Attempt {} of {} ({})",
+        idx + 1,
+        alternates.len(),
+        ex.model.as_ref()
+    ));
+    ui.same_line();
+    if !ex.committed.is_empty() {
+        ui.text_disabled("(in context)");
+    } else {
+        let completed = matches!(ex.response, Response::Completed { .. });
+        ui.disabled(!completed, || {
+            if ui.button("Use this") {
+                action = Some(AlternateAction::Choose(ex.id));
+            }
+        });
+    }
+    let _wrap_token = ui.push_text_wrap_pos_with_pos(0.0);
+    match &ex.response {
+        Response::Streaming { content, .. } => {
+            ui.text(format!("{}{}", content, ellipses(ui)));
+        }
+        Response::Completed { content, .. } => ui.text(content),
+        Response::Errored(err) => ui.text_disabled(format!("(error: {})", err)),
+        Response::Cancelled => ui.text_disabled("(cancelled)"),
+        Response::Blocked(reason) => ui.text_disabled(format!("(blocked: {})", reason)),
+    }
+    action
+}
+
+enum CompareAction {
+    /// Compare the current exchange's line.
+    Run,
+    Pick(ExchangeId),
+}
+
+/// Render the comparison lanes editor and the candidates of the last
+/// comparison side by side.
+fn draw_compare(
+    ui: &Ui,
+    lanes: &mut Vec<CompareLane>,
+    state: &ChatState,
+    comparison: &[ExchangeId],
+    has_current: bool,
+    pricing: &Pricing,
+) -> Option<CompareAction> {
+    let mut action = None;
+    if let Some(_t) = ui.begin_table_header_with_flags(
+        "lanes",
+        [
+            TableColumnSetup::new(""),
+            TableColumnSetup::new("Model"),
+            TableColumnSetup::new("Backend"),
+            TableColumnSetup::new("Endpoint"),
+        ],
+        TableFlags::SIZING_STRETCH_PROP,
+    ) {
+        let mut delete = None;
+        for (idx, lane) in lanes.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(idx);
+            ui.table_next_column();
+            if ui.button_with_size("\u{00d7}", [ui.frame_height(), 0.0]) {
+                delete = Some(idx);
+            }
+            ui.table_next_column();
+            ui.set_next_item_width(ui.content_region_avail()[0]);
+            ui.input_text("##model", &mut lane.model.0).build();
+            ui.table_next_column();
+            ui.set_next_item_width(ui.content_region_avail()[0]);
+            combo_enum(ui, "##backend", &mut lane.translator_type);
+            ui.table_next_column();
+            ui.set_next_item_width(ui.content_region_avail()[0]);
+            ui.input_text("##endpoint", &mut lane.api_endpoint)
+                .hint("default")
+                .build();
+        }
+        if let Some(idx) = delete {
+            lanes.remove(idx);
+        }
+        ui.table_next_column();
+        if ui.button_with_size("+", [ui.frame_height(), 0.0]) {
+            lanes.push(CompareLane {
+                model: state.models.first().cloned().unwrap_or_default(),
+                ..Default::default()
+            });
+        }
+    }
+    ui.disabled(lanes.is_empty() || !has_current, || {
+        if ui.button("Compare current line") {
+            action = Some(CompareAction::Run);
+        }
+    });
+    ui.same_line();
+    help_marker(
+        ui,
+        "Send a line to every lane at once. Each lane sees the same context; \
+         only the picked translation is added to it.",
+    );
+
+    let candidates: Vec<&ExchangeView> = comparison
+        .iter()
+        .filter_map(|id| state.exchange(*id))
+        .collect();
+    if candidates.is_empty() {
+        return action;
+    }
+    let picked = candidates.iter().any(|ex| !ex.committed.is_empty());
+    let Some(_t) = ui.begin_table_with_flags(
+        "candidates",
+        candidates.len(),
+        TableFlags::SIZING_STRETCH_SAME | TableFlags::BORDERS_INNER_V,
+    ) else {
+        return action;
+    };
+    for (idx, ex) in candidates.iter().enumerate() {
+        ui.table_setup_column(format!("{}##{}", ex.model.as_ref(), idx));
+    }
+    ui.table_headers_row();
+    for (idx, ex) in candidates.iter().enumerate() {
+        let _id = ui.push_id_usize(idx);
+        ui.table_next_column();
+        match &ex.response {
+            Response::Streaming { content, .. } => {
+                ui.text_wrapped(format!("{}{}", content, ellipses(ui)));
+            }
+            Response::Completed { content, .. } => ui.text_wrapped(content),
+            Response::Errored(err) => ui.text_disabled(format!("(error: {})", err)),
+            Response::Cancelled => ui.text_disabled("(cancelled)"),
+            Response::Blocked(reason) => ui.text_disabled(format!("(blocked: {})", reason)),
+        }
+        draw_reasoning(ui, ex);
+        let elapsed = |t: Option<Instant>| {
+            t.map(|t| format!("{:.2}s", t.duration_since(ex.started).as_secs_f32()))
+                .unwrap_or_else(|| "-".into())
+        };
+        ui.text_disabled(format!(
+            "First token {}, total {}",
+            elapsed(ex.first_token),
+            elapsed(ex.finished)
+        ));
+        if let Some(usage) = &ex.usage {
+            let cost = pricing
+                .cost(&ex.model, usage)
+                .map(|cost| format!(" ~ {}", pricing.format(cost.total())))
+                .unwrap_or_default();
+            ui.text_disabled(format!(
+                "{} input + {} output tokens{}",
+                usage.prompt_tokens, usage.completion_tokens, cost
+            ));
+        }
+        if !ex.committed.is_empty() {
+            ui.text_disabled("(picked)");
+        } else {
+            let completed = matches!(ex.response, Response::Completed { .. });
+            ui.disabled(picked || !completed, || {
+                if ui.button("Pick") {
+                    action = Some(CompareAction::Pick(ex.id));
+                }
+            });
+        }
+    }
+    action
+}
+
+enum SessionAction {
+    Switch(String),
+    SaveAs(String),
+    Detach,
+    Delete(String),
+}
+
+fn session_of(state: &ChatState, settings: &ChatSettings) -> Session {
+    Session {
+        system_prompt: settings.system_prompt.clone(),
+        prompt_preset: settings.prompt_preset.clone(),
+        game_title: settings.game_title.clone(),
+        context: state.context.iter().map(|e| e.message.clone()).collect(),
+    }
+}
+
+/// The session autosave last wrote, less its context.
+struct SavedSession {
+    name: String,
+    context_revision: u64,
+    system_prompt: String,
+    prompt_preset: Option<String>,
+    game_title: String,
+}
+
+impl SavedSession {
+    fn matches(&self, name: &str, context_revision: u64, settings: &ChatSettings) -> bool {
+        self.name == name
+            && self.context_revision == context_revision
+            && self.system_prompt == settings.system_prompt
+            && self.prompt_preset == settings.prompt_preset
+            && self.game_title == settings.game_title
+    }
+}
+
+/// Render the contents of the Session menu.
+fn draw_session_menu(
+    ui: &Ui,
+    names: &[String],
+    name: &mut String,
+    current: Option<&str>,
+) -> Option<SessionAction> {
+    let mut action = None;
+    for session in names {
+        if ui
+            .menu_item_config(session)
+            .selected(current == Some(session.as_str()))
+            .build()
+            && current != Some(session.as_str())
+        {
+            action = Some(SessionAction::Switch(session.clone()));
+        }
+    }
+    if names.is_empty() {
+        ui.text_disabled("No saved sessions");
+    }
+    ui.separator();
+    ui.set_next_item_width(ui.current_font_size() * 10.0);
+    ui.input_text("##session_name", name)
+        .hint("Session name")
+        .build();
+    ui.same_line();
+    if ui.button("Save as") && !name.trim().is_empty() {
+        action = Some(SessionAction::SaveAs(name.trim().to_string()));
+        name.clear();
+    }
+    if ui
+        .menu_item_config("Detach")
+        .enabled(current.is_some())
+        .build()
+    {
+        action = Some(SessionAction::Detach);
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("Keep the context buffer but stop saving it to this session");
+    }
+    if let Some(current) = current {
+        if ui.menu_item(format!("Delete \"{}\"", current)) {
+            action = Some(SessionAction::Delete(current.to_string()));
+        }
+    }
+    action
+}
+
+/// Restore the prompt settings saved with `session`, returning its context.
+fn restore_session(settings: &mut ChatSettings, session: Session) -> Vec<Message> {
+    settings.system_prompt = session.system_prompt;
+    settings.prompt_preset = session.prompt_preset;
+    settings.game_title = session.game_title;
+    session.context
+}
+
+fn apply_session_action(
+    action: SessionAction,
+    handle: &ChatHandle,
+    sessions: &SessionStore,
+    settings: &mut ChatSettings,
+    state: &ChatState,
+) {
+    match action {
+        SessionAction::Switch(name) => match sessions.load(&name) {
+            Ok(session) => {
+                let context = restore_session(settings, session);
+                handle.load_session(Some(name.clone()), context);
+                settings.session = Some(name);
+            }
+            Err(err) => tracing::error!(%err, %name, "failed to load session"),
+        },
+        SessionAction::SaveAs(name) => {
+            let session = session_of(state, settings);
+            match sessions.save(&name, &session) {
+                Ok(()) => {
+                    handle.load_session(Some(name.clone()), session.context);
+                    settings.session = Some(name);
+                }
+                Err(err) => tracing::error!(%err, %name, "failed to save session"),
+            }
+        }
+        SessionAction::Detach => settings.session = None,
+        SessionAction::Delete(name) => {
+            if let Err(err) = sessions.delete(&name) {
+                tracing::error!(%err, %name, "failed to delete session");
+            }
+            settings.session = None;
+        }
     }
 }
 
@@ -367,6 +1315,16 @@ fn draw_exchange(ui: &Ui, ex: &ExchangeView) {
         );
     }
     match &ex.response {
+        Response::Streaming { .. } if ex.started > Instant::now() => {
+            ui.same_line();
+            stroke_text_with_highlight(
+                ui,
+                &draw_list,
+                &format!("(queued for the rate limit{})", ellipses(ui)),
+                1.0,
+                Some(StyleColor::TextSelectedBg),
+            );
+        }
         Response::Streaming { .. } => {
             if content.is_empty() {
                 ui.same_line();
@@ -401,18 +1359,256 @@ fn draw_exchange(ui: &Ui, ex: &ExchangeView) {
                 Some(StyleColor::PlotLinesHovered),
             );
         }
-        Response::Completed { .. } => {}
+        Response::Blocked(reason) => {
+            ui.same_line();
+            stroke_text_with_highlight(
+                ui,
+                &draw_list,
+                &format!("(blocked: {})", reason),
+                1.0,
+                Some(StyleColor::PlotLinesHovered),
+            );
+        }
+        Response::Completed { .. } => {
+            if !ex.ignored_terms.is_empty() {
+                let terms: Vec<String> = ex
+                    .ignored_terms
+                    .iter()
+                    .map(|term| format!("{} → {}", term.term, term.translation))
+                    .collect();
+                ui.same_line();
+                stroke_text_with_highlight(
+                    ui,
+                    &draw_list,
+                    &format!("(glossary: {})", terms.join(", ")),
+                    1.0,
+                    Some(StyleColor::PlotLinesHovered),
+                );
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("The translation does not use these glossary terms");
+                }
+            }
+            if ex.prefetched {
+                ui.same_line();
+                stroke_text_with_highlight(
+                    ui,
+                    &draw_list,
+                    "(prefetched)",
+                    1.0,
+                    Some(StyleColor::NavHighlight),
+                );
+            }
+            if ex.recalled {
+                ui.same_line();
+                stroke_text_with_highlight(
+                    ui,
+                    &draw_list,
+                    "(memory)",
+                    1.0,
+                    Some(StyleColor::NavHighlight),
+                );
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Served from translation memory. Regenerate to bypass it.");
+                }
+            }
+        }
+    }
+    draw_reasoning(ui, ex);
+}
+
+/// Render the exchange's reasoning as a collapsed tree node, if the model
+/// sent any.
+fn draw_reasoning(ui: &Ui, ex: &ExchangeView) {
+    if ex.reasoning.is_empty() {
+        return;
+    }
+    let tokens = ex
+        .usage
+        .as_ref()
+        .and_then(|usage| usage.completion_tokens_details.as_ref())
+        .map(|details| details.reasoning_tokens)
+        .filter(|&tokens| tokens > 0);
+    let label = if !ex.response.is_terminal() && ex.response.content().is_empty() {
+        format!("Reasoning{}", ellipses(ui))
+    } else if let Some(tokens) = tokens {
+        format!("Reasoning ({} tokens)", tokens)
+    } else {
+        "Reasoning".to_string()
+    };
+    let _id = ui.push_id_usize(ex.id.0 as usize);
+    ui.tree_node_config(&format!("{}###reasoning", label))
+        .build(|| {
+            let _wrap_token = ui.push_text_wrap_pos_with_pos(0.0);
+            ui.text_disabled(&ex.reasoning);
+        });
+}
+
+/// Render translation memory settings and fuzzy matches for the current
+/// exchange, excluding the entry it was served from or recorded as.
+fn draw_memory(
+    ui: &Ui,
+    settings: &mut MemorySettings,
+    len: usize,
+    current: Option<(&ExchangeView, &[Suggestion])>,
+) {
+    let width = ui.current_font_size() * 8.0;
+    ui.checkbox("Translation memory", &mut settings.enabled);
+    ui.same_line();
+    help_marker(
+        ui,
+        "Serve previously translated lines instantly. Lines match when the \
+         normalized text, model, target language, system prompt, glossary \
+         terms and preceding lines agree.",
+    );
+    ui.same_line();
+    ui.text_disabled(format!("({} entries)", len));
+    ui.set_next_item_width(width);
+    ui.input_scalar("Context lines", &mut settings.context_lines)
+        .build();
+    ui.set_next_item_width(width);
+    ui.slider_config("Suggestion threshold", 0.0f32, 1.0f32)
+        .display_format("%.2f")
+        .flags(SliderFlags::ALWAYS_CLAMP)
+        .build(&mut settings.suggestion_threshold);
+
+    let Some((ex, suggestions)) = current else {
+        return;
+    };
+    let text = memory::normalize(ex.user_message.content.as_deref().unwrap_or_default());
+    let suggestions: Vec<&Suggestion> = suggestions
+        .iter()
+        .filter(|s| !(s.entry.key.text == text && s.entry.translation == ex.response.content()))
+        .collect();
+    if suggestions.is_empty() {
+        ui.text_disabled("No similar lines in memory");
+        return;
+    }
+    let Some(_t) = ui.begin_table_header_with_flags(
+        "suggestions",
+        [
+            TableColumnSetup::new("Match"),
+            TableColumnSetup::new("Source"),
+            TableColumnSetup::new("Translation"),
+        ],
+        TableFlags::SIZING_STRETCH_PROP,
+    ) else {
+        return;
+    };
+    for suggestion in suggestions {
+        ui.table_next_column();
+        ui.text(format!("{:.0}%", suggestion.score * 100.0));
+        ui.table_next_column();
+        ui.text_wrapped(&suggestion.entry.source);
+        ui.table_next_column();
+        ui.text_wrapped(&suggestion.entry.translation);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(&suggestion.entry.key.model);
+        }
+    }
+}
+
+/// Render the glossary editor. The glossary is reloaded when the path changes
+/// and written back after every edit. Terms detected in the current exchange
+/// are highlighted, and terms for other target languages dimmed.
+fn draw_glossary(
+    ui: &Ui,
+    glossary: &mut Glossary,
+    path: &mut String,
+    target_language: &str,
+    detected: &[GlossaryTerm],
+) {
+    ui.set_next_item_width(ui.current_font_size() * 16.0);
+    ui.input_text("File", path).build();
+    if ui.is_item_deactivated_after_edit() {
+        *glossary = Glossary::from_file(&*path);
+    }
+    ui.same_line();
+    help_marker(
+        ui,
+        "Preferred translations for names and recurring terms. Terms found in \
+         a line are sent with it, and translations that ignore them are flagged. \
+         Separate alternative translations with |. Terms with a language are \
+         only used when translating into it.",
+    );
+    ui.same_line();
+    ui.text_disabled(format!("({} terms)", glossary.terms.len()));
+    let Some(_t) = ui.begin_table_header_with_flags(
+        "glossary",
+        [
+            TableColumnSetup::new(""),
+            TableColumnSetup::new("Term"),
+            TableColumnSetup::new("Reading"),
+            TableColumnSetup::new("Translation"),
+            TableColumnSetup::new("Language"),
+            TableColumnSetup::new("Notes"),
+        ],
+        TableFlags::SIZING_STRETCH_PROP,
+    ) else {
+        return;
+    };
+    let mut edited = false;
+    let mut delete = None;
+    for (idx, term) in glossary.terms.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(idx);
+        ui.table_next_column();
+        if ui.button_with_size("\u{00d7}", [ui.frame_height(), 0.0]) {
+            delete = Some(idx);
+        }
+        if detected.contains(term) {
+            ui.table_set_bg_color(
+                TableBgTarget::ROW_BG0,
+                ui.style_color(StyleColor::TextSelectedBg),
+            );
+        }
+        let _dim = (!term.applies_to(target_language)).then(|| {
+            ui.push_style_color(StyleColor::Text, ui.style_color(StyleColor::TextDisabled))
+        });
+        for (label, value) in [
+            ("##term", &mut term.term),
+            ("##reading", &mut term.reading),
+            ("##translation", &mut term.translation),
+            ("##language", &mut term.language),
+            ("##notes", &mut term.notes),
+        ] {
+            ui.table_next_column();
+            ui.set_next_item_width(ui.content_region_avail()[0]);
+            ui.input_text(label, value).build();
+            edited |= ui.is_item_deactivated_after_edit();
+        }
+    }
+    if let Some(idx) = delete {
+        glossary.terms.remove(idx);
+        edited = true;
+    }
+    ui.table_next_column();
+    if ui.button_with_size("+", [ui.frame_height(), 0.0]) {
+        glossary.terms.push(GlossaryTerm::default());
+        edited = true;
+    }
+    if edited {
+        if let Err(err) = glossary.write_to_file(&*path) {
+            tracing::error!(%err, "failed to write glossary");
+        }
     }
 }
 
 /// Render the usage progress bar for one exchange.
-fn draw_usage(ui: &Ui, model: &ModelId, usage: &Usage) {
+fn draw_usage(ui: &Ui, model: &ModelId, usage: &Usage, pricing: &Pricing) {
+    let cost = pricing
+        .cost(model, usage)
+        .map(|cost| format!(" ~ {}", pricing.format(cost.total())))
+        .unwrap_or_default();
     ui.same_line();
     ProgressBar::new(0.0)
         .overlay_text(format!(
-            "{}: {} input + {} output ({} reasoning) = {}",
+            "{}: {} input ({} cached) + {} output ({} reasoning) = {}{}",
             model.as_ref(),
             usage.prompt_tokens,
+            usage
+                .prompt_tokens_details
+                .as_ref()
+                .map(|x| x.cached_tokens)
+                .unwrap_or_default(),
             usage.completion_tokens,
             usage
                 .completion_tokens_details
@@ -420,7 +1616,146 @@ fn draw_usage(ui: &Ui, model: &ModelId, usage: &Usage) {
                 .map(|x| x.reasoning_tokens)
                 .unwrap_or_default(),
             usage.total_tokens,
+            cost,
         ))
-        .size([500.0, 0.0])
+        .size([600.0, 0.0])
         .build(ui);
 }
+
+/// Render session token and cost totals.
+fn draw_totals(ui: &Ui, totals: &UsageTotals, pricing: &Pricing) {
+    ui.text(format!(
+        "{} requests: {} input ({} cached) + {} output ({} reasoning) tokens",
+        totals.requests,
+        totals.prompt_tokens,
+        totals.cached_tokens,
+        totals.completion_tokens,
+        totals.reasoning_tokens,
+    ));
+    let cost = &totals.cost;
+    ui.text(format!(
+        "Cost: {} input + {} cached + {} output ({} reasoning) = {}",
+        pricing.format(cost.input),
+        pricing.format(cost.cached_input),
+        pricing.format(cost.output),
+        pricing.format(cost.reasoning),
+        pricing.format(cost.total()),
+    ));
+    if totals.unpriced > 0 {
+        ui.same_line();
+        help_marker(
+            ui,
+            &format!(
+                "{} requests used models without a price and are not included",
+                totals.unpriced
+            ),
+        );
+    }
+}
+
+/// Render budget limits alongside what has been spent against them.
+fn draw_budget(ui: &Ui, budget: &mut Budget, pricing: &Pricing, state: &ChatState) {
+    let now = std::time::Instant::now();
+    let width = ui.current_font_size() * 8.0;
+    checkbox_option_with_default(ui, &mut budget.requests_per_minute, 10, |ui, limit| {
+        ui.set_next_item_width(width);
+        ui.input_scalar("Requests per minute", limit).build();
+    });
+    ui.same_line();
+    help_marker(
+        ui,
+        "Translations beyond the rate limit are queued, and translations beyond \
+         the budgets are blocked instead of sent. Spend is counted once a \
+         request completes. Hourly and daily spend carries over restarts.",
+    );
+    for (window, tokens, cost) in [
+        (
+            Window::Hour,
+            &mut budget.tokens_per_hour,
+            &mut budget.cost_per_hour,
+        ),
+        (
+            Window::Day,
+            &mut budget.tokens_per_day,
+            &mut budget.cost_per_day,
+        ),
+        (
+            Window::Session,
+            &mut budget.tokens_per_session,
+            &mut budget.cost_per_session,
+        ),
+    ] {
+        let spent = budget::spent(&state.exchanges, &state.usage_log, pricing, now, window);
+        let _id = ui.push_id(window.to_string());
+        checkbox_option_with_default(ui, tokens, 100_000, |ui, limit| {
+            ui.set_next_item_width(width);
+            ui.input_scalar(format!("Tokens ({})", window), limit)
+                .build();
+        });
+        ui.same_line();
+        ui.text_disabled(format!(
+            "{} spent",
+            spent.prompt_tokens + spent.completion_tokens
+        ));
+        checkbox_option_with_default(ui, cost, 1.0, |ui, limit| {
+            ui.set_next_item_width(width);
+            ui.input_scalar(format!("Cost ({})", window), limit)
+                .display_format("%.2f")
+                .build();
+        });
+        ui.same_line();
+        ui.text_disabled(format!("{} spent", pricing.format(spent.cost.total())));
+    }
+}
+
+/// Render the editable pricing table.
+fn draw_pricing(ui: &Ui, pricing: &mut Pricing) {
+    ui.set_next_item_width(ui.current_font_size() * 4.0);
+    ui.input_text("Currency", &mut pricing.currency).build();
+    ui.same_line();
+    help_marker(
+        ui,
+        "Prices per million tokens, matched by longest model prefix. \
+         Reasoning tokens are billed as output.",
+    );
+    let Some(_t) = ui.begin_table_header_with_flags(
+        "pricing",
+        [
+            TableColumnSetup::new(""),
+            TableColumnSetup::new("Model prefix"),
+            TableColumnSetup::new("Input"),
+            TableColumnSetup::new("Cached input"),
+            TableColumnSetup::new("Output"),
+        ],
+        TableFlags::SIZING_STRETCH_PROP,
+    ) else {
+        return;
+    };
+    let mut delete = None;
+    for (idx, price) in pricing.models.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(idx);
+        ui.table_next_column();
+        if ui.button_with_size("\u{00d7}", [ui.frame_height(), 0.0]) {
+            delete = Some(idx);
+        }
+        ui.table_next_column();
+        ui.set_next_item_width(ui.content_region_avail()[0]);
+        ui.input_text("##model", &mut price.model).build();
+        for (label, value) in [
+            ("##input", &mut price.input),
+            ("##cached_input", &mut price.cached_input),
+            ("##output", &mut price.output),
+        ] {
+            ui.table_next_column();
+            ui.set_next_item_width(ui.content_region_avail()[0]);
+            ui.input_scalar(label, value).display_format("%.3f").build();
+        }
+    }
+    if let Some(idx) = delete {
+        pricing.models.remove(idx);
+    }
+    ui.table_next_column();
+    if ui.button_with_size("+", [ui.frame_height(), 0.0]) {
+        pricing.models.push(ModelPrice::default());
+    }
+}
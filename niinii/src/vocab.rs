@@ -0,0 +1,481 @@
+//! Vocabulary lists: word frequency ranks and JLPT levels.
+//!
+//! Lists are local files, looked up by JMdict sequence number where the list
+//! has one, then by text and reading, then by text alone. Tab-separated lists
+//! hold one word per line, either on its own (ranked by line) or followed by
+//! its reading and rank or level; a first column of digits is read as a
+//! sequence number. JSON frequency lists are Yomichan term meta banks or
+//! objects mapping words to ranks; JSON JLPT lists are arrays of objects.
+//!
+//! Frequency values are read as ranks, 1 for the most common word. Some
+//! Yomichan banks store occurrence counts instead, which
+//! [`VocabSettings::frequency_counts`] turns into ranks.
+
+use std::{collections::HashMap, fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum_macros::{EnumIter, IntoStaticStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("unrecognized list format")]
+    Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter)]
+pub enum WordColoring {
+    None,
+    #[strum(serialize = "JLPT level")]
+    Jlpt,
+    #[strum(serialize = "Frequency")]
+    Frequency,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct VocabSettings {
+    /// Frequency list. Empty to go without.
+    pub frequency_path: String,
+    /// The frequency list gives how often each word occurs rather than its
+    /// rank.
+    pub frequency_counts: bool,
+    /// JLPT vocabulary list. Empty to go without.
+    pub jlpt_path: String,
+    /// What highlighted words are coloured by.
+    pub coloring: WordColoring,
+}
+
+impl Default for VocabSettings {
+    fn default() -> Self {
+        Self {
+            frequency_path: String::new(),
+            frequency_counts: false,
+            jlpt_path: String::new(),
+            coloring: WordColoring::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, IntoStaticStr)]
+pub enum Jlpt {
+    N5,
+    N4,
+    N3,
+    N2,
+    N1,
+}
+
+impl Jlpt {
+    /// Levels written as `N3`, `3`, `jlpt-n3` and so on.
+    pub fn parse(level: &str) -> Option<Self> {
+        let digit = level.trim().chars().rev().find(char::is_ascii_digit)?;
+        match digit {
+            '5' => Some(Jlpt::N5),
+            '4' => Some(Jlpt::N4),
+            '3' => Some(Jlpt::N3),
+            '2' => Some(Jlpt::N2),
+            '1' => Some(Jlpt::N1),
+            _ => None,
+        }
+    }
+}
+
+/// What the lists say about a word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordInfo {
+    /// 1 for the most common word.
+    pub rank: Option<u32>,
+    pub jlpt: Option<Jlpt>,
+}
+
+impl WordInfo {
+    pub fn is_empty(&self) -> bool {
+        self.rank.is_none() && self.jlpt.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Key {
+    Seq(u32),
+    Word(String, String),
+    Text(String),
+}
+
+impl Key {
+    fn new(text: &str, reading: Option<&str>) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        if text.bytes().all(|b| b.is_ascii_digit()) {
+            return text.parse().ok().map(Key::Seq);
+        }
+        Some(match reading.map(str::trim).filter(|r| !r.is_empty()) {
+            Some(reading) => Key::Word(text.into(), reading.into()),
+            None => Key::Text(text.into()),
+        })
+    }
+}
+
+/// Values by key. The first value seen for a key wins, so lists that repeat
+/// a word keep its best rank.
+struct Index<T> {
+    seq: HashMap<u32, T>,
+    /// By text, then reading, so lookups need not allocate.
+    word: HashMap<String, HashMap<String, T>>,
+    text: HashMap<String, T>,
+}
+
+impl<T> Default for Index<T> {
+    fn default() -> Self {
+        Self {
+            seq: HashMap::new(),
+            word: HashMap::new(),
+            text: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Copy> Index<T> {
+    fn insert(&mut self, key: Key, value: T) {
+        match key {
+            Key::Seq(seq) => self.seq.entry(seq).or_insert(value),
+            Key::Word(text, reading) => {
+                // Also findable by text, for words read another way.
+                self.text.entry(text.clone()).or_insert(value);
+                self.word
+                    .entry(text)
+                    .or_default()
+                    .entry(reading)
+                    .or_insert(value)
+            }
+            Key::Text(text) => self.text.entry(text).or_insert(value),
+        };
+    }
+
+    fn get(&self, seq: Option<u32>, text: &str, kana: &str) -> Option<T> {
+        seq.and_then(|seq| self.seq.get(&seq))
+            .or_else(|| self.word.get(text)?.get(kana))
+            .or_else(|| self.text.get(text))
+            .copied()
+    }
+
+    fn len(&self) -> usize {
+        self.seq.len() + self.word.values().map(HashMap::len).sum::<usize>() + self.text.len()
+    }
+}
+
+impl Index<u32> {
+    /// Ranks for occurrence counts, 1 for the most frequent word. Words
+    /// that occur equally often share a rank.
+    fn ranked_by_count(mut self) -> Self {
+        // Entries by text that only mirror an entry by text and reading are
+        // left out, so they do not push later words down.
+        let mut counts: Vec<u32> = self
+            .seq
+            .values()
+            .chain(self.word.values().flat_map(HashMap::values))
+            .chain(
+                self.text
+                    .iter()
+                    .filter(|(text, _)| !self.word.contains_key(*text))
+                    .map(|(_, count)| count),
+            )
+            .copied()
+            .collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        let rank = |count: &mut u32| {
+            *count = counts.partition_point(|&c| c > *count) as u32 + 1;
+        };
+        self.seq.values_mut().for_each(rank);
+        self.word
+            .values_mut()
+            .flat_map(HashMap::values_mut)
+            .for_each(rank);
+        self.text.values_mut().for_each(rank);
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct VocabLists {
+    ranks: Index<u32>,
+    levels: Index<Jlpt>,
+}
+
+impl fmt::Debug for VocabLists {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VocabLists")
+            .field("ranks", &self.ranks.len())
+            .field("levels", &self.levels.len())
+            .finish()
+    }
+}
+
+impl VocabLists {
+    /// Load the lists described by `settings`. Lists that fail to load are
+    /// left out.
+    pub fn open(settings: &VocabSettings) -> Self {
+        let mut lists = Self::default();
+        if !settings.frequency_path.is_empty() {
+            let path = Path::new(&settings.frequency_path);
+            match read_frequency(path) {
+                Ok(ranks) if settings.frequency_counts => lists.ranks = ranks.ranked_by_count(),
+                Ok(ranks) => lists.ranks = ranks,
+                Err(err) => tracing::error!(%err, ?path, "failed to load frequency list"),
+            }
+        }
+        if !settings.jlpt_path.is_empty() {
+            let path = Path::new(&settings.jlpt_path);
+            match read_jlpt(path) {
+                Ok(levels) => lists.levels = levels,
+                Err(err) => tracing::error!(%err, ?path, "failed to load JLPT list"),
+            }
+        }
+        lists
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranks.len() == 0 && self.levels.len() == 0
+    }
+
+    pub fn get(&self, seq: Option<u32>, text: &str, kana: &str) -> WordInfo {
+        WordInfo {
+            rank: self.ranks.get(seq, text, kana),
+            jlpt: self.levels.get(seq, text, kana),
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+fn read_frequency(path: &Path) -> Result<Index<u32>, Error> {
+    let content = std::fs::read_to_string(path)?;
+    if is_json(path) {
+        frequency_from_json(&serde_json::from_str(&content)?)
+    } else {
+        Ok(frequency_from_tsv(&content))
+    }
+}
+
+fn read_jlpt(path: &Path) -> Result<Index<Jlpt>, Error> {
+    let content = std::fs::read_to_string(path)?;
+    if is_json(path) {
+        jlpt_from_json(&serde_json::from_str(&content)?)
+    } else {
+        Ok(jlpt_from_tsv(&content))
+    }
+}
+
+fn tsv_lines(content: &str) -> impl Iterator<Item = Vec<&str>> {
+    content
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| line.split('\t').collect())
+}
+
+fn frequency_from_tsv(content: &str) -> Index<u32> {
+    let mut index = Index::default();
+    for (line, fields) in tsv_lines(content).enumerate() {
+        let (key, rank) = match fields[..] {
+            [text] => (Key::new(text, None), Some(line as u32 + 1)),
+            [text, rank] => (Key::new(text, None), rank.trim().parse().ok()),
+            [text, reading, rank, ..] => (Key::new(text, Some(reading)), rank.trim().parse().ok()),
+            [] => continue,
+        };
+        if let (Some(key), Some(rank)) = (key, rank) {
+            index.insert(key, rank);
+        }
+    }
+    index
+}
+
+fn jlpt_from_tsv(content: &str) -> Index<Jlpt> {
+    let mut index = Index::default();
+    for fields in tsv_lines(content) {
+        let (key, level) = match fields[..] {
+            [text, level] => (Key::new(text, None), Jlpt::parse(level)),
+            [text, reading, level, ..] => (Key::new(text, Some(reading)), Jlpt::parse(level)),
+            _ => continue,
+        };
+        if let (Some(key), Some(level)) = (key, level) {
+            index.insert(key, level);
+        }
+    }
+    index
+}
+
+/// Yomichan term meta banks, `[["語", "freq", 12], ...]` with the frequency
+/// possibly given as `{"value": 12}` or `{"reading": "ご", "frequency": 12}`,
+/// or an object of ranks, `{"語": 12, ...}`. Values are taken as they are;
+/// see [`Index::ranked_by_count`] for banks of counts.
+fn frequency_from_json(json: &Value) -> Result<Index<u32>, Error> {
+    fn rank(value: &Value) -> Option<u32> {
+        match value {
+            Value::Number(n) => n.as_u64().map(|n| n as u32),
+            Value::Object(map) => rank(map.get("value")?),
+            _ => None,
+        }
+    }
+
+    let mut index = Index::default();
+    match json {
+        Value::Array(entries) => {
+            for entry in entries {
+                let Some([text, Value::String(mode), data]) = entry.as_array().map(Vec::as_slice)
+                else {
+                    continue;
+                };
+                if mode != "freq" {
+                    continue;
+                }
+                let Some(text) = text.as_str() else {
+                    continue;
+                };
+                let (reading, rank) = match data {
+                    Value::Object(map) if map.contains_key("frequency") => (
+                        map.get("reading").and_then(Value::as_str),
+                        rank(&map["frequency"]),
+                    ),
+                    data => (None, rank(data)),
+                };
+                if let (Some(key), Some(rank)) = (Key::new(text, reading), rank) {
+                    index.insert(key, rank);
+                }
+            }
+        }
+        Value::Object(ranks) => {
+            for (text, value) in ranks {
+                if let (Some(key), Some(rank)) = (Key::new(text, None), rank(value)) {
+                    index.insert(key, rank);
+                }
+            }
+        }
+        _ => return Err(Error::Format),
+    }
+    Ok(index)
+}
+
+/// Arrays of objects such as `{"word": "語", "reading": "ご", "level": "N5"}`.
+/// `seq` or `jmdict_seq` are used when present.
+fn jlpt_from_json(json: &Value) -> Result<Index<Jlpt>, Error> {
+    fn field<'a>(entry: &'a Value, names: &[&str]) -> Option<&'a Value> {
+        names.iter().find_map(|name| entry.get(name))
+    }
+
+    let Value::Array(entries) = json else {
+        return Err(Error::Format);
+    };
+    let mut index = Index::default();
+    for entry in entries {
+        let level = match field(entry, &["level", "jlpt"]) {
+            Some(Value::String(level)) => Jlpt::parse(level),
+            Some(Value::Number(level)) => Jlpt::parse(&level.to_string()),
+            _ => None,
+        };
+        let seq = field(entry, &["seq", "jmdict_seq"]).and_then(Value::as_u64);
+        let text = field(entry, &["word", "expression", "kanji", "text"]).and_then(Value::as_str);
+        let reading = field(entry, &["reading", "kana", "furigana"]).and_then(Value::as_str);
+        let key = match seq {
+            Some(seq) => Some(Key::Seq(seq as u32)),
+            None => text.and_then(|text| Key::new(text, reading)),
+        };
+        if let (Some(key), Some(level)) = (key, level) {
+            index.insert(key, level);
+        }
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_levels() {
+        assert_eq!(Jlpt::parse("N3"), Some(Jlpt::N3));
+        assert_eq!(Jlpt::parse("jlpt-n1"), Some(Jlpt::N1));
+        assert_eq!(Jlpt::parse("5"), Some(Jlpt::N5));
+        assert_eq!(Jlpt::parse("N6"), None);
+        assert_eq!(Jlpt::parse("level"), None);
+    }
+
+    #[test]
+    fn ranks_from_tsv() {
+        let ranks = frequency_from_tsv("# rank list\nの\nに\r\n");
+        assert_eq!(ranks.get(None, "に", "に"), Some(2));
+        let ranks = frequency_from_tsv("word\treading\trank\n日本\tにほん\t120\n1234567\t\t5\n");
+        assert_eq!(ranks.get(None, "日本", "にほん"), Some(120));
+        // Found by text when read differently.
+        assert_eq!(ranks.get(None, "日本", "にっぽん"), Some(120));
+        assert_eq!(ranks.get(Some(1234567), "何", "なに"), Some(5));
+        assert_eq!(ranks.get(None, "word", "reading"), None);
+    }
+
+    #[test]
+    fn ranks_from_yomichan() {
+        let bank = json!([
+            ["猫", "freq", 900],
+            ["犬", "freq", {"value": 800, "displayValue": "800"}],
+            ["生", "freq", {"reading": "なま", "frequency": 3000}],
+            ["生", "freq", {"reading": "せい", "frequency": {"value": 2000}}],
+            ["猫", "pitch", {"reading": "ねこ", "pitches": []}],
+        ]);
+        let ranks = frequency_from_json(&bank).unwrap();
+        assert_eq!(ranks.get(None, "猫", "ねこ"), Some(900));
+        assert_eq!(ranks.get(None, "犬", "いぬ"), Some(800));
+        assert_eq!(ranks.get(None, "生", "せい"), Some(2000));
+        assert_eq!(ranks.get(None, "生", "なま"), Some(3000));
+
+        let ranks = frequency_from_json(&json!({"猫": 1})).unwrap();
+        assert_eq!(ranks.get(None, "猫", "ねこ"), Some(1));
+        assert!(frequency_from_json(&json!("猫")).is_err());
+    }
+
+    #[test]
+    fn ranks_from_counts() {
+        let bank = json!([
+            ["の", "freq", 5000],
+            ["猫", "freq", {"reading": "ねこ", "frequency": 40}],
+            ["犬", "freq", 40],
+            ["鰐", "freq", 2],
+        ]);
+        let ranks = frequency_from_json(&bank).unwrap().ranked_by_count();
+        assert_eq!(ranks.get(None, "の", "の"), Some(1));
+        assert_eq!(ranks.get(None, "猫", "ねこ"), Some(2));
+        assert_eq!(ranks.get(None, "猫", "びょう"), Some(2));
+        assert_eq!(ranks.get(None, "犬", "いぬ"), Some(2));
+        assert_eq!(ranks.get(None, "鰐", "わに"), Some(4));
+    }
+
+    #[test]
+    fn levels_prefer_sequence_numbers() {
+        let list = json!([
+            {"word": "食べる", "reading": "たべる", "level": "N5"},
+            {"seq": 1358280, "word": "食べる", "level": 4},
+            {"kanji": "概念", "furigana": "がいねん", "jlpt": "N1"},
+        ]);
+        let levels = jlpt_from_json(&list).unwrap();
+        assert_eq!(
+            levels.get(Some(1358280), "食べる", "たべる"),
+            Some(Jlpt::N4)
+        );
+        assert_eq!(levels.get(Some(1), "食べる", "たべる"), Some(Jlpt::N5));
+        assert_eq!(levels.get(None, "概念", "がいねん"), Some(Jlpt::N1));
+
+        let levels = jlpt_from_tsv("概念\tがいねん\tN1\n猫\tN5\n");
+        assert_eq!(levels.get(None, "猫", "ねこ"), Some(Jlpt::N5));
+        assert_eq!(levels.get(None, "概念", "がいねん"), Some(Jlpt::N1));
+    }
+}
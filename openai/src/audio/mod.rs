@@ -0,0 +1,37 @@
+//! https://platform.openai.com/docs/api-reference/audio
+//!
+//! Text-to-speech. Responses are raw audio rather than JSON, so only errors
+//! are parsed.
+
+use reqwest::Method;
+use tracing::Level;
+
+pub use crate::protocol::audio::{AudioFormat, SpeechRequest};
+
+use crate::{protocol::audio::ErrorResponse, Client, Error};
+
+impl Client {
+    /// Generate audio from the input text, encoded as `response_format`.
+    #[tracing::instrument(level = Level::DEBUG, skip_all, err)]
+    pub async fn speech(&self, request: SpeechRequest) -> Result<Vec<u8>, Error> {
+        tracing::debug!(?request);
+        let response = self
+            .shared
+            .request(Method::POST, "v1/audio/speech")
+            .body(&request)
+            .send()
+            .await?;
+        if response.status().is_success() {
+            return Ok(response.bytes().await?.to_vec());
+        }
+        // HTTP error: Expect JSON response
+        let response_err = response.error_for_status_ref().unwrap_err();
+        match response.json::<ErrorResponse>().await {
+            Ok(err) => Err(Error::Protocol(err.error)),
+            Err(err) => {
+                tracing::error!(?response_err, ?err, "unexpected speech response");
+                Err(response_err.into())
+            }
+        }
+    }
+}
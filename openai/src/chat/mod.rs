@@ -9,6 +9,9 @@ pub use crate::protocol::chat::{
     FunctionCall, FunctionDef, Message, PartialFunctionCall, PartialMessage, PartialToolCall,
     Request, Role, Tool, ToolCall, ToolCallKind, ToolChoice, ToolChoiceMode, Usage,
 };
+pub use tokenizer::Tokenizer;
+
+mod tokenizer;
 
 /// Accumulates streaming `PartialToolCall` fragments (keyed by `index`) into
 /// complete [`ToolCall`]s once the model stops emitting chunks.
@@ -397,6 +400,58 @@ mod tests {
         assert_eq!(calls[1].function.arguments, "{\"tz\":\"JST\"}");
     }
 
+    #[test]
+    fn deserialize_reasoning_content() {
+        // llama.cpp and DeepSeek stream reasoning ahead of the answer;
+        // OpenRouter calls the same field `reasoning`.
+        let llama = r#"{
+            "id":"chatcmpl-1","object":"chat.completion.chunk","created":1,"model":"qwen3",
+            "choices":[{"index":0,"delta":{"content":null,"reasoning_content":"The user"},
+                "finish_reason":null}]
+        }"#;
+        let partial: chat::PartialCompletion = serde_json::from_str(llama).unwrap();
+        let delta = &partial.choices[0].delta;
+        assert_eq!(delta.reasoning_text(), Some("The user"));
+        assert!(delta.content.is_none());
+
+        let openrouter = r#"{
+            "id":"chatcmpl-2","object":"chat.completion","created":1,"model":"deepseek-r1",
+            "choices":[{"index":0,"finish_reason":"stop","message":{
+                "role":"assistant","content":"Hello.","reasoning":"A greeting."}}],
+            "usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15,
+                "completion_tokens_details":{"reasoning_tokens":3}}
+        }"#;
+        let completion: chat::Completion = serde_json::from_str(openrouter).unwrap();
+        let message = &completion.choices[0].message;
+        assert_eq!(message.reasoning_text(), Some("A greeting."));
+        let details = completion.usage.completion_tokens_details.as_ref().unwrap();
+        assert_eq!(details.reasoning_tokens, 3);
+        // Never echoed back to the server.
+        let json = serde_json::to_value(message).unwrap();
+        assert!(json.get("reasoning_content").is_none());
+        assert!(json.get("reasoning").is_none());
+    }
+
+    #[test]
+    fn deserialize_reasoning_under_both_keys() {
+        // Some proxies forward both names for the same text.
+        let both = r#"{
+            "id":"chatcmpl-3","object":"chat.completion.chunk","created":1,"model":"glm-4.6",
+            "choices":[{"index":0,"delta":{"reasoning_content":"Hm.","reasoning":"Hm."},
+                "finish_reason":null}]
+        }"#;
+        let mut partial: chat::PartialCompletion = serde_json::from_str(both).unwrap();
+        let delta = &mut partial.choices[0].delta;
+        assert_eq!(delta.take_reasoning().as_deref(), Some("Hm."));
+        assert!(delta.take_reasoning().is_none());
+
+        let message: chat::Message = serde_json::from_str(
+            r#"{"role":"assistant","content":"Hi.","reasoning_content":"A","reasoning":"B"}"#,
+        )
+        .unwrap();
+        assert_eq!(message.reasoning_text(), Some("A"));
+    }
+
     #[test]
     fn deserialize_stream_chunks_with_tool_call_deltas() {
         // First delta: role + tool call skeleton.
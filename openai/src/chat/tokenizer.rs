@@ -0,0 +1,195 @@
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton};
+
+use super::{Message, Tool};
+
+/// Per-model token counting. Counts are estimates of what the server will
+/// bill; the chat framing overhead follows the OpenAI cookbook.
+/// https://cookbook.openai.com/examples/how_to_count_tokens_with_tiktoken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// gpt-4, gpt-3.5-turbo and embeddings.
+    Cl100k,
+    /// gpt-4o, gpt-4.1, gpt-5, o-series and gpt-oss.
+    O200k,
+    /// Models whose tokenizer is unknown (local models, other vendors).
+    /// Counts each non-ASCII character as one token and ASCII text as four
+    /// bytes per token, which is in the right ballpark for Japanese and
+    /// English on most modern vocabularies.
+    Heuristic,
+}
+
+/// Fixed cost of every message: `<|start|>{role}<|message|>...<|end|>`.
+const TOKENS_PER_MESSAGE: u32 = 3;
+/// Extra cost of a `name` field on top of its content.
+const TOKENS_PER_NAME: u32 = 1;
+/// Every reply is primed with `<|start|>assistant<|message|>`.
+const TOKENS_PER_REPLY: u32 = 3;
+/// Framing around the tool definitions block, and around each definition.
+const TOKENS_PER_TOOLS: u32 = 12;
+const TOKENS_PER_TOOL: u32 = 7;
+
+impl Tokenizer {
+    /// Pick the tokenizer for a model id. Vendor prefixes as used by routers
+    /// (e.g. `openai/gpt-4o`) are ignored.
+    pub fn for_model(model: &str) -> Self {
+        let model = model
+            .rsplit('/')
+            .next()
+            .unwrap_or(model)
+            .to_ascii_lowercase();
+        const O200K: &[&str] = &[
+            "gpt-4o",
+            "chatgpt-4o",
+            "gpt-4.1",
+            "gpt-4.5",
+            "gpt-5",
+            "gpt-oss",
+            "o1",
+            "o3",
+            "o4",
+        ];
+        const CL100K: &[&str] = &["gpt-4", "gpt-3.5", "text-embedding"];
+        if O200K.iter().any(|prefix| model.starts_with(prefix)) {
+            Self::O200k
+        } else if CL100K.iter().any(|prefix| model.starts_with(prefix)) {
+            Self::Cl100k
+        } else {
+            Self::Heuristic
+        }
+    }
+
+    pub fn count(&self, text: &str) -> u32 {
+        match self {
+            Self::Cl100k => cl100k_base_singleton()
+                .encode_with_special_tokens(text)
+                .len() as u32,
+            Self::O200k => o200k_base_singleton()
+                .encode_with_special_tokens(text)
+                .len() as u32,
+            Self::Heuristic => {
+                let (ascii, other) = text.chars().fold((0u32, 0u32), |(ascii, other), c| {
+                    if c.is_ascii() {
+                        (ascii + 1, other)
+                    } else {
+                        (ascii, other + 1)
+                    }
+                });
+                ascii.div_ceil(4) + other
+            }
+        }
+    }
+
+    /// Tokens taken by one message in a prompt, including role, name and any
+    /// tool calls or tool results.
+    pub fn count_message(&self, message: &Message) -> u32 {
+        // The role always encodes to a single token.
+        let mut tokens = TOKENS_PER_MESSAGE + 1;
+        if let Some(content) = &message.content {
+            tokens += self.count(content);
+        }
+        if let Some(name) = &message.name {
+            tokens += self.count(name) + TOKENS_PER_NAME;
+        }
+        for call in message.tool_calls.iter().flatten() {
+            tokens += TOKENS_PER_MESSAGE
+                + self.count(&call.function.name)
+                + self.count(&call.function.arguments);
+        }
+        if let Some(id) = &message.tool_call_id {
+            tokens += self.count(id);
+        }
+        tokens
+    }
+
+    /// Tokens taken by tool definitions. The server renders these into the
+    /// system prompt in an undocumented format, so this counts the JSON
+    /// definitions plus a fixed framing overhead.
+    pub fn count_tools(&self, tools: &[Tool]) -> u32 {
+        if tools.is_empty() {
+            return 0;
+        }
+        TOKENS_PER_TOOLS
+            + tools
+                .iter()
+                .map(|Tool::Function { function }| {
+                    let json = serde_json::to_string(function).unwrap_or_default();
+                    TOKENS_PER_TOOL + self.count(&json)
+                })
+                .sum::<u32>()
+    }
+
+    /// Tokens taken by a whole prompt, including reply priming.
+    pub fn count_prompt(&self, messages: &[Message], tools: &[Tool]) -> u32 {
+        messages
+            .iter()
+            .map(|message| self.count_message(message))
+            .sum::<u32>()
+            + self.count_tools(tools)
+            + TOKENS_PER_REPLY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{FunctionDef, Role};
+
+    #[test]
+    fn selects_tokenizer_by_model() {
+        assert_eq!(Tokenizer::for_model("gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("gpt-5-nano"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("o3-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("openai/gpt-4.1"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("gpt-4-turbo"), Tokenizer::Cl100k);
+        assert_eq!(Tokenizer::for_model("gpt-3.5-turbo"), Tokenizer::Cl100k);
+        assert_eq!(
+            Tokenizer::for_model("claude-sonnet-4-5"),
+            Tokenizer::Heuristic
+        );
+        assert_eq!(
+            Tokenizer::for_model("qwen2.5-7b-instruct"),
+            Tokenizer::Heuristic
+        );
+    }
+
+    #[test]
+    fn vocabularies_differ() {
+        // o200k has far better coverage of Japanese than cl100k.
+        let text = "吾輩は猫である。名前はまだ無い。どこで生れたかとんと見当がつかぬ。";
+        assert!(Tokenizer::O200k.count(text) < Tokenizer::Cl100k.count(text));
+    }
+
+    #[test]
+    fn heuristic_counts_characters() {
+        assert_eq!(Tokenizer::Heuristic.count("猫です"), 3);
+        assert_eq!(Tokenizer::Heuristic.count("hello"), 2);
+        assert_eq!(Tokenizer::Heuristic.count("cat 猫"), 2);
+        assert_eq!(Tokenizer::Heuristic.count(""), 0);
+    }
+
+    #[test]
+    fn names_and_tools_add_tokens() {
+        let tokenizer = Tokenizer::O200k;
+        let mut message = Message {
+            role: Role::User,
+            content: Some("こんにちは".into()),
+            ..Default::default()
+        };
+        let bare = tokenizer.count_message(&message);
+        message.name = Some("info".into());
+        assert_eq!(
+            tokenizer.count_message(&message),
+            bare + tokenizer.count("info") + TOKENS_PER_NAME
+        );
+
+        let tools = vec![Tool::function(FunctionDef {
+            name: "lookup".into(),
+            description: Some("Look up a term".into()),
+            parameters: None,
+            strict: None,
+        })];
+        let messages = [message];
+        assert!(tokenizer.count_prompt(&messages, &tools) > tokenizer.count_prompt(&messages, &[]));
+        assert_eq!(tokenizer.count_tools(&[]), 0);
+    }
+}
@@ -5,8 +5,13 @@
 //!    You literally wrote it just for this program.
 //! A: Yes, and?
 
+pub mod audio;
 pub mod chat;
+pub mod messages;
+#[cfg(feature = "mock")]
+pub mod mock;
 mod protocol;
+pub mod responses;
 
 use std::{sync::Arc, time::Duration};
 
@@ -37,6 +42,16 @@ struct Shared {
     client: reqwest::Client,
     api_base: reqwest::Url,
     token: String,
+    auth: Auth,
+}
+
+/// How the API token is presented to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Auth {
+    /// `Authorization: Bearer <token>` (OpenAI and compatibles).
+    Bearer,
+    /// `x-api-key: <token>` plus a pinned `anthropic-version`.
+    Anthropic,
 }
 
 #[derive(Clone)]
@@ -69,6 +84,7 @@ impl RequestBuilder {
         self.reqwest_builder = self.reqwest_builder.header("OpenAI-Beta", beta.into());
         self
     }
+
     pub async fn send(self) -> reqwest::Result<reqwest::Response> {
         let request_fn_mut = || async { self.reqwest_builder.try_clone().unwrap().send().await };
         request_fn_mut
@@ -85,6 +101,52 @@ impl Client {
         token: impl Into<String>,
         api_endpoint: impl reqwest::IntoUrl,
         connection_policy: ConnectionPolicy,
+    ) -> Self {
+        Self::with_auth(http(connection_policy), token, api_endpoint, Auth::Bearer)
+    }
+    /// Client for the Anthropic API (https://api.anthropic.com). Speaks the
+    /// Messages API via [`Client::messages`] and lists models as usual.
+    pub fn anthropic(
+        token: impl Into<String>,
+        api_endpoint: impl reqwest::IntoUrl,
+        connection_policy: ConnectionPolicy,
+    ) -> Self {
+        Self::with_auth(
+            http(connection_policy),
+            token,
+            api_endpoint,
+            Auth::Anthropic,
+        )
+    }
+    /// Client for another OpenAI-compatible endpoint that shares this
+    /// client's connection pool and timeouts.
+    pub fn sibling(&self, token: impl Into<String>, api_endpoint: impl reqwest::IntoUrl) -> Self {
+        Self::with_auth(
+            self.shared.client.clone(),
+            token,
+            api_endpoint,
+            Auth::Bearer,
+        )
+    }
+    /// Client for the Anthropic API that shares this client's connection
+    /// pool and timeouts.
+    pub fn anthropic_sibling(
+        &self,
+        token: impl Into<String>,
+        api_endpoint: impl reqwest::IntoUrl,
+    ) -> Self {
+        Self::with_auth(
+            self.shared.client.clone(),
+            token,
+            api_endpoint,
+            Auth::Anthropic,
+        )
+    }
+    fn with_auth(
+        client: reqwest::Client,
+        token: impl Into<String>,
+        api_endpoint: impl reqwest::IntoUrl,
+        auth: Auth,
     ) -> Self {
         let mut api_base = api_endpoint.into_url().unwrap();
         // `Url::join("v1/...")` on a base whose path has no trailing slash
@@ -96,21 +158,10 @@ impl Client {
         }
         Self {
             shared: Arc::new(Shared {
-                client: reqwest::Client::builder()
-                    .timeout(connection_policy.timeout)
-                    .connect_timeout(connection_policy.connect_timeout)
-                    // Keep the TLS/HTTP-2 connection to the chat endpoint hot
-                    // so the first translation token after an idle period
-                    // does not pay TCP+TLS handshake latency.
-                    .pool_idle_timeout(None)
-                    .tcp_keepalive(Duration::from_secs(30))
-                    .http2_keep_alive_interval(Duration::from_secs(30))
-                    .http2_keep_alive_timeout(Duration::from_secs(10))
-                    .http2_keep_alive_while_idle(true)
-                    .build()
-                    .unwrap(),
+                client,
                 api_base,
                 token: token.into(),
+                auth,
             }),
         }
     }
@@ -126,11 +177,38 @@ impl Client {
     }
 }
 
+fn http(connection_policy: ConnectionPolicy) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(connection_policy.timeout)
+        .connect_timeout(connection_policy.connect_timeout)
+        // Keep the TLS/HTTP-2 connection to the chat endpoint hot so the
+        // first translation token after an idle period does not pay TCP+TLS
+        // handshake latency.
+        .pool_idle_timeout(None)
+        .tcp_keepalive(Duration::from_secs(30))
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .http2_keep_alive_timeout(Duration::from_secs(10))
+        .http2_keep_alive_while_idle(true)
+        .build()
+        .unwrap()
+}
+
 impl Shared {
     fn request(&self, method: reqwest::Method, path: impl AsRef<str>) -> RequestBuilder {
-        let Shared { token, client, .. } = self;
+        let Shared {
+            token,
+            client,
+            auth,
+            ..
+        } = self;
         let uri = self.api_base.join(path.as_ref()).unwrap();
-        let r = client.request(method, uri).bearer_auth(token);
+        let r = client.request(method, uri);
+        let r = match auth {
+            Auth::Bearer => r.bearer_auth(token),
+            Auth::Anthropic => r
+                .header("x-api-key", token)
+                .header("anthropic-version", "2023-06-01"),
+        };
         RequestBuilder::new(r)
     }
 }
@@ -0,0 +1,342 @@
+//! https://docs.anthropic.com/en/api/messages
+//!
+//! Anthropic's Messages API. Requires a client built with
+//! [`Client::anthropic`](crate::Client::anthropic).
+
+use eventsource_stream::Eventsource;
+use reqwest::Method;
+use tokio_stream::{Stream, StreamExt};
+use tracing::Level;
+
+pub use crate::protocol::messages::{
+    CacheControl, ContentBlock, Delta, Message, MessageDelta, MessageResponse, Request, Role,
+    StreamEvent, Thinking, Tool, ToolChoice, Usage,
+};
+
+use crate::{
+    chat,
+    protocol::messages::{MessagesResponse, StreamEvent as Event},
+    Client, Error,
+};
+
+/// Convert a Chat Completions transcript into a Messages API system prompt
+/// and message list.
+///
+/// - `system`/`developer`-style messages are hoisted into the system prompt.
+/// - Assistant tool calls become `tool_use` blocks.
+/// - `role: tool` replies become `tool_result` blocks on a user turn.
+/// - `name` has no equivalent and is dropped.
+pub fn from_chat_messages(messages: Vec<chat::Message>) -> (Vec<ContentBlock>, Vec<Message>) {
+    let mut system = Vec::new();
+    let mut out: Vec<Message> = Vec::with_capacity(messages.len());
+    for message in messages {
+        let (role, content) = match message.role {
+            chat::Role::System => {
+                if let Some(content) = message.content {
+                    system.push(ContentBlock::text(content));
+                }
+                continue;
+            }
+            chat::Role::User => (
+                Role::User,
+                message
+                    .content
+                    .map(ContentBlock::text)
+                    .into_iter()
+                    .collect(),
+            ),
+            chat::Role::Assistant => {
+                let mut content: Vec<ContentBlock> = message
+                    .content
+                    .map(ContentBlock::text)
+                    .into_iter()
+                    .collect();
+                for call in message.tool_calls.unwrap_or_default() {
+                    content.push(ContentBlock::ToolUse {
+                        input: serde_json::from_str(&call.function.arguments)
+                            .unwrap_or_else(|_| serde_json::json!({})),
+                        id: call.id,
+                        name: call.function.name,
+                    });
+                }
+                (Role::Assistant, content)
+            }
+            chat::Role::Tool => (
+                Role::User,
+                vec![ContentBlock::ToolResult {
+                    tool_use_id: message.tool_call_id.unwrap_or_default(),
+                    content: message.content.unwrap_or_default(),
+                    is_error: None,
+                }],
+            ),
+        };
+        if content.is_empty() {
+            continue;
+        }
+        // Tool results must directly follow the tool_use turn; merge runs of
+        // same-role turns so parallel tool results share one user message.
+        match out.last_mut() {
+            Some(last) if last.role == role => last.content.extend(content),
+            _ => out.push(Message { role, content }),
+        }
+    }
+    (system, out)
+}
+
+impl Client {
+    #[tracing::instrument(level = Level::DEBUG, skip_all, err)]
+    pub async fn messages(&self, mut request: Request) -> Result<MessageResponse, Error> {
+        request.stream = None;
+        tracing::debug!(?request);
+        let response: MessagesResponse = self
+            .shared
+            .request(Method::POST, "v1/messages")
+            .body(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+        tracing::debug!(?response);
+        Ok(response.0?)
+    }
+
+    #[tracing::instrument(level = Level::DEBUG, skip_all, err)]
+    pub async fn messages_stream(
+        &self,
+        mut request: Request,
+    ) -> Result<impl Stream<Item = Result<StreamEvent, Error>>, Error> {
+        request.stream = Some(true);
+        tracing::debug!(?request);
+        let response = self
+            .shared
+            .request(Method::POST, "v1/messages")
+            .body(&request)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            // HTTP success: Expect SSE response. The stream is over once
+            // `message_stop` has been delivered.
+            let mut done = false;
+            let stream = response.bytes_stream().eventsource();
+            Ok(stream.map_while(move |event| {
+                tracing::trace!(?event);
+                if done {
+                    return None;
+                }
+                match event {
+                    Ok(event) => {
+                        let response = match serde_json::from_str::<Event>(&event.data) {
+                            Ok(Event::Error { error }) => {
+                                // Errors can arrive mid-stream (e.g. overloaded)
+                                tracing::error!(?error);
+                                done = true;
+                                Err(Error::Protocol(error))
+                            }
+                            Ok(response) => {
+                                tracing::debug!(?response);
+                                done = matches!(response, Event::MessageStop);
+                                Ok(response)
+                            }
+                            Err(err) => {
+                                // Serde error
+                                tracing::error!(?err, ?event.data);
+                                Err(err.into())
+                            }
+                        };
+                        Some(response)
+                    }
+                    Err(err) => {
+                        // SSE error
+                        tracing::error!(?err);
+                        Some(Err(err.into()))
+                    }
+                }
+            }))
+        } else {
+            // HTTP error: Expect JSON response
+            let response_err = response.error_for_status_ref().unwrap_err();
+            let messages_response = response.json::<MessagesResponse>().await;
+            match messages_response {
+                Ok(MessagesResponse(Err(err))) => {
+                    // Anthropic application error
+                    Err(Error::Protocol(err))
+                }
+                Ok(MessagesResponse(Ok(_))) | Err(_) => {
+                    // Not application error, return HTTP error
+                    tracing::error!(?response_err, "unexpected stream response");
+                    Err(response_err.into())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat_message(role: chat::Role, content: &str) -> chat::Message {
+        chat::Message {
+            role,
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn system_messages_are_hoisted() {
+        let (system, messages) = from_chat_messages(vec![
+            chat_message(chat::Role::System, "Translate into English."),
+            chat_message(chat::Role::User, "こんにちは"),
+            chat_message(chat::Role::Assistant, "Hello."),
+        ]);
+        assert_eq!(system, vec![ContentBlock::text("Translate into English.")]);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::User);
+        assert_eq!(messages[1].role, Role::Assistant);
+    }
+
+    #[test]
+    fn tool_calls_and_results_convert_to_blocks() {
+        let assistant = chat::Message {
+            role: chat::Role::Assistant,
+            tool_calls: Some(vec![
+                chat::ToolCall {
+                    id: "call_a".into(),
+                    kind: chat::ToolCallKind::Function,
+                    function: chat::FunctionCall {
+                        name: "lookup".into(),
+                        arguments: "{\"term\":\"猫\"}".into(),
+                    },
+                },
+                chat::ToolCall {
+                    id: "call_b".into(),
+                    kind: chat::ToolCallKind::Function,
+                    function: chat::FunctionCall {
+                        name: "lookup".into(),
+                        arguments: "{\"term\":\"犬\"}".into(),
+                    },
+                },
+            ]),
+            ..Default::default()
+        };
+        let (_, messages) = from_chat_messages(vec![
+            chat_message(chat::Role::User, "猫と犬"),
+            assistant,
+            chat::Message::tool_result("call_a", "cat"),
+            chat::Message::tool_result("call_b", "dog"),
+        ]);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1].content[0],
+            ContentBlock::ToolUse {
+                id: "call_a".into(),
+                name: "lookup".into(),
+                input: serde_json::json!({ "term": "猫" }),
+            }
+        );
+        // Parallel tool results share one user turn.
+        assert_eq!(messages[2].role, Role::User);
+        assert_eq!(messages[2].content.len(), 2);
+        let json = serde_json::to_value(&messages[2]).unwrap();
+        assert_eq!(json["content"][0]["type"], "tool_result");
+        assert_eq!(json["content"][0]["tool_use_id"], "call_a");
+        assert!(json["content"][0].get("is_error").is_none());
+    }
+
+    #[test]
+    fn request_serializes_system_and_cache_control() {
+        let mut system = ContentBlock::text("Translate.");
+        system.set_cache_control(CacheControl::Ephemeral);
+        let request = Request::builder()
+            .model(crate::ModelId("claude-sonnet-4-5".into()))
+            .messages(vec![])
+            .system(vec![system])
+            .max_tokens(256)
+            .build();
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["system"][0]["type"], "text");
+        assert_eq!(json["system"][0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(json["max_tokens"], 256);
+        assert!(json.get("stream").is_none());
+        assert!(json.get("stop_sequences").is_none());
+        assert!(json.get("temperature").is_none());
+    }
+
+    #[test]
+    fn chat_tool_choice_converts() {
+        let choice: ToolChoice = chat::ToolChoice::Mode(chat::ToolChoiceMode::Required).into();
+        assert_eq!(serde_json::to_value(choice).unwrap()["type"], "any");
+        let choice: ToolChoice = chat::ToolChoice::function("lookup").into();
+        let json = serde_json::to_value(choice).unwrap();
+        assert_eq!(json["type"], "tool");
+        assert_eq!(json["name"], "lookup");
+    }
+
+    #[test]
+    fn deserialize_stream_events() {
+        let start = r#"{"type":"message_start","message":{"id":"msg_1","type":"message",
+            "role":"assistant","content":[],"model":"claude-sonnet-4-5","stop_reason":null,
+            "stop_sequence":null,"usage":{"input_tokens":25,"output_tokens":1,
+            "cache_creation_input_tokens":0,"cache_read_input_tokens":1024}}}"#;
+        let Event::MessageStart { message } = serde_json::from_str(start).unwrap() else {
+            panic!("expected message_start");
+        };
+        assert_eq!(message.usage.cache_read_input_tokens, Some(1024));
+
+        let tool_start = r#"{"type":"content_block_start","index":1,"content_block":
+            {"type":"tool_use","id":"toolu_1","name":"lookup","input":{}}}"#;
+        let Event::ContentBlockStart {
+            index,
+            content_block: ContentBlock::ToolUse { id, .. },
+        } = serde_json::from_str(tool_start).unwrap()
+        else {
+            panic!("expected tool_use block");
+        };
+        assert_eq!((index, id.as_str()), (1, "toolu_1"));
+
+        let delta = r#"{"type":"content_block_delta","index":1,
+            "delta":{"type":"input_json_delta","partial_json":"{\"term\":"}}"#;
+        let Event::ContentBlockDelta { delta, .. } = serde_json::from_str(delta).unwrap() else {
+            panic!("expected delta");
+        };
+        assert_eq!(
+            delta,
+            Delta::InputJsonDelta {
+                partial_json: "{\"term\":".into()
+            }
+        );
+
+        let message_delta = r#"{"type":"message_delta","delta":{"stop_reason":"end_turn",
+            "stop_sequence":null},"usage":{"output_tokens":15}}"#;
+        let Event::MessageDelta { delta, usage } = serde_json::from_str(message_delta).unwrap()
+        else {
+            panic!("expected message_delta");
+        };
+        assert_eq!(delta.stop_reason.as_deref(), Some("end_turn"));
+        assert_eq!(usage.output_tokens, 15);
+
+        let unknown: Event = serde_json::from_str(r#"{"type":"future_event"}"#).unwrap();
+        assert!(matches!(unknown, Event::Unknown));
+    }
+
+    #[test]
+    fn usage_converts_with_cache_tokens() {
+        let mut usage = Usage {
+            input_tokens: 25,
+            output_tokens: 1,
+            cache_creation_input_tokens: Some(100),
+            cache_read_input_tokens: Some(1000),
+        };
+        usage.merge(&Usage {
+            output_tokens: 15,
+            ..Default::default()
+        });
+        let usage: chat::Usage = usage.into();
+        assert_eq!(usage.prompt_tokens, 1125);
+        assert_eq!(usage.completion_tokens, 15);
+        assert_eq!(usage.total_tokens, 1140);
+        assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, 1000);
+    }
+}
@@ -0,0 +1,476 @@
+//! In-process mock of an OpenAI-compatible server for offline tests.
+//!
+//! [`MockServer`] binds an ephemeral port on localhost and answers each
+//! request with the next [`MockResponse`] scripted for its path. Point
+//! [`Client::new`](crate::Client::new) at [`MockServer::url`] and the client
+//! is none the wiser. Every request is recorded so tests can assert on the
+//! wire format.
+//!
+//! The HTTP implementation is deliberately minimal: one request per
+//! connection, `Connection: close` on every response, no chunked uploads.
+//! That is all `reqwest` needs to talk to it.
+
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// A request as received by the mock server.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// Case-insensitive header lookup.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parse the body as JSON, or `Value::Null` if it isn't.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MockBody {
+    Full(Vec<u8>),
+    /// Server-sent events. Each entry is written as a `data:` payload (or
+    /// verbatim, if it already contains a field name), followed by a blank
+    /// line, with `interval` between events.
+    Events {
+        events: Vec<String>,
+        interval: Duration,
+    },
+}
+
+/// A scripted response. Build with one of the constructors and adjust with
+/// the `with_*` methods.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    content_type: &'static str,
+    body: MockBody,
+    /// Delay before the response head is written.
+    latency: Duration,
+}
+
+impl MockResponse {
+    /// `200 OK` with a JSON body.
+    pub fn json(body: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: MockBody::Full(body.to_string().into_bytes()),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// An OpenAI-style application error (`{"error": {...}}`).
+    pub fn error(status: u16, error_type: &str, message: &str) -> Self {
+        Self::json(serde_json::json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "param": null,
+                "code": null,
+            }
+        }))
+        .with_status(status)
+    }
+
+    /// A body that is not JSON at all, as returned by a misbehaving proxy.
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: MockBody::Full(body.into().into_bytes()),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// `200 OK` with a binary body, e.g. synthesized audio.
+    pub fn bytes(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body: MockBody::Full(body.into()),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// A non-streaming chat completion with a single assistant choice.
+    pub fn completion(content: &str) -> Self {
+        Self::json(serde_json::json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": chunk::MODEL,
+            "choices": [{
+                "index": 0,
+                "finish_reason": "stop",
+                "message": { "role": "assistant", "content": content },
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 },
+        }))
+    }
+
+    /// A chat completion stream: each chunk is sent as one event, followed by
+    /// the `[DONE]` sentinel. See [`chunk`] for builders.
+    pub fn stream(chunks: impl IntoIterator<Item = serde_json::Value>) -> Self {
+        let mut events: Vec<String> = chunks.into_iter().map(|c| c.to_string()).collect();
+        events.push("[DONE]".into());
+        Self::events(events)
+    }
+
+    /// Raw server-sent events, written as-is. Use for malformed payloads or
+    /// streams that end without `[DONE]`.
+    pub fn events(events: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            body: MockBody::Events {
+                events: events.into_iter().map(Into::into).collect(),
+                interval: Duration::ZERO,
+            },
+            latency: Duration::ZERO,
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Wait before writing the response head.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Wait between server-sent events. No effect on non-streaming bodies.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        if let MockBody::Events { interval: i, .. } = &mut self.body {
+            *i = interval;
+        }
+        self
+    }
+}
+
+/// Builders for `chat.completion.chunk` payloads.
+pub mod chunk {
+    use serde_json::{json, Value};
+
+    pub const MODEL: &str = "mock-model";
+
+    fn wrap(choices: Value) -> Value {
+        json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": MODEL,
+            "choices": choices,
+        })
+    }
+
+    fn delta(delta: Value) -> Value {
+        wrap(json!([{ "index": 0, "delta": delta, "finish_reason": null }]))
+    }
+
+    /// Opening chunk carrying only the assistant role.
+    pub fn role() -> Value {
+        delta(json!({ "role": "assistant", "content": "" }))
+    }
+
+    pub fn content(text: &str) -> Value {
+        delta(json!({ "content": text }))
+    }
+
+    /// Reasoning fragment, as sent by DeepSeek-style servers.
+    pub fn reasoning(text: &str) -> Value {
+        delta(json!({ "reasoning_content": text }))
+    }
+
+    /// First fragment of a tool call: id, name and (usually empty) arguments.
+    pub fn tool_call(index: u32, id: &str, name: &str, arguments: &str) -> Value {
+        delta(json!({ "tool_calls": [{
+            "index": index,
+            "id": id,
+            "type": "function",
+            "function": { "name": name, "arguments": arguments },
+        }]}))
+    }
+
+    /// Subsequent argument fragment for the tool call at `index`.
+    pub fn tool_arguments(index: u32, arguments: &str) -> Value {
+        delta(json!({ "tool_calls": [{
+            "index": index,
+            "function": { "arguments": arguments },
+        }]}))
+    }
+
+    pub fn finish(reason: &str) -> Value {
+        wrap(json!([{ "index": 0, "delta": {}, "finish_reason": reason }]))
+    }
+
+    /// Trailing usage chunk, as sent with `stream_options.include_usage`.
+    pub fn usage(prompt_tokens: u32, completion_tokens: u32) -> Value {
+        let mut chunk = wrap(json!([]));
+        chunk["usage"] = json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+        });
+        chunk
+    }
+}
+
+#[derive(Default)]
+struct Route {
+    path: String,
+    queued: VecDeque<MockResponse>,
+    fallback: Option<MockResponse>,
+}
+
+#[derive(Default)]
+struct Routes {
+    /// In registration order, one per path.
+    routes: Vec<Route>,
+    requests: Vec<RecordedRequest>,
+}
+
+impl Routes {
+    fn route(&mut self, path: &str) -> &mut Route {
+        let path = path.trim_start_matches('/');
+        match self.routes.iter().position(|route| route.path == path) {
+            Some(idx) => &mut self.routes[idx],
+            None => {
+                self.routes.push(Route {
+                    path: path.into(),
+                    ..Default::default()
+                });
+                self.routes.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Match on path suffix so clients configured with a base path (e.g.
+    /// `http://host/llama.cpp`) still hit the route. When several routes
+    /// match, the longest wins. The query string is ignored.
+    fn next(&mut self, path: &str) -> Option<MockResponse> {
+        let path = without_query(path);
+        let route = self
+            .routes
+            .iter_mut()
+            .filter(|route| path.ends_with(route.path.as_str()))
+            .max_by_key(|route| route.path.len())?;
+        route.queued.pop_front().or_else(|| route.fallback.clone())
+    }
+}
+
+/// Scriptable OpenAI-compatible server. Stops when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    routes: Arc<Mutex<Routes>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Bind to an ephemeral localhost port and start serving. `v1/models`
+    /// answers with a single model, [`chunk::MODEL`], unless overridden.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let routes = Arc::new(Mutex::new(Routes::default()));
+        routes.lock().unwrap().route("v1/models").fallback =
+            Some(MockResponse::json(serde_json::json!({
+                "object": "list",
+                "data": [{ "id": chunk::MODEL }],
+            })));
+        let task = tokio::spawn({
+            let routes = routes.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(serve(stream, routes.clone()));
+                }
+            }
+        });
+        Self { addr, routes, task }
+    }
+
+    /// Base URL to hand to [`Client::new`](crate::Client::new).
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Queue a one-shot response for requests whose path ends with `path`
+    /// (e.g. `"v1/chat/completions"`). Responses are served in FIFO order.
+    pub fn enqueue(&self, path: &str, response: MockResponse) {
+        let mut routes = self.routes.lock().unwrap();
+        routes.route(path).queued.push_back(response);
+    }
+
+    /// Response served for `path` whenever its queue is empty.
+    pub fn set_fallback(&self, path: &str, response: MockResponse) {
+        let mut routes = self.routes.lock().unwrap();
+        routes.route(path).fallback = Some(response);
+    }
+
+    /// All requests received so far, in arrival order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.routes.lock().unwrap().requests.clone()
+    }
+
+    /// Requests received so far whose path ends with `path`, ignoring the
+    /// query string.
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|r| without_query(&r.path).ends_with(path))
+            .collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn without_query(path: &str) -> &str {
+    path.split_once('?').map_or(path, |(path, _)| path)
+}
+
+async fn serve(stream: TcpStream, routes: Arc<Mutex<Routes>>) {
+    let mut reader = BufReader::new(stream);
+    let Some(request) = read_request(&mut reader).await else {
+        return;
+    };
+    let response = {
+        let mut routes = routes.lock().unwrap();
+        let response = routes.next(&request.path);
+        routes.requests.push(request);
+        response
+    };
+    let response = response
+        .unwrap_or_else(|| MockResponse::error(404, "not_found", "no mock response scripted"));
+    let _ = write_response(reader.get_mut(), response).await;
+}
+
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Option<RecordedRequest> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.ok()?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = header.split_once(':') {
+            headers.push((k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+
+    let content_length = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+async fn write_response(stream: &mut TcpStream, response: MockResponse) -> std::io::Result<()> {
+    let MockResponse {
+        status,
+        content_type,
+        body,
+        latency,
+    } = response;
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    match body {
+        MockBody::Full(body) => {
+            let head = format!(
+                "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                reason(status),
+                body.len(),
+            );
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(&body).await?;
+        }
+        MockBody::Events { events, interval } => {
+            // No Content-Length: the body runs until we close the connection.
+            let head = format!(
+                "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+                reason(status),
+            );
+            stream.write_all(head.as_bytes()).await?;
+            stream.flush().await?;
+            for event in events {
+                if !interval.is_zero() {
+                    tokio::time::sleep(interval).await;
+                }
+                let frame = if is_raw_event(&event) {
+                    format!("{event}\n\n")
+                } else {
+                    format!("data: {event}\n\n")
+                };
+                stream.write_all(frame.as_bytes()).await?;
+                stream.flush().await?;
+            }
+        }
+    }
+    stream.shutdown().await
+}
+
+/// Events that already spell out an SSE field (e.g. `event: ping\ndata: {}`)
+/// are written verbatim instead of being wrapped in `data:`.
+fn is_raw_event(event: &str) -> bool {
+    ["event:", "data:", "id:", "retry:", ":"]
+        .iter()
+        .any(|field| event.starts_with(field))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
@@ -0,0 +1,44 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use super::Error;
+use crate::ModelId;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Mp3,
+    Opus,
+    Aac,
+    Flac,
+    #[default]
+    Wav,
+    /// Raw 24kHz 16-bit signed little-endian samples, without a header.
+    Pcm,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+pub struct SpeechRequest {
+    /// ID of the model to use, e.g. `gpt-4o-mini-tts`.
+    pub model: ModelId,
+    /// The text to generate audio for.
+    pub input: String,
+    /// The voice to use, e.g. `alloy`.
+    pub voice: String,
+    /// Control the voice with additional instructions. Not supported by
+    /// `tts-1` or `tts-1-hd`.
+    pub instructions: Option<String>,
+    /// Defaults to mp3.
+    pub response_format: Option<AudioFormat>,
+    /// Between 0.25 and 4.0. Defaults to 1.0.
+    pub speed: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ErrorResponse {
+    pub error: Error,
+}
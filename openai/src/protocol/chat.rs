@@ -210,8 +210,29 @@ pub struct Message {
     pub tool_calls: Option<Vec<ToolCall>>,
     /// Set on `role: tool` messages that return a tool's output to the model.
     pub tool_call_id: Option<String>,
+    /// Reasoning returned alongside the answer by DeepSeek-style servers
+    /// (llama.cpp, vLLM). Never sent back; these servers reject it in the
+    /// input. Read through [`Message::take_reasoning`].
+    #[serde(skip_serializing)]
+    pub reasoning_content: Option<String>,
+    /// OpenRouter's name for [`Message::reasoning_content`]. Some servers send
+    /// both keys.
+    #[serde(skip_serializing)]
+    pub reasoning: Option<String>,
 }
 impl Message {
+    /// Reasoning under either key, preferring `reasoning_content`.
+    pub fn reasoning_text(&self) -> Option<&str> {
+        self.reasoning_content
+            .as_deref()
+            .or(self.reasoning.as_deref())
+    }
+    pub fn take_reasoning(&mut self) -> Option<String> {
+        let reasoning = self.reasoning.take();
+        self.reasoning_content.take().or(reasoning)
+    }
+    /// Assumes `cl100k_base`. See [`Tokenizer`](crate::chat::Tokenizer) for
+    /// model-aware counts.
     pub fn estimate_tokens(&self) -> u32 {
         // https://platform.openai.com/docs/guides/text-generation/managing-tokens
         if let Some(content) = &self.content {
@@ -240,6 +261,8 @@ impl Default for Message {
             name: None,
             tool_calls: None,
             tool_call_id: None,
+            reasoning_content: None,
+            reasoning: None,
         }
     }
 }
@@ -252,6 +275,24 @@ pub struct PartialMessage {
     pub content: Option<String>,
     #[serde(default)]
     pub tool_calls: Option<Vec<PartialToolCall>>,
+    /// Reasoning fragment. See [`Message::reasoning_content`].
+    #[serde(default)]
+    pub reasoning_content: Option<String>,
+    /// See [`Message::reasoning`].
+    #[serde(default)]
+    pub reasoning: Option<String>,
+}
+impl PartialMessage {
+    /// Reasoning under either key, preferring `reasoning_content`.
+    pub fn reasoning_text(&self) -> Option<&str> {
+        self.reasoning_content
+            .as_deref()
+            .or(self.reasoning.as_deref())
+    }
+    pub fn take_reasoning(&mut self) -> Option<String> {
+        let reasoning = self.reasoning.take();
+        self.reasoning_content.take().or(reasoning)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -262,7 +303,7 @@ pub struct Choice {
     // index
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(default)]
 pub struct CompletionTokensDetails {
     pub accepted_prediction_tokens: u32,
@@ -271,14 +312,14 @@ pub struct CompletionTokensDetails {
     pub rejected_prediction_tokens: u32,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(default)]
 pub struct PromptTokensDetails {
     pub audio_tokens: u32,
     pub cached_tokens: u32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
@@ -0,0 +1,310 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use super::{chat, untagged_ok_result, Error, Result};
+use crate::ModelId;
+
+/// Marks a prefix of the prompt as cacheable.
+/// https://docs.anthropic.com/en/docs/build-with-claude/prompt-caching
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CacheControl {
+    Ephemeral,
+}
+
+/// https://docs.anthropic.com/en/api/messages#body-messages-content
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+        cache_control: Option<CacheControl>,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        is_error: Option<bool>,
+    },
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
+    /// Block types this crate does not model (e.g. server tool results).
+    #[serde(other)]
+    Unknown,
+}
+
+impl ContentBlock {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text {
+            text: text.into(),
+            cache_control: None,
+        }
+    }
+    /// Set a cache breakpoint on this block, if the block type supports one.
+    pub fn set_cache_control(&mut self, cache: CacheControl) {
+        if let Self::Text { cache_control, .. } = self {
+            *cache_control = Some(cache);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, IntoStaticStr, EnumIter)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Message {
+    pub role: Role,
+    pub content: Vec<ContentBlock>,
+}
+
+/// https://docs.anthropic.com/en/docs/build-with-claude/tool-use
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tool {
+    pub name: String,
+    pub description: Option<String>,
+    pub input_schema: serde_json::Value,
+}
+
+impl From<chat::Tool> for Tool {
+    fn from(tool: chat::Tool) -> Self {
+        let chat::Tool::Function { function } = tool;
+        Self {
+            name: function.name,
+            description: function.description,
+            input_schema: function
+                .parameters
+                .unwrap_or_else(|| serde_json::json!({ "type": "object" })),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    Auto,
+    Any,
+    Tool { name: String },
+    None,
+}
+
+impl From<chat::ToolChoice> for ToolChoice {
+    fn from(choice: chat::ToolChoice) -> Self {
+        match choice {
+            chat::ToolChoice::Mode(chat::ToolChoiceMode::None) => Self::None,
+            chat::ToolChoice::Mode(chat::ToolChoiceMode::Auto) => Self::Auto,
+            chat::ToolChoice::Mode(chat::ToolChoiceMode::Required) => Self::Any,
+            chat::ToolChoice::Function { function, .. } => Self::Tool {
+                name: function.name,
+            },
+        }
+    }
+}
+
+/// Extended thinking configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Thinking {
+    Enabled { budget_tokens: u32 },
+    Disabled,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+pub struct Request {
+    /// ID of the model to use.
+    pub model: ModelId,
+    /// Alternating user and assistant turns. Consecutive turns with the same
+    /// role are merged by the server.
+    pub messages: Vec<Message>,
+    /// System prompt. Unlike Chat Completions, this is a top-level parameter
+    /// rather than a message.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub system: Vec<ContentBlock>,
+    /// The maximum number of tokens to generate before stopping. Required.
+    pub max_tokens: u32,
+    /// Amount of randomness injected into the response, between 0 and 1.
+    pub temperature: Option<f32>,
+    /// Use nucleus sampling. You should either alter temperature or top_p,
+    /// but not both.
+    pub top_p: Option<f32>,
+    /// Only sample from the top K options for each subsequent token.
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub stop_sequences: Vec<String>,
+    /// Tools the model may use.
+    pub tools: Option<Vec<Tool>>,
+    /// How the model should use the provided tools.
+    pub tool_choice: Option<ToolChoice>,
+    /// Configuration for extended thinking.
+    pub thinking: Option<Thinking>,
+    pub(crate) stream: Option<bool>,
+}
+
+/// Token usage. Input tokens exclude tokens read from or written to the
+/// prompt cache, which are reported separately.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Usage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_creation_input_tokens: Option<u32>,
+    pub cache_read_input_tokens: Option<u32>,
+}
+
+impl Usage {
+    /// Fold a later usage report (e.g. from `message_delta`) into this one.
+    /// Counts in later reports are cumulative, so non-zero values replace.
+    pub fn merge(&mut self, other: &Usage) {
+        if other.input_tokens != 0 {
+            self.input_tokens = other.input_tokens;
+        }
+        if other.output_tokens != 0 {
+            self.output_tokens = other.output_tokens;
+        }
+        if other.cache_creation_input_tokens.is_some() {
+            self.cache_creation_input_tokens = other.cache_creation_input_tokens;
+        }
+        if other.cache_read_input_tokens.is_some() {
+            self.cache_read_input_tokens = other.cache_read_input_tokens;
+        }
+    }
+}
+
+impl From<Usage> for chat::Usage {
+    /// Express in Chat Completions terms: cache reads and writes count
+    /// towards prompt tokens, and cache reads are reported as cached tokens.
+    fn from(usage: Usage) -> Self {
+        let cache_read = usage.cache_read_input_tokens.unwrap_or_default();
+        let cache_write = usage.cache_creation_input_tokens.unwrap_or_default();
+        let prompt_tokens = usage.input_tokens + cache_read + cache_write;
+        Self {
+            prompt_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: prompt_tokens + usage.output_tokens,
+            completion_tokens_details: None,
+            prompt_tokens_details: Some(chat::PromptTokensDetails {
+                audio_tokens: 0,
+                cached_tokens: cache_read,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageResponse {
+    pub id: String,
+    pub model: ModelId,
+    pub role: Role,
+    #[serde(default)]
+    pub content: Vec<ContentBlock>,
+    pub stop_reason: Option<String>,
+    pub stop_sequence: Option<String>,
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+impl MessageResponse {
+    /// Concatenated text of all text blocks.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Concatenated extended thinking. Redacted blocks are skipped.
+    pub fn thinking(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Thinking { thinking, .. } => Some(thinking.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct MessagesResponse(
+    #[serde(deserialize_with = "untagged_ok_result::deserialize")] pub Result<MessageResponse>,
+);
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Delta {
+    TextDelta {
+        text: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    SignatureDelta {
+        signature: String,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct MessageDelta {
+    pub stop_reason: Option<String>,
+    pub stop_sequence: Option<String>,
+}
+
+/// https://docs.anthropic.com/en/docs/build-with-claude/streaming#event-types
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    MessageStart {
+        message: MessageResponse,
+    },
+    ContentBlockStart {
+        index: u32,
+        content_block: ContentBlock,
+    },
+    ContentBlockDelta {
+        index: u32,
+        delta: Delta,
+    },
+    ContentBlockStop {
+        index: u32,
+    },
+    MessageDelta {
+        delta: MessageDelta,
+        #[serde(default)]
+        usage: Usage,
+    },
+    MessageStop,
+    Ping,
+    Error {
+        error: Error,
+    },
+    #[serde(other)]
+    Unknown,
+}
@@ -2,13 +2,17 @@ use serde::{Deserialize, Serialize};
 use strum_macros::{EnumIter, IntoStaticStr};
 use thiserror::Error;
 
+pub mod audio;
 pub mod chat;
+pub mod messages;
+pub mod responses;
 
 #[derive(Error, Debug, Clone, Deserialize, PartialEq, Eq)]
 #[error("{error_type}: {message} (param={param:?}, code={code:?}, event_id={event_id:?})")]
 pub struct Error {
     pub message: String,
-    #[serde(rename = "type")]
+    /// Absent on errors embedded in a failed Responses API object.
+    #[serde(rename = "type", default)]
     pub error_type: String,
     pub param: Option<String>,
     pub code: Option<String>,
@@ -0,0 +1,414 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use super::{chat, untagged_ok_result, Error, Result};
+use crate::{
+    protocol::{ReasoningEffort, Role, ServiceTier, Verbosity},
+    ModelId,
+};
+
+/// https://platform.openai.com/docs/api-reference/responses/create#responses-create-input
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputItem {
+    Message {
+        role: Role,
+        content: String,
+    },
+    /// A tool call previously emitted by the model. Only needed when not
+    /// chaining with `previous_response_id`.
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+    /// The output of a function tool call, correlated by `call_id`.
+    FunctionCallOutput {
+        call_id: String,
+        output: String,
+    },
+}
+
+impl InputItem {
+    pub fn message(role: Role, content: impl Into<String>) -> Self {
+        Self::Message {
+            role,
+            content: content.into(),
+        }
+    }
+}
+
+/// Function tools are flattened compared to Chat Completions. The remaining
+/// variants are built-in tools executed by the server.
+/// https://platform.openai.com/docs/guides/tools
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Tool {
+    Function {
+        name: String,
+        description: Option<String>,
+        parameters: Option<serde_json::Value>,
+        strict: Option<bool>,
+    },
+    WebSearch {
+        search_context_size: Option<SearchContextSize>,
+    },
+    FileSearch {
+        vector_store_ids: Vec<String>,
+        max_num_results: Option<u32>,
+    },
+    CodeInterpreter {
+        container: serde_json::Value,
+    },
+}
+
+impl From<chat::Tool> for Tool {
+    fn from(tool: chat::Tool) -> Self {
+        let chat::Tool::Function { function } = tool;
+        Self::Function {
+            name: function.name,
+            description: function.description,
+            parameters: function.parameters,
+            strict: function.strict,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchContextSize {
+    Low,
+    Medium,
+    High,
+}
+
+/// See https://platform.openai.com/docs/api-reference/responses/create#responses-create-tool_choice
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    Mode(chat::ToolChoiceMode),
+    Function {
+        #[serde(rename = "type")]
+        kind: chat::ToolCallKind,
+        name: String,
+    },
+}
+
+impl From<chat::ToolChoice> for ToolChoice {
+    fn from(choice: chat::ToolChoice) -> Self {
+        match choice {
+            chat::ToolChoice::Mode(mode) => Self::Mode(mode),
+            chat::ToolChoice::Function { kind, function } => Self::Function {
+                kind,
+                name: function.name,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, IntoStaticStr, EnumIter)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningSummary {
+    Auto,
+    Concise,
+    Detailed,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct Reasoning {
+    pub effort: Option<ReasoningEffort>,
+    /// Ask the model to emit a summary of its reasoning. Raw reasoning is
+    /// never returned.
+    pub summary: Option<ReasoningSummary>,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct TextConfig {
+    pub verbosity: Option<Verbosity>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Truncation {
+    /// Drop items from the start of the conversation when the context window
+    /// overflows.
+    Auto,
+    /// Fail the request when the context window overflows.
+    Disabled,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+pub struct Request {
+    /// ID of the model to use.
+    pub model: ModelId,
+    /// New input items for this turn. When chaining with
+    /// `previous_response_id`, only the new items are sent.
+    pub input: Vec<InputItem>,
+    /// System (developer) message. Not carried over by
+    /// `previous_response_id`, so it must be sent on every request.
+    pub instructions: Option<String>,
+    /// Continue the conversation from a stored response. The server prepends
+    /// that response's input and output to this request's input.
+    pub previous_response_id: Option<String>,
+    /// Whether to store the response server-side so it can be chained.
+    /// Defaults to true.
+    pub store: Option<bool>,
+    /// What sampling temperature to use, between 0 and 2.
+    pub temperature: Option<f32>,
+    /// Nucleus sampling. Alter this or temperature but not both.
+    pub top_p: Option<f32>,
+    /// Upper bound for generated tokens, including reasoning tokens.
+    pub max_output_tokens: Option<u32>,
+    /// Specifies the processing type used for serving the request.
+    pub service_tier: Option<ServiceTier>,
+    /// Configuration for reasoning models.
+    pub reasoning: Option<Reasoning>,
+    /// Configuration for text output.
+    pub text: Option<TextConfig>,
+    /// Function and built-in tools the model may use.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub tools: Vec<Tool>,
+    /// How the model should select tools.
+    pub tool_choice: Option<ToolChoice>,
+    /// Whether the model may emit multiple tool calls in a single turn.
+    pub parallel_tool_calls: Option<bool>,
+    /// Overflow strategy for long conversations.
+    pub truncation: Option<Truncation>,
+    pub(crate) stream: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputContent {
+    OutputText {
+        text: String,
+    },
+    Refusal {
+        refusal: String,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SummaryPart {
+    SummaryText {
+        text: String,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// https://platform.openai.com/docs/api-reference/responses/object#responses/object-output
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputItem {
+    Message {
+        id: String,
+        #[serde(default)]
+        content: Vec<OutputContent>,
+        status: Option<String>,
+    },
+    Reasoning {
+        id: String,
+        #[serde(default)]
+        summary: Vec<SummaryPart>,
+    },
+    FunctionCall {
+        id: Option<String>,
+        call_id: String,
+        name: String,
+        #[serde(default)]
+        arguments: String,
+        status: Option<String>,
+    },
+    WebSearchCall {
+        id: String,
+        status: Option<String>,
+    },
+    FileSearchCall {
+        id: String,
+        status: Option<String>,
+        #[serde(default)]
+        queries: Vec<String>,
+    },
+    /// Item types this crate does not model (e.g. code interpreter calls).
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct InputTokensDetails {
+    pub cached_tokens: u32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OutputTokensDetails {
+    pub reasoning_tokens: u32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+    pub input_tokens_details: InputTokensDetails,
+    pub output_tokens_details: OutputTokensDetails,
+}
+
+impl From<Usage> for chat::Usage {
+    fn from(usage: Usage) -> Self {
+        Self {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: usage.total_tokens,
+            completion_tokens_details: Some(chat::CompletionTokensDetails {
+                reasoning_tokens: usage.output_tokens_details.reasoning_tokens,
+                ..Default::default()
+            }),
+            prompt_tokens_details: Some(chat::PromptTokensDetails {
+                audio_tokens: 0,
+                cached_tokens: usage.input_tokens_details.cached_tokens,
+            }),
+        }
+    }
+}
+
+/// https://platform.openai.com/docs/api-reference/responses/object
+#[derive(Debug, Clone, Deserialize)]
+pub struct Response {
+    pub id: String,
+    pub model: ModelId,
+    /// `completed`, `failed`, `in_progress`, `cancelled`, `queued` or
+    /// `incomplete`.
+    pub status: Option<String>,
+    #[serde(default)]
+    pub output: Vec<OutputItem>,
+    pub usage: Option<Usage>,
+    pub error: Option<Error>,
+    pub previous_response_id: Option<String>,
+}
+
+impl Response {
+    /// Concatenated text of all `output_text` parts.
+    pub fn output_text(&self) -> String {
+        self.output
+            .iter()
+            .filter_map(|item| match item {
+                OutputItem::Message { content, .. } => Some(content),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                OutputContent::OutputText { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Concatenated reasoning summaries.
+    pub fn reasoning_summary(&self) -> String {
+        self.output
+            .iter()
+            .filter_map(|item| match item {
+                OutputItem::Reasoning { summary, .. } => Some(summary),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                SummaryPart::SummaryText { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ResponsesResponse(
+    #[serde(deserialize_with = "untagged_ok_result::deserialize")] pub Result<Response>,
+);
+
+/// https://platform.openai.com/docs/api-reference/responses-streaming
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+pub enum StreamEvent {
+    #[serde(rename = "response.created")]
+    Created { response: Box<Response> },
+    #[serde(rename = "response.in_progress")]
+    InProgress { response: Box<Response> },
+    #[serde(rename = "response.completed")]
+    Completed { response: Box<Response> },
+    #[serde(rename = "response.incomplete")]
+    Incomplete { response: Box<Response> },
+    #[serde(rename = "response.failed")]
+    Failed { response: Box<Response> },
+    #[serde(rename = "response.output_item.added")]
+    OutputItemAdded { output_index: u32, item: OutputItem },
+    #[serde(rename = "response.output_item.done")]
+    OutputItemDone { output_index: u32, item: OutputItem },
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta {
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.output_text.done")]
+    OutputTextDone {
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        text: String,
+    },
+    #[serde(rename = "response.refusal.delta")]
+    RefusalDelta {
+        item_id: String,
+        output_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.function_call_arguments.delta")]
+    FunctionCallArgumentsDelta {
+        item_id: String,
+        output_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.function_call_arguments.done")]
+    FunctionCallArgumentsDone {
+        item_id: String,
+        output_index: u32,
+        arguments: String,
+    },
+    #[serde(rename = "response.reasoning_summary_text.delta")]
+    ReasoningSummaryTextDelta {
+        item_id: String,
+        output_index: u32,
+        summary_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.reasoning_summary_text.done")]
+    ReasoningSummaryTextDone {
+        item_id: String,
+        output_index: u32,
+        summary_index: u32,
+        text: String,
+    },
+    #[serde(rename = "error")]
+    Error {
+        code: Option<String>,
+        message: String,
+        param: Option<String>,
+    },
+    #[serde(other)]
+    Unknown,
+}
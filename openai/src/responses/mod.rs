@@ -0,0 +1,336 @@
+//! https://platform.openai.com/docs/api-reference/responses
+//!
+//! The Responses API. Conversation state can be kept server-side by chaining
+//! requests with `previous_response_id`.
+
+use eventsource_stream::Eventsource;
+use reqwest::Method;
+use tokio_stream::{Stream, StreamExt};
+use tracing::Level;
+
+pub use crate::protocol::responses::{
+    InputItem, InputTokensDetails, OutputContent, OutputItem, OutputTokensDetails, Reasoning,
+    ReasoningSummary, Request, Response, SearchContextSize, StreamEvent, SummaryPart, TextConfig,
+    Tool, ToolChoice, Truncation, Usage,
+};
+
+use crate::{
+    chat,
+    protocol::{self, responses::ResponsesResponse, Role},
+    Client, Error,
+};
+
+/// Convert a Chat Completions transcript into Responses API instructions
+/// and input items.
+///
+/// - `system` messages are joined into `instructions`.
+/// - Assistant tool calls become `function_call` items.
+/// - `role: tool` replies become `function_call_output` items.
+/// - `name` has no equivalent and is dropped.
+pub fn from_chat_messages(messages: Vec<chat::Message>) -> (Option<String>, Vec<InputItem>) {
+    let mut instructions: Vec<String> = Vec::new();
+    let mut input = Vec::with_capacity(messages.len());
+    for message in messages {
+        match message.role {
+            chat::Role::System => instructions.extend(message.content),
+            chat::Role::User => {
+                if let Some(content) = message.content {
+                    input.push(InputItem::message(Role::User, content));
+                }
+            }
+            chat::Role::Assistant => {
+                if let Some(content) = message.content {
+                    input.push(InputItem::message(Role::Assistant, content));
+                }
+                for call in message.tool_calls.unwrap_or_default() {
+                    input.push(InputItem::FunctionCall {
+                        call_id: call.id,
+                        name: call.function.name,
+                        arguments: call.function.arguments,
+                    });
+                }
+            }
+            chat::Role::Tool => input.push(InputItem::FunctionCallOutput {
+                call_id: message.tool_call_id.unwrap_or_default(),
+                output: message.content.unwrap_or_default(),
+            }),
+        }
+    }
+    let instructions = (!instructions.is_empty()).then(|| instructions.join("\n\n"));
+    (instructions, input)
+}
+
+impl Client {
+    #[tracing::instrument(level = Level::DEBUG, skip_all, err)]
+    pub async fn responses(&self, mut request: Request) -> Result<Response, Error> {
+        request.stream = None;
+        tracing::debug!(?request);
+        let response: ResponsesResponse = self
+            .shared
+            .request(Method::POST, "v1/responses")
+            .body(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+        tracing::debug!(?response);
+        Ok(response.0?)
+    }
+
+    #[tracing::instrument(level = Level::DEBUG, skip_all, err)]
+    pub async fn responses_stream(
+        &self,
+        mut request: Request,
+    ) -> Result<impl Stream<Item = Result<StreamEvent, Error>>, Error> {
+        request.stream = Some(true);
+        tracing::debug!(?request);
+        let response = self
+            .shared
+            .request(Method::POST, "v1/responses")
+            .body(&request)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            // HTTP success: Expect SSE response. There is no `[DONE]`
+            // sentinel; the stream is over once the response reaches a
+            // terminal state.
+            let mut done = false;
+            let stream = response.bytes_stream().eventsource();
+            Ok(stream.map_while(move |event| {
+                tracing::trace!(?event);
+                if done {
+                    return None;
+                }
+                match event {
+                    Ok(event) => {
+                        let response = match serde_json::from_str::<StreamEvent>(&event.data) {
+                            Ok(StreamEvent::Error {
+                                code,
+                                message,
+                                param,
+                            }) => {
+                                tracing::error!(?code, ?message);
+                                done = true;
+                                Err(Error::Protocol(protocol::Error {
+                                    message,
+                                    error_type: code.clone().unwrap_or_else(|| "error".into()),
+                                    param,
+                                    code,
+                                    event_id: None,
+                                }))
+                            }
+                            Ok(StreamEvent::Failed { response }) => {
+                                tracing::error!(?response.error);
+                                done = true;
+                                Err(Error::Protocol(response.error.unwrap_or_else(|| {
+                                    protocol::Error {
+                                        message: "response failed".into(),
+                                        error_type: "response_failed".into(),
+                                        param: None,
+                                        code: None,
+                                        event_id: None,
+                                    }
+                                })))
+                            }
+                            Ok(response) => {
+                                tracing::debug!(?response);
+                                done = matches!(
+                                    response,
+                                    StreamEvent::Completed { .. } | StreamEvent::Incomplete { .. }
+                                );
+                                Ok(response)
+                            }
+                            Err(err) => {
+                                // Serde error
+                                tracing::error!(?err, ?event.data);
+                                Err(err.into())
+                            }
+                        };
+                        Some(response)
+                    }
+                    Err(err) => {
+                        // SSE error
+                        tracing::error!(?err);
+                        Some(Err(err.into()))
+                    }
+                }
+            }))
+        } else {
+            // HTTP error: Expect JSON response
+            let response_err = response.error_for_status_ref().unwrap_err();
+            let responses_response = response.json::<ResponsesResponse>().await;
+            match responses_response {
+                Ok(ResponsesResponse(Err(err))) => {
+                    // OpenAI application error
+                    Err(Error::Protocol(err))
+                }
+                Ok(ResponsesResponse(Ok(_))) | Err(_) => {
+                    // Not application error, return HTTP error
+                    tracing::error!(?response_err, "unexpected stream response");
+                    Err(response_err.into())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_transcript_converts_to_input_items() {
+        let assistant = chat::Message {
+            role: chat::Role::Assistant,
+            tool_calls: Some(vec![chat::ToolCall {
+                id: "call_a".into(),
+                kind: chat::ToolCallKind::Function,
+                function: chat::FunctionCall {
+                    name: "lookup".into(),
+                    arguments: "{\"term\":\"猫\"}".into(),
+                },
+            }]),
+            ..Default::default()
+        };
+        let (instructions, input) = from_chat_messages(vec![
+            chat::Message {
+                role: chat::Role::System,
+                content: Some("Translate into English.".into()),
+                ..Default::default()
+            },
+            chat::Message {
+                role: chat::Role::User,
+                content: Some("猫".into()),
+                ..Default::default()
+            },
+            assistant,
+            chat::Message::tool_result("call_a", "cat"),
+        ]);
+        assert_eq!(instructions.as_deref(), Some("Translate into English."));
+        assert_eq!(input.len(), 3);
+        let json = serde_json::to_value(&input).unwrap();
+        assert_eq!(json[0]["type"], "message");
+        assert_eq!(json[0]["role"], "user");
+        assert_eq!(json[1]["type"], "function_call");
+        assert_eq!(json[1]["call_id"], "call_a");
+        assert_eq!(json[2]["type"], "function_call_output");
+        assert_eq!(json[2]["output"], "cat");
+    }
+
+    #[test]
+    fn request_serializes_chaining_and_tools() {
+        let request = Request::builder()
+            .model(crate::ModelId("gpt-5-mini".into()))
+            .input(vec![InputItem::message(Role::User, "こんにちは")])
+            .instructions("Translate.".into())
+            .previous_response_id("resp_1".into())
+            .reasoning(Reasoning {
+                effort: None,
+                summary: Some(ReasoningSummary::Auto),
+            })
+            .tools(vec![
+                Tool::WebSearch {
+                    search_context_size: None,
+                },
+                chat::Tool::function(chat::FunctionDef {
+                    name: "lookup".into(),
+                    description: None,
+                    parameters: None,
+                    strict: None,
+                })
+                .into(),
+            ])
+            .build();
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["previous_response_id"], "resp_1");
+        assert_eq!(json["instructions"], "Translate.");
+        assert_eq!(json["reasoning"]["summary"], "auto");
+        assert!(json["reasoning"].get("effort").is_none());
+        assert_eq!(
+            json["tools"][0],
+            serde_json::json!({ "type": "web_search" })
+        );
+        assert_eq!(json["tools"][1]["type"], "function");
+        assert_eq!(json["tools"][1]["name"], "lookup");
+        assert!(json.get("stream").is_none());
+        assert!(json.get("temperature").is_none());
+    }
+
+    #[test]
+    fn chat_tool_choice_converts() {
+        let choice: ToolChoice = chat::ToolChoice::function("lookup").into();
+        let json = serde_json::to_value(choice).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "type": "function", "name": "lookup" })
+        );
+    }
+
+    #[test]
+    fn deserialize_response_object() {
+        let raw = r#"{
+            "id": "resp_1", "object": "response", "created_at": 1, "status": "completed",
+            "error": null, "incomplete_details": null, "model": "gpt-5-mini",
+            "previous_response_id": null,
+            "output": [
+                {"id": "rs_1", "type": "reasoning",
+                 "summary": [{"type": "summary_text", "text": "Casual greeting."}]},
+                {"id": "ws_1", "type": "web_search_call", "status": "completed"},
+                {"id": "ci_1", "type": "code_interpreter_call", "status": "completed"},
+                {"id": "msg_1", "type": "message", "status": "completed", "role": "assistant",
+                 "content": [{"type": "output_text", "text": "Hello.", "annotations": []}]},
+                {"id": "fc_1", "type": "function_call", "status": "completed",
+                 "call_id": "call_1", "name": "lookup", "arguments": "{}"}
+            ],
+            "usage": {"input_tokens": 30, "input_tokens_details": {"cached_tokens": 16},
+                      "output_tokens": 12, "output_tokens_details": {"reasoning_tokens": 8},
+                      "total_tokens": 42}
+        }"#;
+        let response: Response = serde_json::from_str(raw).unwrap();
+        assert_eq!(response.output_text(), "Hello.");
+        assert_eq!(response.reasoning_summary(), "Casual greeting.");
+        assert!(matches!(response.output[2], OutputItem::Unknown));
+        assert!(matches!(
+            &response.output[4],
+            OutputItem::FunctionCall { call_id, .. } if call_id == "call_1"
+        ));
+        let usage: chat::Usage = response.usage.unwrap().into();
+        assert_eq!(usage.total_tokens, 42);
+        assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, 16);
+        assert_eq!(usage.completion_tokens_details.unwrap().reasoning_tokens, 8);
+    }
+
+    #[test]
+    fn deserialize_stream_events() {
+        let delta = r#"{"type":"response.output_text.delta","sequence_number":4,
+            "item_id":"msg_1","output_index":1,"content_index":0,"delta":"Hel"}"#;
+        let StreamEvent::OutputTextDelta { delta, .. } = serde_json::from_str(delta).unwrap()
+        else {
+            panic!("expected output_text delta");
+        };
+        assert_eq!(delta, "Hel");
+
+        let summary = r#"{"type":"response.reasoning_summary_text.delta","item_id":"rs_1",
+            "output_index":0,"summary_index":0,"delta":"Thinking"}"#;
+        assert!(matches!(
+            serde_json::from_str(summary).unwrap(),
+            StreamEvent::ReasoningSummaryTextDelta { .. }
+        ));
+
+        let failed = r#"{"type":"response.failed","response":{"id":"resp_1",
+            "model":"gpt-5-mini","status":"failed","output":[],
+            "error":{"code":"server_error","message":"The model failed."}}}"#;
+        let StreamEvent::Failed { response } = serde_json::from_str(failed).unwrap() else {
+            panic!("expected failure");
+        };
+        assert_eq!(
+            response.error.unwrap().code.as_deref(),
+            Some("server_error")
+        );
+
+        let unknown: StreamEvent =
+            serde_json::from_str(r#"{"type":"response.content_part.added"}"#).unwrap();
+        assert!(matches!(unknown, StreamEvent::Unknown));
+    }
+}
@@ -0,0 +1,533 @@
+//! Offline tests for the API clients against [`MockServer`].
+//!
+//! Unlike `tests/chat.rs`, these always run: responses are scripted, so they
+//! cover SSE parsing, tool-call streaming and error paths deterministically.
+//! Requires the `mock` feature (`cargo test -p openai --features mock`).
+
+use std::time::Duration;
+
+use openai::{
+    chat::{Message, Request, Role, ToolCallAccumulator},
+    mock::{chunk, MockResponse, MockServer},
+    Client, ModelId,
+};
+use tokio_stream::StreamExt;
+
+const CHAT: &str = "v1/chat/completions";
+
+fn client(server: &MockServer) -> Client {
+    Client::new("sk-mock", server.url(), Default::default())
+}
+
+fn request() -> Request {
+    Request::builder()
+        .model(ModelId(chunk::MODEL.into()))
+        .messages(vec![Message {
+            role: Role::User,
+            content: Some("こんにちは".into()),
+            ..Default::default()
+        }])
+        .build()
+}
+
+#[tokio::test]
+async fn models_lists_fallback() {
+    let server = MockServer::start().await;
+    let models = client(&server).models().await.unwrap();
+    assert_eq!(models, vec![ModelId(chunk::MODEL.into())]);
+}
+
+#[tokio::test]
+async fn chat_returns_completion_and_sends_request() {
+    let server = MockServer::start().await;
+    server.enqueue(CHAT, MockResponse::completion("Hello."));
+    let completion = client(&server).chat(request()).await.unwrap();
+    assert_eq!(
+        completion.choices[0].message.content.as_deref(),
+        Some("Hello.")
+    );
+    assert_eq!(completion.usage.total_tokens, 15);
+
+    let requests = server.requests_to(CHAT);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].header("authorization"), Some("Bearer sk-mock"));
+    let body = requests[0].json();
+    assert_eq!(body["model"], chunk::MODEL);
+    assert_eq!(body["messages"][0]["content"], "こんにちは");
+    assert!(body.get("stream").is_none());
+}
+
+#[tokio::test]
+async fn chat_surfaces_application_error() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::error(400, "invalid_request_error", "bad model"),
+    );
+    match client(&server).chat(request()).await {
+        Err(openai::Error::Protocol(err)) => {
+            assert_eq!(err.error_type, "invalid_request_error");
+            assert_eq!(err.message, "bad model");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn stream_accumulates_content_and_usage() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::stream([
+            chunk::role(),
+            chunk::content("Hello"),
+            chunk::content(", world."),
+            chunk::finish("stop"),
+            chunk::usage(12, 3),
+        ]),
+    );
+    let mut stream = client(&server).stream(request()).await.unwrap();
+    let mut content = String::new();
+    let mut finish = None;
+    let mut usage = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        if let Some(u) = chunk.usage {
+            usage = Some(u);
+        }
+        for choice in chunk.choices {
+            if let Some(delta) = choice.delta.content {
+                content.push_str(&delta);
+            }
+            if choice.finish_reason.is_some() {
+                finish = choice.finish_reason;
+            }
+        }
+    }
+    assert_eq!(content, "Hello, world.");
+    assert_eq!(finish.as_deref(), Some("stop"));
+    let usage = usage.unwrap();
+    assert_eq!(usage.prompt_tokens, 12);
+    assert_eq!(usage.total_tokens, 15);
+
+    let body = server.requests_to(CHAT)[0].json();
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["stream_options"]["include_usage"], true);
+}
+
+#[tokio::test]
+async fn stream_accumulates_interleaved_tool_calls() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::stream([
+            chunk::tool_call(0, "call_a", "get_weather", ""),
+            chunk::tool_call(1, "call_b", "get_time", ""),
+            chunk::tool_arguments(0, "{\"loc"),
+            chunk::tool_arguments(1, "{\"tz\":\"JST\"}"),
+            chunk::tool_arguments(0, "ation\":\"Tokyo\"}"),
+            chunk::finish("tool_calls"),
+        ]),
+    );
+    let mut stream = client(&server).stream(request()).await.unwrap();
+    let mut acc = ToolCallAccumulator::new();
+    while let Some(chunk) = stream.next().await {
+        for choice in chunk.unwrap().choices {
+            if let Some(calls) = choice.delta.tool_calls {
+                acc.extend(calls);
+            }
+        }
+    }
+    let calls = acc.finish();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].id, "call_a");
+    assert_eq!(calls[0].function.arguments, "{\"location\":\"Tokyo\"}");
+    assert_eq!(calls[1].function.name, "get_time");
+}
+
+#[tokio::test]
+async fn stream_surfaces_http_error_before_first_event() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::error(429, "rate_limit_exceeded", "slow down"),
+    );
+    match client(&server).stream(request()).await {
+        Err(openai::Error::Protocol(err)) => assert_eq!(err.error_type, "rate_limit_exceeded"),
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("expected error"),
+    }
+}
+
+#[tokio::test]
+async fn stream_surfaces_non_json_http_error() {
+    let server = MockServer::start().await;
+    server.enqueue(CHAT, MockResponse::text(502, "upstream unavailable"));
+    match client(&server).stream(request()).await {
+        Err(openai::Error::Request(err)) => {
+            assert_eq!(err.status().map(|s| s.as_u16()), Some(502))
+        }
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("expected error"),
+    }
+}
+
+#[tokio::test]
+async fn stream_yields_error_on_malformed_event_and_continues() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::events([
+            chunk::content("a").to_string(),
+            "{not json".to_string(),
+            chunk::content("b").to_string(),
+            "[DONE]".to_string(),
+        ]),
+    );
+    let stream = client(&server).stream(request()).await.unwrap();
+    let items: Vec<_> = stream.collect().await;
+    assert_eq!(items.len(), 3);
+    assert!(items[0].is_ok());
+    assert!(matches!(items[1], Err(openai::Error::Serde(_))));
+    assert!(items[2].is_ok());
+}
+
+#[tokio::test]
+async fn stream_ends_without_done_sentinel() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::events([chunk::content("partial").to_string()]),
+    );
+    let stream = client(&server).stream(request()).await.unwrap();
+    let items: Vec<_> = stream.collect().await;
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_ok());
+}
+
+#[tokio::test]
+async fn stream_delivers_slow_chunks_incrementally() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        CHAT,
+        MockResponse::stream([
+            chunk::content("a"),
+            chunk::content("b"),
+            chunk::content("c"),
+        ])
+        .with_interval(Duration::from_millis(100)),
+    );
+    let mut stream = client(&server).stream(request()).await.unwrap();
+    let start = std::time::Instant::now();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.choices[0].delta.content.as_deref(), Some("a"));
+    // The first chunk must arrive before the whole stream has been written.
+    assert!(start.elapsed() < Duration::from_millis(250));
+    let rest: Vec<_> = stream.collect().await;
+    assert_eq!(rest.len(), 2);
+}
+
+#[tokio::test]
+async fn unscripted_route_returns_not_found() {
+    let server = MockServer::start().await;
+    match client(&server).chat(request()).await {
+        Err(openai::Error::Protocol(err)) => assert_eq!(err.error_type, "not_found"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn longest_matching_route_wins() {
+    let server = MockServer::start().await;
+    // Registered first and also a suffix of the chat path.
+    server.set_fallback("completions", MockResponse::completion("Legacy."));
+    server.set_fallback(CHAT, MockResponse::completion("Chat."));
+    for _ in 0..3 {
+        let completion = client(&server).chat(request()).await.unwrap();
+        assert_eq!(
+            completion.choices[0].message.content.as_deref(),
+            Some("Chat.")
+        );
+    }
+}
+
+const MESSAGES: &str = "v1/messages";
+
+fn anthropic(server: &MockServer) -> Client {
+    Client::anthropic("sk-ant-mock", server.url(), Default::default())
+}
+
+fn messages_request() -> openai::messages::Request {
+    openai::messages::Request::builder()
+        .model(ModelId("claude-mock".into()))
+        .messages(vec![openai::messages::Message {
+            role: openai::messages::Role::User,
+            content: vec![openai::messages::ContentBlock::text("こんにちは")],
+        }])
+        .max_tokens(64)
+        .build()
+}
+
+/// SSE event whose `event:` name mirrors the payload's `type`, as sent by
+/// the Messages and Responses APIs.
+fn typed_event(data: serde_json::Value) -> String {
+    format!("event: {}\ndata: {}", data["type"].as_str().unwrap(), data)
+}
+
+#[tokio::test]
+async fn messages_stream_yields_typed_events() {
+    use openai::messages::{ContentBlock, Delta, StreamEvent};
+    use serde_json::json;
+
+    let server = MockServer::start().await;
+    server.enqueue(
+        MESSAGES,
+        MockResponse::events([
+            typed_event(json!({"type": "message_start", "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "content": [],
+                "model": "claude-mock", "stop_reason": null, "stop_sequence": null,
+                "usage": {"input_tokens": 10, "output_tokens": 1, "cache_read_input_tokens": 5}}})),
+            typed_event(json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "text", "text": ""}})),
+            typed_event(json!({"type": "ping"})),
+            typed_event(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": "Hello"}})),
+            typed_event(json!({"type": "content_block_stop", "index": 0})),
+            typed_event(json!({"type": "message_delta",
+                "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+                "usage": {"output_tokens": 2}})),
+            typed_event(json!({"type": "message_stop"})),
+        ]),
+    );
+    let stream = anthropic(&server)
+        .messages_stream(messages_request())
+        .await
+        .unwrap();
+    let events: Vec<_> = stream.collect().await;
+    assert_eq!(events.len(), 7);
+    assert!(matches!(
+        &events[1],
+        Ok(StreamEvent::ContentBlockStart {
+            content_block: ContentBlock::Text { .. },
+            ..
+        })
+    ));
+    assert!(matches!(
+        &events[3],
+        Ok(StreamEvent::ContentBlockDelta { delta: Delta::TextDelta { text }, .. }) if text == "Hello"
+    ));
+    assert!(matches!(events[6], Ok(StreamEvent::MessageStop)));
+
+    let request = &server.requests_to(MESSAGES)[0];
+    assert_eq!(request.header("x-api-key"), Some("sk-ant-mock"));
+    assert!(request.header("anthropic-version").is_some());
+    assert!(request.header("authorization").is_none());
+    assert_eq!(request.json()["stream"], true);
+}
+
+#[tokio::test]
+async fn messages_stream_surfaces_mid_stream_error() {
+    use serde_json::json;
+
+    let server = MockServer::start().await;
+    server.enqueue(
+        MESSAGES,
+        MockResponse::events([
+            typed_event(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": "Hel"}})),
+            typed_event(json!({"type": "error",
+                "error": {"type": "overloaded_error", "message": "Overloaded"}})),
+            typed_event(json!({"type": "ping"})),
+        ]),
+    );
+    let stream = anthropic(&server)
+        .messages_stream(messages_request())
+        .await
+        .unwrap();
+    let events: Vec<_> = stream.collect().await;
+    assert_eq!(events.len(), 2);
+    match &events[1] {
+        Err(openai::Error::Protocol(err)) => assert_eq!(err.error_type, "overloaded_error"),
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn messages_returns_response() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        MESSAGES,
+        MockResponse::json(serde_json::json!({
+            "id": "msg_1", "type": "message", "role": "assistant",
+            "content": [{"type": "text", "text": "Hello."}],
+            "model": "claude-mock", "stop_reason": "end_turn", "stop_sequence": null,
+            "usage": {"input_tokens": 10, "output_tokens": 3},
+        })),
+    );
+    let response = anthropic(&server)
+        .messages(messages_request())
+        .await
+        .unwrap();
+    assert_eq!(response.text(), "Hello.");
+    assert_eq!(response.usage.output_tokens, 3);
+}
+
+const RESPONSES: &str = "v1/responses";
+
+fn responses_request() -> openai::responses::Request {
+    openai::responses::Request::builder()
+        .model(ModelId(chunk::MODEL.into()))
+        .input(vec![openai::responses::InputItem::message(
+            openai::Role::User,
+            "こんにちは",
+        )])
+        .previous_response_id("resp_0".into())
+        .build()
+}
+
+fn response_object(status: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": "resp_1", "object": "response", "status": status, "model": chunk::MODEL,
+        "output": [], "error": null, "previous_response_id": "resp_0",
+        "usage": {"input_tokens": 10, "output_tokens": 2, "total_tokens": 12},
+    })
+}
+
+#[tokio::test]
+async fn responses_stream_yields_typed_events() {
+    use openai::responses::StreamEvent;
+    use serde_json::json;
+
+    let server = MockServer::start().await;
+    server.enqueue(
+        RESPONSES,
+        MockResponse::events([
+            typed_event(json!({"type": "response.created",
+                "response": response_object("in_progress")})),
+            typed_event(
+                json!({"type": "response.output_item.added", "output_index": 0,
+                "item": {"id": "msg_1", "type": "message", "status": "in_progress",
+                         "role": "assistant", "content": []}}),
+            ),
+            typed_event(
+                json!({"type": "response.output_text.delta", "item_id": "msg_1",
+                "output_index": 0, "content_index": 0, "delta": "Hello"}),
+            ),
+            typed_event(json!({"type": "response.completed",
+                "response": response_object("completed")})),
+            // Anything after the terminal event is ignored.
+            typed_event(
+                json!({"type": "response.output_text.delta", "item_id": "msg_1",
+                "output_index": 0, "content_index": 0, "delta": "!"}),
+            ),
+        ]),
+    );
+    let stream = client(&server)
+        .responses_stream(responses_request())
+        .await
+        .unwrap();
+    let events: Vec<_> = stream.collect().await;
+    assert_eq!(events.len(), 4);
+    assert!(matches!(
+        &events[2],
+        Ok(StreamEvent::OutputTextDelta { delta, .. }) if delta == "Hello"
+    ));
+    let Ok(StreamEvent::Completed { response }) = &events[3] else {
+        panic!("unexpected event: {:?}", events[3]);
+    };
+    assert_eq!(response.id, "resp_1");
+    assert_eq!(response.usage.as_ref().unwrap().total_tokens, 12);
+
+    let body = server.requests_to(RESPONSES)[0].json();
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["previous_response_id"], "resp_0");
+    assert_eq!(body["input"][0]["content"], "こんにちは");
+}
+
+#[tokio::test]
+async fn responses_stream_surfaces_failed_response() {
+    use serde_json::json;
+
+    let mut failed = response_object("failed");
+    failed["error"] = json!({"code": "server_error", "message": "The model failed."});
+    let server = MockServer::start().await;
+    server.enqueue(
+        RESPONSES,
+        MockResponse::events([
+            typed_event(json!({"type": "response.created",
+                "response": response_object("in_progress")})),
+            typed_event(json!({"type": "response.failed", "response": failed})),
+        ]),
+    );
+    let stream = client(&server)
+        .responses_stream(responses_request())
+        .await
+        .unwrap();
+    let events: Vec<_> = stream.collect().await;
+    assert_eq!(events.len(), 2);
+    match &events[1] {
+        Err(openai::Error::Protocol(err)) => assert_eq!(err.message, "The model failed."),
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn responses_surfaces_unknown_previous_response() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        RESPONSES,
+        MockResponse::error(
+            400,
+            "invalid_request_error",
+            "Previous response with id 'resp_0' not found.",
+        ),
+    );
+    let result = client(&server).responses(responses_request()).await;
+    match result {
+        Err(openai::Error::Protocol(err)) => {
+            assert_eq!(err.error_type, "invalid_request_error")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+const SPEECH: &str = "v1/audio/speech";
+
+fn speech_request() -> openai::audio::SpeechRequest {
+    openai::audio::SpeechRequest::builder()
+        .model(ModelId("tts-mock".into()))
+        .input("こんにちは".into())
+        .voice("alloy".into())
+        .response_format(openai::audio::AudioFormat::Wav)
+        .build()
+}
+
+#[tokio::test]
+async fn speech_returns_audio_and_sends_request() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        SPEECH,
+        MockResponse::bytes("audio/wav", b"RIFF\0\xff".to_vec()),
+    );
+    let audio = client(&server).speech(speech_request()).await.unwrap();
+    assert_eq!(audio, b"RIFF\0\xff");
+    let body = server.requests_to(SPEECH)[0].json();
+    assert_eq!(body["input"], "こんにちは");
+    assert_eq!(body["voice"], "alloy");
+    assert_eq!(body["response_format"], "wav");
+    assert!(body.get("speed").is_none());
+}
+
+#[tokio::test]
+async fn speech_surfaces_application_error() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        SPEECH,
+        MockResponse::error(400, "invalid_request_error", "Unknown voice"),
+    );
+    match client(&server).speech(speech_request()).await {
+        Err(openai::Error::Protocol(err)) => assert_eq!(err.message, "Unknown voice"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}